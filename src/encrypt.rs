@@ -0,0 +1,83 @@
+//! Passphrase encryption of backup archives in the age format (<https://age-encryption.org>),
+//! so encrypted backups can also be opened with the `age` command line tool.
+
+use std::io::{self, BufReader, Read, Write};
+use std::iter;
+
+use age::secrecy::{ExposeSecret, SecretString};
+
+pub fn encrypt(mut input: impl Read, output: impl Write, passphrase: &SecretString) -> io::Result<()> {
+    let encryptor = age::Encryptor::with_user_passphrase(copy_secret(passphrase));
+    let mut writer = encryptor.wrap_output(output)?;
+    io::copy(&mut input, &mut writer)?;
+    // The final chunk is only written on finish, skipping it leaves a truncated file
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn decrypt(input: impl Read, mut output: impl Write, passphrase: &SecretString) -> io::Result<()> {
+    let decryptor = age::Decryptor::new_buffered(BufReader::new(input)).map_err(decrypt_error)?;
+    if !decryptor.is_scrypt() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the archive is encrypted to a key, not a passphrase",
+        ));
+    }
+    let identity = age::scrypt::Identity::new(copy_secret(passphrase));
+    let mut reader = decryptor
+        .decrypt(iter::once(&identity as &dyn age::Identity))
+        .map_err(decrypt_error)?;
+    io::copy(&mut reader, &mut output)?;
+    Ok(())
+}
+
+fn copy_secret(passphrase: &SecretString) -> SecretString {
+    SecretString::from(passphrase.expose_secret().to_owned())
+}
+
+fn decrypt_error(error: age::DecryptError) -> io::Error {
+    match error {
+        age::DecryptError::Io(e) => e,
+        age::DecryptError::DecryptionFailed | age::DecryptError::NoMatchingKeys => io::Error::new(
+            io::ErrorKind::InvalidData,
+            "wrong passphrase or damaged archive",
+        ),
+        e => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let passphrase = SecretString::from("correct horse battery staple".to_string());
+        let archive = b"not really a tar.gz, but any bytes will do".repeat(2000);
+
+        let mut encrypted = Vec::new();
+        encrypt(&archive[..], &mut encrypted, &passphrase).unwrap();
+        assert!(encrypted.starts_with(b"age-encryption.org/v1"));
+        assert!(!encrypted.windows(16).any(|w| w == &archive[..16]));
+
+        let mut decrypted = Vec::new();
+        decrypt(&encrypted[..], &mut decrypted, &passphrase).unwrap();
+        assert_eq!(decrypted, archive);
+    }
+
+    #[test]
+    fn wrong_passphrase_or_damage_is_an_error() {
+        let passphrase = SecretString::from("right".to_string());
+        let mut encrypted = Vec::new();
+        encrypt(&b"world data"[..], &mut encrypted, &passphrase).unwrap();
+
+        let wrong = SecretString::from("wrong".to_string());
+        let error = decrypt(&encrypted[..], io::sink(), &wrong).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // Cut off before the final chunk
+        let truncated = &encrypted[..encrypted.len() - 8];
+        assert!(decrypt(truncated, io::sink(), &passphrase).is_err());
+        assert!(decrypt(&b"plain tar.gz"[..], io::sink(), &passphrase).is_err());
+    }
+}
@@ -1,14 +1,26 @@
-use clap::{Parser, Subcommand};
+mod encrypt;
+mod nbt;
+mod ping;
+mod region;
+mod secrets;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use colored::*;
-use dialoguer::{Confirm, Input, Select};
-use indicatif::{ProgressBar, ProgressStyle};
+use age::secrecy::SecretString;
+use dialoguer::{Confirm, Input, Password, Select};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle, TermLike};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs;
-use std::io::{self, Write};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::net::{IpAddr, TcpListener};
 use std::path::{Path, PathBuf};
-use std::process::{Command as ProcessCommand, Stdio};
-use std::time::Duration;
+use std::process::{Child, Command as ProcessCommand, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -25,14 +37,174 @@ pub enum ServerError {
     ConfigParse(#[from] serde_json::Error),
     #[error("Failed to parse yaml: {0}")]
     YamlError(#[from] serde_yaml::Error),
+    #[error("Failed to parse settings: {0}")]
+    SettingsParse(#[from] toml::de::Error),
+    #[error("Failed to write settings: {0}")]
+    SettingsWrite(#[from] toml::ser::Error),
     #[error("Invalid server name: {0}")]
     InvalidServerName(String),
+    #[error("Invalid port: {0}")]
+    InvalidPort(String),
+    #[error("Port {0} is already used by server '{1}'")]
+    PortConflict(String, String),
+    #[error("Port {0} is already in use on this host")]
+    PortUnavailable(String),
+    #[error("Port {0} is reserved, run 'ports release {0}' to use it for a server")]
+    PortReserved(String),
+    #[error("Invalid server type: {0}")]
+    InvalidServerType(String),
+    #[error("Unknown key: {0}")]
+    UnknownKey(String),
+    #[error("Invalid value for '{0}': {1}")]
+    InvalidValue(String, String),
+    #[error("No terminal to prompt on, {0}")]
+    NotInteractive(String),
+    #[error("Secret '{0}' is kept in the OS keyring, which {1}")]
+    SecretUnavailable(String, String),
     #[error("Docker command failed: {0}")]
     DockerCommandFailed(String),
+    #[error("Permission denied connecting to the Docker daemon. Add your user to the 'docker' group with 'sudo usermod -aG docker $USER', then log out and back in")]
+    DockerPermissionDenied,
+    #[error("Docker is installed but the daemon isn't reachable: {0}")]
+    DockerDaemonUnavailable(String),
+    #[error("RCON command failed: {0}")]
+    RconFailed(String),
+    #[error("Server '{0}' is not running")]
+    ServerNotRunning(String),
+    #[error("Server '{0}' is running, stop it first")]
+    ServerRunning(String),
+    #[error("Server '{0}' is archived, run 'unarchive {0}' first")]
+    ServerArchived(String),
+    #[error("Not enough free space in {0}: {} needed, {} available", format_size(*.1), format_size(*.2))]
+    InsufficientSpace(String, u64, u64),
+    #[error("Timed out after {0}s waiting for {1} (last seen: {2})")]
+    Timeout(u64, String, String),
+    #[error("Server '{0}' crashed during startup: {1}")]
+    StartupCrashed(String, String),
+    #[error("{1} hook of server '{0}' failed: {2}")]
+    HookFailed(String, String, String),
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
+    #[error("Archive error: {0}")]
+    Archive(#[from] zip::result::ZipError),
+    #[error("Environment variable '{0}' is not set on the host")]
+    HostEnvMissing(String),
+    #[error("Invalid backup archive: {0}")]
+    InvalidBackup(String),
     #[error("Dialog error: {0}")]
     DialogError(#[from] dialoguer::Error),
 }
 
+impl ServerError {
+    /// Stable variant name for machine-readable output
+    fn kind(&self) -> &'static str {
+        match self {
+            ServerError::Io(_) => "Io",
+            ServerError::ServerNotFound(_) => "ServerNotFound",
+            ServerError::ServerExists(_) => "ServerExists",
+            ServerError::DockerNotInstalled => "DockerNotInstalled",
+            ServerError::ConfigParse(_) => "ConfigParse",
+            ServerError::YamlError(_) => "YamlError",
+            ServerError::SettingsParse(_) => "SettingsParse",
+            ServerError::SettingsWrite(_) => "SettingsWrite",
+            ServerError::InvalidServerName(_) => "InvalidServerName",
+            ServerError::InvalidPort(_) => "InvalidPort",
+            ServerError::PortConflict(..) => "PortConflict",
+            ServerError::PortUnavailable(_) => "PortUnavailable",
+            ServerError::PortReserved(_) => "PortReserved",
+            ServerError::InvalidServerType(_) => "InvalidServerType",
+            ServerError::UnknownKey(_) => "UnknownKey",
+            ServerError::InvalidValue(..) => "InvalidValue",
+            ServerError::NotInteractive(_) => "NotInteractive",
+            ServerError::SecretUnavailable(..) => "SecretUnavailable",
+            ServerError::DockerCommandFailed(_) => "DockerCommandFailed",
+            ServerError::DockerPermissionDenied => "DockerPermissionDenied",
+            ServerError::DockerDaemonUnavailable(_) => "DockerDaemonUnavailable",
+            ServerError::RconFailed(_) => "RconFailed",
+            ServerError::ServerNotRunning(_) => "ServerNotRunning",
+            ServerError::ServerRunning(_) => "ServerRunning",
+            ServerError::ServerArchived(_) => "ServerArchived",
+            ServerError::InsufficientSpace(..) => "InsufficientSpace",
+            ServerError::Timeout(..) => "Timeout",
+            ServerError::StartupCrashed(..) => "StartupCrashed",
+            ServerError::HookFailed(..) => "HookFailed",
+            ServerError::InvalidPattern(_) => "InvalidPattern",
+            ServerError::Archive(_) => "Archive",
+            ServerError::HostEnvMissing(_) => "HostEnvMissing",
+            ServerError::InvalidBackup(_) => "InvalidBackup",
+            ServerError::DialogError(_) => "DialogError",
+        }
+    }
+
+    /// Server the error is about, when there is one
+    fn server(&self) -> Option<&str> {
+        match self {
+            ServerError::ServerNotFound(name)
+            | ServerError::ServerExists(name)
+            | ServerError::ServerNotRunning(name)
+            | ServerError::ServerRunning(name)
+            | ServerError::ServerArchived(name)
+            | ServerError::StartupCrashed(name, _)
+            | ServerError::HookFailed(name, ..)
+            | ServerError::PortConflict(_, name) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "error": self.to_string(),
+            "kind": self.kind(),
+            "exit_code": self.exit_code(),
+        });
+        if let Some(server) = self.server() {
+            value["server"] = server.into();
+        }
+        value
+    }
+
+    /// Process exit code, so scripts can tell outcomes apart. Listed in the README; 2 matches
+    /// what clap exits with for bad arguments.
+    fn exit_code(&self) -> i32 {
+        match self {
+            ServerError::InvalidServerName(_)
+            | ServerError::InvalidPort(_)
+            | ServerError::InvalidServerType(_)
+            | ServerError::UnknownKey(_)
+            | ServerError::InvalidValue(..)
+            | ServerError::NotInteractive(_)
+            | ServerError::InvalidPattern(_)
+            | ServerError::HostEnvMissing(_) => 2,
+            ServerError::ServerNotFound(_) => 3,
+            ServerError::DockerNotInstalled
+            | ServerError::DockerCommandFailed(_)
+            | ServerError::DockerPermissionDenied
+            | ServerError::DockerDaemonUnavailable(_) => 4,
+            ServerError::Timeout(..) => 5,
+            ServerError::StartupCrashed(..) => 6,
+            ServerError::ServerExists(_)
+            | ServerError::ServerRunning(_)
+            | ServerError::ServerNotRunning(_)
+            | ServerError::ServerArchived(_)
+            | ServerError::PortConflict(..)
+            | ServerError::PortUnavailable(_)
+            | ServerError::PortReserved(_) => 7,
+            ServerError::RconFailed(_) => 8,
+            ServerError::HookFailed(..) => 9,
+            ServerError::InsufficientSpace(..) => 10,
+            ServerError::InvalidBackup(_) => 11,
+            ServerError::Io(_)
+            | ServerError::ConfigParse(_)
+            | ServerError::YamlError(_)
+            | ServerError::SettingsParse(_)
+            | ServerError::SettingsWrite(_)
+            | ServerError::Archive(_)
+            | ServerError::SecretUnavailable(..)
+            | ServerError::DialogError(_) => 1,
+        }
+    }
+}
+
 type Result<T> = std::result::Result<T, ServerError>;
 
 #[derive(Parser)]
@@ -43,6 +215,24 @@ type Result<T> = std::result::Result<T, ServerError>;
     author = "Tristan J. Poland"
 )]
 struct Cli {
+    /// Machine-readable output: errors are printed to stderr as JSON objects
+    #[arg(long, global = true)]
+    json: bool,
+    /// Log-friendly output for cron and systemd timers: no banner, colors or spinners, and
+    /// timestamped lines for each step and the result
+    #[arg(long, global = true, conflicts_with = "json")]
+    cron: bool,
+    /// Timeout for every blocking wait (--wait, --wait-healthy, --drain, logs --follow-until and
+    /// --wait-ready, pregen), overriding their defaults. Timing out exits with code 5.
+    #[arg(long, global = true, value_name = "SECS")]
+    wait_timeout: Option<u64>,
+    /// Plain output without colors, same as setting NO_COLOR
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Save secrets this command writes (env values set with it, the webhook URL, managed backup
+    /// RCON passwords, backup passphrases) in the OS keyring instead of BlockOps' files
+    #[arg(long, global = true)]
+    use_keyring: bool,
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -50,26 +240,145 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Create a new Minecraft server
-    Create,
+    Create {
+        /// Start the server once it's created
+        #[arg(long, conflicts_with = "no_start")]
+        start: bool,
+        /// Don't start the server or ask whether to
+        #[arg(long)]
+        no_start: bool,
+        #[command(flatten)]
+        options: CreateOptions,
+    },
+    /// Create several identical servers with sequential ports
+    BatchCreate {
+        /// Base name, servers are named <base_name>1 to <base_name>N
+        base_name: String,
+        /// Number of servers to create
+        count: usize,
+        /// Port of the first server, incremented for each following server
+        start_port: u16,
+        /// Server type (VANILLA, PAPER, FORGE, FABRIC, SPIGOT, PURPUR)
+        #[arg(short = 't', long)]
+        server_type: Option<String>,
+        /// Minecraft version
+        #[arg(short, long)]
+        version: Option<String>,
+        /// Server memory, absolute (4G) or a percentage of host RAM (50%)
+        #[arg(short, long)]
+        memory: Option<String>,
+        /// Mod loader version for FORGE and FABRIC servers
+        #[arg(long)]
+        loader_version: Option<String>,
+        /// Custom Java arguments
+        #[arg(long)]
+        java_args: Option<String>,
+        /// Accept the Minecraft EULA without prompting
+        #[arg(long)]
+        accept_eula: bool,
+        #[command(flatten)]
+        options: CreateOptions,
+    },
     /// List all servers
-    List,
+    List {
+        #[command(flatten)]
+        options: ListOptions,
+    },
+    /// Overview of every server: status, players, memory, disk use and backups
+    Summary,
     /// Start specific server(s)
     Start {
         /// Server name (optional, starts all if not specified)
         name: Option<String>,
+        /// Maximum number of servers started at once (defaults to the CPU count)
+        #[arg(long, value_name = "N")]
+        parallel: Option<usize>,
+        /// Also start this companion service, e.g. bluemap (repeatable)
+        #[arg(long = "profile", value_name = "PROFILE")]
+        profiles: Vec<String>,
+        #[command(flatten)]
+        wait: WaitOptions,
+        /// Start even if a before_start hook fails
+        #[arg(long)]
+        ignore_hook_errors: bool,
+        /// Recreate the container even if its configuration looks unchanged
+        #[arg(long, conflicts_with = "no_recreate")]
+        force_recreate: bool,
+        /// Keep an existing container even if its configuration changed
+        #[arg(long)]
+        no_recreate: bool,
+        /// Only print failures and a final count instead of a line per server
+        #[arg(long)]
+        only_errors: bool,
+        /// Extra arguments passed to 'docker-compose up' after its own, e.g. -- --build
+        #[arg(last = true, value_name = "COMPOSE_ARGS")]
+        compose_args: Vec<String>,
     },
     /// Stop specific server(s)
     Stop {
         /// Server name (optional, stops all if not specified)
         name: Option<String>,
+        /// Maximum number of servers stopped at once (defaults to the CPU count)
+        #[arg(long, value_name = "N")]
+        parallel: Option<usize>,
+        /// Don't fail when an after_stop hook does
+        #[arg(long)]
+        ignore_hook_errors: bool,
+        /// Also delete the containers' anonymous volumes (the data directory is kept)
+        #[arg(long)]
+        volumes: bool,
+        /// Also remove containers of services no longer in the compose file
+        #[arg(long)]
+        remove_orphans: bool,
+        /// Only print failures and a final count instead of a line per server
+        #[arg(long)]
+        only_errors: bool,
+        #[command(flatten)]
+        drain: DrainOptions,
+        /// Extra arguments passed to 'docker-compose down' after its own, e.g. -- --timeout 300
+        #[arg(last = true, value_name = "COMPOSE_ARGS")]
+        compose_args: Vec<String>,
+    },
+    /// Restart running server(s), waiting until each is ready again
+    Restart {
+        /// Server name (optional, restarts every running server if not specified)
+        name: Option<String>,
+        /// Restart one server at a time, so the others stay up while each comes back
+        #[arg(long)]
+        rolling: bool,
+        /// Only restart servers with this tag
+        #[arg(long, conflicts_with = "name")]
+        tag: Option<String>,
+        /// Seconds players are warned in chat before a server restarts, 0 to restart right away
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_RESTART_WARNING)]
+        warning: u64,
+        /// Wait until the container's healthcheck reports healthy instead of watching the logs
+        #[arg(long)]
+        wait_healthy: bool,
+        /// Regex marking the server as ready, for modded or proxy servers with a different "Done" line
+        #[arg(long, value_name = "PATTERN", conflicts_with = "wait_healthy")]
+        ready_pattern: Option<String>,
+        /// Give up waiting for a server after this many seconds (default 300); later servers of a
+        /// rolling restart are left running
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
     },
     /// Show server logs
     Logs {
         /// Server name
         name: String,
-        /// Follow logs in real-time
-        #[arg(short, long)]
-        follow: bool,
+        #[command(flatten)]
+        options: LogOptions,
+    },
+    /// Stop a server and pack its data directory into a single archive, hiding it from 'list'
+    Archive {
+        /// Server name
+        name: String,
+    },
+    /// Unpack an archived server's data directory so it can be started again
+    Unarchive {
+        /// Server name
+        name: String,
     },
     /// Remove a server
     Remove {
@@ -78,659 +387,9339 @@ enum Commands {
         /// Force removal without confirmation
         #[arg(short, long)]
         force: bool,
+        /// Skip the backup that's normally taken before the data is deleted
+        #[arg(long)]
+        no_backup: bool,
+        /// Only stop managing the server: remove its container and config entry, leave its files
+        #[arg(long, conflicts_with = "no_backup")]
+        keep_data: bool,
+    },
+    /// Recreate a server's container and compose file from its config, keeping the world
+    Reinstall {
+        /// Server name
+        name: String,
+        /// Back up the server data first
+        #[arg(long)]
+        backup_before: bool,
+    },
+    /// Show, enable or disable a server's web map
+    Map {
+        /// Server name
+        name: String,
+        /// Map renderer to use
+        #[arg(long, value_enum, default_value_t = Companion::Bluemap)]
+        kind: Companion,
+        /// Add the map service to the server
+        #[arg(long, conflicts_with = "disable")]
+        enable: bool,
+        /// Remove the map service from the server
+        #[arg(long)]
+        disable: bool,
+        /// Host port the map is served on
+        #[arg(long, conflicts_with = "disable")]
+        port: Option<u16>,
+    },
+    /// Find servers whose files are gone and containers without a configured server
+    #[command(alias = "prune-stopped")]
+    Reconcile,
+    /// Run a server's healthcheck now and show its recent health log
+    Health {
+        /// Server name
+        name: String,
+    },
+    /// Look for common problems with a server: Java version, data file ownership
+    Doctor {
+        /// Server name
+        name: String,
+        /// Offer to chown the data directory to the user the container runs as
+        #[arg(long)]
+        fix_permissions: bool,
+        /// Fix without asking for confirmation
+        #[arg(short, long, requires = "fix_permissions")]
+        force: bool,
+    },
+    /// Check that each running server's image has a Java new enough for its Minecraft version
+    VersionCheck {
+        /// Server name (all running servers when omitted)
+        name: Option<String>,
+    },
+    /// Print a server's docker-compose.yml
+    ShowCompose {
+        /// Server name
+        name: String,
+        /// Print what would be generated from the stored config instead of the file on disk
+        #[arg(long)]
+        regenerate: bool,
+    },
+    /// Compare the settings and container environments of two servers
+    Diff {
+        /// First server
+        a: String,
+        /// Second server
+        b: String,
     },
     /// Attach to server console
     Console {
         /// Server name
         name: String,
+        /// Key sequence that detaches from the console, in docker's format
+        #[arg(long, value_name = "KEYS", default_value = DEFAULT_DETACH_KEYS)]
+        detach_keys: String,
+        /// Append a transcript of the session (commands and responses) to this file
+        #[arg(long, value_name = "PATH")]
+        tee: Option<PathBuf>,
+    },
+    /// Run a command inside a server's container
+    Exec {
+        /// Server name
+        name: String,
+        /// Command and arguments to run, e.g. `ls -la /data`
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
     },
     /// List available versions and types
     Versions,
+    /// Show the environment the tool runs in (versions, Docker host, config dir), for bug reports
+    #[command(alias = "whoami")]
+    Context,
     /// Backup server data
     Backup {
         /// Server name
-        name: String,
+        #[arg(required_unless_present = "all")]
+        name: Option<String>,
+        /// Back up every server
+        #[arg(long, conflicts_with = "name")]
+        all: bool,
+        /// Maximum number of backups running at once with --all (defaults to the CPU count)
+        #[arg(long, value_name = "N", requires = "all")]
+        parallel: Option<usize>,
+        /// With --all, skip servers whose data hasn't changed since their last backup
+        #[arg(long, requires = "all")]
+        since_last_backup: bool,
+        /// Report the expected archive size and duration instead of creating a backup
+        #[arg(long)]
+        estimate: bool,
+        #[command(flatten)]
+        options: BackupOptions,
     },
     /// Restore server from backup
     Restore {
         /// Server name
         name: String,
-        /// Backup file path
-        path: PathBuf,
+        /// Backup file path (picked from the server's backups when omitted)
+        path: Option<PathBuf>,
+        /// Back up the current data before it's overwritten
+        #[arg(long)]
+        backup_before: bool,
+    },
+    /// List a server's backups with their labels
+    Backups {
+        /// Server name
+        name: String,
+    },
+    /// Print a server setting (e.g. memory, port, tags, env.DIFFICULTY)
+    Get {
+        /// Server name
+        name: String,
+        /// Setting key
+        key: String,
+    },
+    /// Change a server setting (an empty value clears optional settings)
+    Set {
+        /// Server name
+        name: String,
+        /// Setting key
+        key: String,
+        /// New value
+        value: String,
+        /// Apply without showing the change and confirming it, skip the confirmation for large
+        /// version jumps (a backup is still taken), and keep a mod loader version that doesn't
+        /// match the Minecraft version
+        #[arg(short, long)]
+        force: bool,
+        /// Show how the stored config and compose file would change, without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Send an RCON command to a running server
+    Rcon {
+        /// Server name
+        name: String,
+        /// Command to send
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required_unless_present_any = ["history", "repeat"])]
+        command: Vec<String>,
+        /// List recently sent commands
+        #[arg(long, conflicts_with_all = ["command", "repeat"])]
+        history: bool,
+        /// Re-send a command from the history (1 is the most recent)
+        #[arg(long, value_name = "N", conflicts_with = "command")]
+        repeat: Option<usize>,
+    },
+    /// Check a stopped server's region files for corruption
+    Verify {
+        /// Server name
+        name: String,
+    },
+    /// Query a server the way a Minecraft client does (Server List Ping)
+    Ping {
+        /// Server name
+        name: String,
+        /// Address to ping as host or host:port (defaults to localhost on the server's port)
+        address: Option<String>,
+    },
+    /// Show ticks per second and milliseconds per tick (Paper, Purpur, Spigot, Forge, or Spark)
+    Tps {
+        /// Server name
+        name: String,
+    },
+    /// Back up, then delete one dimension so it regenerates
+    ResetDimension {
+        /// Server name
+        name: String,
+        /// Dimension to reset
+        #[arg(value_enum)]
+        dimension: Dimension,
+        /// World to reset, for servers with several worlds (defaults to the main world)
+        #[arg(long)]
+        world: Option<String>,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Edit whitelist.json while the server is stopped, looking players up by name
+    Whitelist {
+        /// Server name
+        name: String,
+        #[command(subcommand)]
+        action: AccessAction,
+    },
+    /// Edit ops.json while the server is stopped, looking players up by name (added at level 4)
+    Ops {
+        /// Server name
+        name: String,
+        #[command(subcommand)]
+        action: AccessAction,
+    },
+    /// Inspect or reset a player's saved data
+    Player {
+        /// Server name
+        name: String,
+        #[command(subcommand)]
+        action: PlayerAction,
+    },
+    /// Collect a diagnostic zip (config, logs, crash reports) with secrets redacted
+    Bundle {
+        /// Server name
+        name: String,
+        /// Number of log lines to include
+        #[arg(long, default_value_t = 500)]
+        lines: usize,
+        /// Output zip path (defaults to <name>-bundle-<timestamp>.zip in the current directory)
+        #[arg(long = "to", value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Watch a server's status and player count
+    Monitor {
+        /// Server name
+        name: String,
+        /// Seconds between samples
+        #[arg(long, default_value_t = 60)]
+        interval: u64,
+        /// Record which players are online for the activity leaderboard
+        #[arg(long)]
+        track_activity: bool,
+    },
+    /// Show the playtime leaderboard recorded by 'monitor --track-activity'
+    Activity {
+        /// Server name
+        name: String,
+        /// Number of players to show
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Check the chat webhook set with 'settings set notifications.webhook_url <url>'
+    Notify {
+        /// Post a test message and report whether it went through
+        #[arg(long)]
+        test: bool,
+    },
+    /// Show or change defaults used for new servers
+    Settings {
+        #[command(subcommand)]
+        action: SettingsAction,
+    },
+    /// Keep host ports free for other services, so no server is created on them
+    Ports {
+        #[command(subcommand)]
+        action: PortsAction,
+    },
+    /// Pregenerate world chunks around spawn (Paper and Purpur only)
+    Pregen {
+        /// Server name
+        name: String,
+        /// Radius in blocks
+        radius: u32,
+    },
+    /// Include a server in bulk start again
+    Enable {
+        /// Server name
+        name: String,
+    },
+    /// Leave a server out of bulk start; 'start <name>' still starts it
+    Disable {
+        /// Server name
+        name: String,
+    },
+    /// Mirror a server's data directory to or from another host with rsync
+    Sync {
+        /// Server name
+        name: String,
+        /// rsync destination such as user@host:/srv/minecraft/survival (defaults to the last one used)
+        target: Option<String>,
+        /// Copy from the target into this server instead, replacing its data
+        #[arg(long)]
+        pull: bool,
+        /// Show what would be transferred without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move servers, backups and history from an older version's .mc-servers directory into ~/.mc-servers
+    Migrate {
+        /// The old .mc-servers directory, or the directory containing it (defaults to the current directory)
+        from: Option<PathBuf>,
+    },
+    /// List, set or clear the shell hooks run around a server's start and stop
+    Hooks {
+        /// Server name
+        name: String,
+        /// Shell command run before the server starts (empty to remove)
+        #[arg(long, value_name = "CMD")]
+        before_start: Option<String>,
+        /// Shell command run after the server stops (empty to remove)
+        #[arg(long, value_name = "CMD")]
+        after_stop: Option<String>,
+        /// Remove all hooks
+        #[arg(long, conflicts_with_all = ["before_start", "after_stop"])]
+        clear: bool,
+    },
+    /// Show a server's server.properties, or merge another server.properties file into it
+    Properties {
+        /// Server name
+        name: String,
+        /// server.properties file to merge in (the server is stopped while it's written)
+        #[arg(long, value_name = "FILE")]
+        import: Option<PathBuf>,
     },
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct ServerConfig {
-    servers: HashMap<String, ServerInfo>,
+/// Optional services added to a server's compose file behind a profile of the same name
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Companion {
+    /// BlueMap 3D web map, rendered from the world files
+    Bluemap,
+    /// Web server for the Dynmap plugin's standalone map files
+    Dynmap,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct ServerInfo {
-    version: String,
-    port: String,
-    memory: String,
-    data_path: String,
-    server_type: String,
-    mod_loader: Option<String>,
-    mod_loader_version: Option<String>,
-    java_args: Option<String>,
-    created_at: chrono::DateTime<chrono::Utc>,
-    last_started: Option<chrono::DateTime<chrono::Utc>>,
-}
+impl Companion {
+    fn profile(self) -> &'static str {
+        match self {
+            Companion::Bluemap => "bluemap",
+            Companion::Dynmap => "dynmap",
+        }
+    }
 
-#[derive(Serialize, Deserialize)]
-struct ComposeConfig {
-    version: String,
-    services: HashMap<String, MinecraftService>,
+    fn default_port(self) -> u16 {
+        match self {
+            Companion::Bluemap => 8100,
+            Companion::Dynmap => 8123,
+        }
+    }
+
+    fn service(self, name: &str, info: &ServerInfo) -> MinecraftService {
+        let data_path = info.compose_data_path();
+        let (image, container_port, command, volumes) = match self {
+            Companion::Bluemap => (
+                "ghcr.io/bluemap-minecraft/bluemap:latest",
+                8100,
+                vec!["-r".to_string(), "-u".to_string(), "-w".to_string()],
+                vec![
+                    format!("{}/world:/app/world:ro", data_path),
+                    format!("{}/bluemap/config:/app/config", data_path),
+                    format!("{}/bluemap/data:/app/data", data_path),
+                    format!("{}/bluemap/web:/app/web", data_path),
+                ],
+            ),
+            Companion::Dynmap => (
+                "nginx:alpine",
+                80,
+                Vec::new(),
+                vec![format!("{}/plugins/dynmap/web:/usr/share/nginx/html:ro", data_path)],
+            ),
+        };
+        MinecraftService {
+            image: image.to_string(),
+            container_name: format!("mc-{}-{}", name, self.profile()),
+            ports: vec![format!("{}:{}", info.companion_port(self), container_port)],
+            environment: Vec::new(),
+            volumes,
+            restart: info.restart_policy.clone().unwrap_or_else(|| DEFAULT_RESTART_POLICY.to_string()),
+            stdin_open: false,
+            tty: false,
+            read_only: false,
+            tmpfs: Vec::new(),
+            command,
+            profiles: vec![self.profile().to_string()],
+            logging: Some(info.logging()),
+            healthcheck: None,
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
-struct MinecraftService {
-    image: String,
-    container_name: String,
-    ports: Vec<String>,
-    environment: Vec<String>,
-    volumes: Vec<String>,
-    restart: String,
-    stdin_open: bool,
-    tty: bool,
+/// JVM and world settings presets, expanded into the compose file from the server's memory and
+/// the host's CPU count each time it's written
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Tuning {
+    /// Aikar's G1 flags and default view distance, a good fit for most servers
+    Balanced,
+    /// Tight G1 settings and shorter view distances for servers with little RAM
+    Memory,
+    /// Parallel GC across all cores for throughput on big machines
+    Cpu,
 }
 
-const CONFIG_DIR: &str = ".mc-servers";
-const CONFIG_FILE: &str = "servers.json";
-const BACKUP_DIR: &str = "backups";
+impl Tuning {
+    fn name(self) -> &'static str {
+        match self {
+            Tuning::Balanced => "balanced",
+            Tuning::Memory => "memory",
+            Tuning::Cpu => "cpu",
+        }
+    }
 
-fn main() -> Result<()> {
-    print_banner();
-    let cli = Cli::parse();
+    /// Environment variables for the image, as (key, value) pairs
+    fn environment(self, memory: &str) -> Vec<(&'static str, String)> {
+        let gib = memory_bytes(memory).unwrap_or(0) / (1024 * 1024 * 1024);
+        let cpus = default_parallelism();
+        let (view, simulation) = match self {
+            Tuning::Balanced => (10, 10),
+            Tuning::Memory if gib <= 2 => (6, 4),
+            Tuning::Memory => (8, 6),
+            Tuning::Cpu if gib >= 8 => (12, 10),
+            Tuning::Cpu => (10, 8),
+        };
+        let mut environment = vec![
+            ("VIEW_DISTANCE", view.to_string()),
+            ("SIMULATION_DISTANCE", simulation.to_string()),
+        ];
+        match self {
+            // The image picks Aikar's flags for the heap size itself
+            Tuning::Balanced => environment.push(("USE_AIKAR_FLAGS", "true".to_string())),
+            Tuning::Memory => environment.push((
+                "JVM_XX_OPTS",
+                "-XX:+UseG1GC -XX:MaxGCPauseMillis=100 -XX:G1HeapRegionSize=4M -XX:+UseStringDeduplication \
+                 -XX:+DisableExplicitGC -XX:MaxMetaspaceSize=256M"
+                    .to_string(),
+            )),
+            Tuning::Cpu => environment.push((
+                "JVM_XX_OPTS",
+                format!(
+                    "-XX:+UseParallelGC -XX:ParallelGCThreads={} -XX:+AlwaysPreTouch -XX:+DisableExplicitGC",
+                    cpus
+                ),
+            )),
+        }
+        environment
+    }
+}
 
-    ensure_config_dir()?;
+/// World generator of a newly created world (`level-type`)
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LevelType {
+    Normal,
+    /// Superflat, layers can be set with --generator-settings
+    Flat,
+    Amplified,
+    #[value(name = "large_biomes")]
+    LargeBiomes,
+    /// One biome everywhere (1.19+), picked with --generator-settings '{"biome":"minecraft:desert"}'
+    #[value(name = "single_biome")]
+    SingleBiome,
+}
 
-    if !check_docker_installed() {
-        install_docker()?;
+impl LevelType {
+    fn name(self) -> &'static str {
+        match self {
+            LevelType::Normal => "normal",
+            LevelType::Flat => "flat",
+            LevelType::Amplified => "amplified",
+            LevelType::LargeBiomes => "large_biomes",
+            LevelType::SingleBiome => "single_biome",
+        }
     }
 
-    match cli.command.unwrap_or(Commands::List) {
-        Commands::Create => create_server()?,
-        Commands::List => list_servers()?,
-        Commands::Start { name } => start_servers(name)?,
-        Commands::Stop { name } => stop_servers(name)?,
-        Commands::Logs { name, follow } => show_logs(&name, follow)?,
-        Commands::Remove { name, force } => remove_server(&name, force)?,
-        Commands::Console { name } => attach_console(&name)?,
-        Commands::Versions => list_versions(),
-        Commands::Backup { name } => backup_server(&name)?,
-        Commands::Restore { name, path } => restore_server(&name, &path)?,
+    /// The server.properties value for a Minecraft version: namespaced ids since 1.19, the old
+    /// upper-case names before. `None` when the version doesn't have this type.
+    fn property(self, version: &str) -> Option<&'static str> {
+        let namespaced = parse_mc_version(version).is_none_or(|v| v >= (1, 19, 0));
+        Some(match (self, namespaced) {
+            (LevelType::Normal, true) => "minecraft:normal",
+            (LevelType::Flat, true) => "minecraft:flat",
+            (LevelType::Amplified, true) => "minecraft:amplified",
+            (LevelType::LargeBiomes, true) => "minecraft:large_biomes",
+            (LevelType::SingleBiome, true) => "minecraft:single_biome_surface",
+            (LevelType::Normal, false) => "DEFAULT",
+            (LevelType::Flat, false) => "FLAT",
+            (LevelType::Amplified, false) => "AMPLIFIED",
+            (LevelType::LargeBiomes, false) => "LARGEBIOMES",
+            (LevelType::SingleBiome, false) => return None,
+        })
     }
 
-    Ok(())
+    /// Inverse of `property`, for compose files written by either form
+    fn from_property(value: &str) -> Option<LevelType> {
+        let value = value.trim_start_matches("minecraft:").to_lowercase();
+        Some(match value.as_str() {
+            "normal" | "default" => LevelType::Normal,
+            "flat" => LevelType::Flat,
+            "amplified" => LevelType::Amplified,
+            "large_biomes" | "largebiomes" => LevelType::LargeBiomes,
+            "single_biome_surface" => LevelType::SingleBiome,
+            _ => return None,
+        })
+    }
 }
 
-fn print_banner() {
-    println!("{}", r#"
-╔╦╗╔═╗  ╔═╗╔═╗╦═╗╦  ╦╔═╗╦═╗
-║║║║    ╚═╗║╣ ╠╦╝╚╗╔╝║╣ ╠╦╝
-╩ ╩╚═╝  ╚═╝╚═╝╩╚═ ╚╝ ╚═╝╩╚═
-    "#.bright_green());
-    println!("{}", "Production Minecraft Server Manager".bright_blue().bold());
-    println!("{}", "=================================".bright_blue());
+#[derive(Clone, Copy, ValueEnum)]
+enum Dimension {
+    Overworld,
+    Nether,
+    End,
 }
 
-fn ensure_config_dir() -> Result<()> {
-    let config_dir = Path::new(CONFIG_DIR);
-    if !config_dir.exists() {
-        fs::create_dir_all(config_dir)?;
-        fs::create_dir_all(config_dir.join(BACKUP_DIR))?;
-        save_server_config(&ServerConfig {
-            servers: HashMap::new(),
-        })?;
-    }
-    Ok(())
+#[derive(Subcommand)]
+enum PortsAction {
+    /// List reserved ports
+    List,
+    /// Reserve a port
+    Reserve {
+        port: u16,
+    },
+    /// Make a reserved port available to servers again
+    Release {
+        port: u16,
+    },
 }
 
-fn load_server_config() -> Result<ServerConfig> {
-    let config_path = Path::new(CONFIG_DIR).join(CONFIG_FILE);
-    if config_path.exists() {
-        let content = fs::read_to_string(config_path)?;
-        Ok(serde_json::from_str(&content)?)
-    } else {
-        Ok(ServerConfig {
-            servers: HashMap::new(),
-        })
-    }
+#[derive(Subcommand)]
+enum SettingsAction {
+    /// Print one setting, or all of them
+    Get {
+        /// Setting key (e.g. defaults.memory)
+        key: Option<String>,
+    },
+    /// Change a setting (an empty value resets it)
+    Set {
+        /// Setting key
+        key: String,
+        /// New value
+        value: String,
+    },
 }
 
-fn save_server_config(config: &ServerConfig) -> Result<()> {
-    let config_path = Path::new(CONFIG_DIR).join(CONFIG_FILE);
-    let content = serde_json::to_string_pretty(config)?;
-    fs::write(config_path, content)?;
-    Ok(())
+#[derive(Subcommand)]
+enum AccessAction {
+    /// Show the players in the file
+    List,
+    /// Add players by name
+    Add {
+        #[arg(required = true)]
+        players: Vec<String>,
+    },
+    /// Remove players by name or UUID
+    Remove {
+        #[arg(required = true)]
+        players: Vec<String>,
+    },
 }
 
-fn check_docker_installed() -> bool {
-    ProcessCommand::new("docker")
-        .arg("--version")
-        .output()
-        .is_ok()
+#[derive(Subcommand)]
+enum PlayerAction {
+    /// Show a player's position, health and inventory from their saved data
+    Inspect {
+        /// Player name (looked up in usercache.json) or UUID
+        player: String,
+    },
+    /// Back up and delete a player's inventory, stats and advancements; they start fresh on next join
+    Reset {
+        /// Player name (looked up in usercache.json) or UUID
+        player: String,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+    },
 }
 
-fn install_docker() -> Result<()> {
-    println!("{}", "\nDocker not found! Installing Docker...".yellow());
-    let pb = create_spinner("Installing Docker");
+#[derive(Args, Default)]
+struct BackupOptions {
+    /// Archive file or directory to write to (defaults to the backups directory); a directory with --all
+    #[arg(long = "to", value_name = "PATH")]
+    out: Option<PathBuf>,
+    /// Only archive this world (repeatable), with its nether and end folders and server.properties
+    #[arg(long = "world", value_name = "WORLD")]
+    worlds: Vec<String>,
+    /// Encrypt the archive with a passphrase (prompted, or from BLOCKOPS_BACKUP_PASSPHRASE)
+    #[arg(long)]
+    encrypt: bool,
+    /// Short note added to the archive name, e.g. "pre-mod-install"
+    #[arg(long, value_parser = parse_backup_label)]
+    label: Option<String>,
+    /// Write the archive to standard output, for piping elsewhere; messages go to stderr
+    #[arg(long, conflicts_with_all = ["out", "label", "all", "estimate"])]
+    stdout: bool,
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        pb.set_message("Installing Docker on Linux...");
-        let output = ProcessCommand::new("sh")
-            .arg("-c")
-            .arg("curl -fsSL https://get.docker.com | sh")
-            .output()?;
+/// Filters for `list`; several state flags show servers in any of those states
+#[derive(Args, Default)]
+struct ListOptions {
+    /// Only show running servers
+    #[arg(long)]
+    running: bool,
+    /// Only show stopped servers
+    #[arg(long)]
+    stopped: bool,
+    /// Only show paused servers
+    #[arg(long)]
+    paused: bool,
+    /// Order of the listing
+    #[arg(long, value_enum, default_value_t = ListSort::Name)]
+    sort: ListSort,
+    /// Reverse the order
+    #[arg(long)]
+    reverse: bool,
+    /// Include archived servers
+    #[arg(long)]
+    archived: bool,
+    /// Print one line per server from a format string, e.g. "{name}\t{status}\t{port}". Placeholders:
+    /// name, status, type, version, mod_loader, java, port, bind, memory, created, last_started,
+    /// description, tags, depends_on, enabled, archived. \t and \n are expanded, {{ and }} are braces.
+    #[arg(long, value_name = "FORMAT")]
+    template: Option<String>,
+}
 
-        if !output.status.success() {
-            return Err(ServerError::DockerCommandFailed(
-                String::from_utf8_lossy(&output.stderr).to_string(),
-            ));
+/// `list --sort` fields. Dates sort oldest first, memory smallest first and status running first;
+/// ties are broken by name.
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum ListSort {
+    #[default]
+    Name,
+    Created,
+    LastStarted,
+    Memory,
+    Status,
+}
+
+impl ListOptions {
+    fn matches(&self, state: ServerState) -> bool {
+        if !(self.running || self.stopped || self.paused) {
+            return true;
+        }
+        match state {
+            ServerState::Running => self.running,
+            ServerState::Stopped => self.stopped,
+            ServerState::Paused => self.paused,
         }
     }
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        pb.set_message("Installing Docker on macOS...");
-        let output = ProcessCommand::new("brew")
-            .arg("install")
-            .arg("docker")
-            .output()?;
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ServerState {
+    Running,
+    Paused,
+    Stopped,
+}
 
-        if !output.status.success() {
-            return Err(ServerError::DockerCommandFailed(
-                String::from_utf8_lossy(&output.stderr).to_string(),
-            ));
+impl ServerState {
+    fn label(self) -> &'static str {
+        match self {
+            ServerState::Running => "running",
+            ServerState::Paused => "paused",
+            ServerState::Stopped => "stopped",
         }
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        pb.set_message("Installing Docker on Windows...");
-        println!("{}", "\nPlease download and install Docker Desktop from:".yellow());
-        println!("https://www.docker.com/products/docker-desktop");
-        if !Confirm::new().with_prompt("Have you installed Docker Desktop?").interact()? {
-            return Err(ServerError::DockerNotInstalled);
+    fn colored(self) -> ColoredString {
+        match self {
+            ServerState::Running => "RUNNING".bright_green(),
+            ServerState::Paused => "PAUSED".yellow(),
+            ServerState::Stopped => "STOPPED".red(),
         }
     }
+}
 
-    pb.finish_with_message("Docker installed successfully!");
-    Ok(())
+#[derive(Args)]
+struct LogOptions {
+    /// Follow logs in real-time
+    #[arg(short, long)]
+    follow: bool,
+    /// Follow logs until a line matches this regex, then exit successfully
+    #[arg(long, value_name = "PATTERN", group = "until")]
+    follow_until: Option<String>,
+    /// Wait until the server has finished starting, then exit (exit code 5 on timeout, 6 on crash)
+    #[arg(long, group = "until", conflicts_with = "follow_until")]
+    wait_ready: bool,
+    /// Regex marking the server as ready for --wait-ready
+    #[arg(long, value_name = "PATTERN", requires = "wait_ready")]
+    ready_pattern: Option<String>,
+    /// Give up on --follow-until or --wait-ready after this many seconds
+    #[arg(long, value_name = "SECS", requires = "until")]
+    timeout: Option<u64>,
+    /// Prefix each line with its timestamp
+    #[arg(short, long)]
+    timestamps: bool,
+    /// While following, alert on lines matching this regex
+    #[arg(long, value_name = "PATTERN", requires = "follow", conflicts_with = "until")]
+    alert: Option<String>,
+    /// Command run for each alert, with the line as $1 and on stdin (defaults to a desktop notification)
+    #[arg(long, value_name = "CMD", requires = "alert")]
+    alert_cmd: Option<String>,
+    /// Print the newest crash report with the container log lines from just before it was written
+    #[arg(long, conflicts_with_all = ["follow", "until", "alert"])]
+    crash: bool,
+    /// Read the container's logs with 'docker logs', which works without a usable compose file
+    #[arg(long, conflicts_with_all = ["until", "alert", "crash"])]
+    container: bool,
+    /// Extra arguments passed to 'docker-compose logs' (or 'docker logs') after its own, e.g. -- --since 1h
+    #[arg(last = true, value_name = "COMPOSE_ARGS")]
+    compose_args: Vec<String>,
 }
 
-fn create_spinner(msg: &str) -> ProgressBar {
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
+#[derive(Args)]
+struct WaitOptions {
+    /// Wait until the server has finished starting (exit code 5 on timeout, 6 on crash)
+    #[arg(long, group = "waiting")]
+    wait: bool,
+    /// Wait until the container's healthcheck reports healthy instead of watching the logs
+    #[arg(long, group = "waiting", conflicts_with = "wait")]
+    wait_healthy: bool,
+    /// Regex marking the server as ready, for modded or proxy servers with a different "Done" line
+    #[arg(long, value_name = "PATTERN", requires = "wait")]
+    ready_pattern: Option<String>,
+    /// Give up waiting after this many seconds (default 300)
+    #[arg(long, value_name = "SECS", requires = "waiting")]
+    timeout: Option<u64>,
+}
+
+#[derive(Args)]
+struct DrainOptions {
+    /// Warn players and wait until nobody is online before stopping
+    #[arg(long, visible_alias = "wait-empty")]
+    drain: bool,
+    /// Give up draining after this many seconds (default 300; exit code 5, nothing is stopped)
+    #[arg(long, value_name = "SECS", requires = "drain")]
+    drain_timeout: Option<u64>,
+    /// Kick whoever is still online when the drain timeout runs out, then stop
+    #[arg(long, requires = "drain")]
+    kick: bool,
+    /// Message shown to kicked players
+    #[arg(long, value_name = "MESSAGE", default_value = DEFAULT_KICK_MESSAGE, requires = "kick")]
+    kick_message: String,
+}
+
+/// Extra arguments for the docker-compose invocations behind start and stop
+#[derive(Default)]
+struct ComposeArgs {
+    /// Profiles enabled for `up` on top of the default services
+    profiles: Vec<String>,
+    /// Passed verbatim after the subcommand's own arguments
+    passthrough: Vec<String>,
+    /// Carry on when a before_start or after_stop hook exits non-zero
+    ignore_hook_errors: bool,
+    /// `up --force-recreate` / `up --no-recreate`
+    force_recreate: bool,
+    no_recreate: bool,
+    /// `down --volumes` / `down --remove-orphans`
+    volumes: bool,
+    remove_orphans: bool,
+    /// How long a bulk start waits for a server others depend on to be ready
+    ready_timeout: Option<u64>,
+    /// Leave out per-server success lines, for `--only-errors`
+    only_errors: bool,
+}
+
+/// Optional settings shared by `create` and `batch-create`
+#[derive(Args)]
+struct CreateOptions {
+    /// Java version of the server image (8, 11, 17 or 21), inferred from the Minecraft version by default
+    #[arg(long, value_name = "VERSION")]
+    java: Option<u8>,
+    /// Server that has to be up before this one in a bulk `start`, e.g. backends of a proxy (repeatable)
+    #[arg(long = "depends-on", value_name = "SERVER")]
+    depends_on: Vec<String>,
+    /// Host address the server port is published on, e.g. 127.0.0.1 to only let a local proxy in
+    /// (default: all interfaces)
+    #[arg(long, value_name = "ADDRESS", value_parser = parse_bind_address)]
+    bind: Option<IpAddr>,
+    /// User id the server runs as (defaults to the invoking user on Unix)
+    #[arg(long)]
+    uid: Option<u32>,
+    /// Group id the server runs as (defaults to the invoking user's group on Unix)
+    #[arg(long)]
+    gid: Option<u32>,
+    /// Run the container with a read-only root filesystem, only /data and tmpfs mounts are writable
+    #[arg(long)]
+    read_only: bool,
+    /// Back up the server every time it's stopped, applying the backup retention setting
+    #[arg(long)]
+    auto_backup: bool,
+    /// Copy a variable from this shell's environment into the container (repeatable)
+    #[arg(long = "env-from-host", value_name = "VAR")]
+    env_from_host: Vec<String>,
+    /// Start from another server's extra environment variables, minus ports and secrets
+    #[arg(long, value_name = "SERVER")]
+    inherit_env_from: Option<String>,
+    /// Rotate the container's log once it reaches this size, e.g. 10m or 1g
+    #[arg(long, value_name = "SIZE")]
+    log_max_size: Option<String>,
+    /// Number of rotated log files Docker keeps
+    #[arg(long, value_name = "N")]
+    log_max_files: Option<u32>,
+    /// Shell command used as the container healthcheck instead of the image's mc-health probe
+    #[arg(long, value_name = "CMD")]
+    healthcheck_cmd: Option<String>,
+    /// Milliseconds one tick may take before the server's watchdog kills it, so the restart policy
+    /// brings it back (max-tick-time, default 60000); -1 turns the watchdog off
+    #[arg(long, value_name = "MS", allow_negative_numbers = true)]
+    watchdog: Option<i64>,
+    /// Resource pack sent to players: an http(s) URL, or a local zip served from this host
+    #[arg(long, value_name = "URL|FILE")]
+    resource_pack: Option<String>,
+    /// SHA-1 of the resource pack, computed automatically for local files
+    #[arg(long, value_name = "SHA1", requires = "resource_pack")]
+    resource_pack_sha1: Option<String>,
+    /// Kick players who decline the resource pack
+    #[arg(long, requires = "resource_pack")]
+    resource_pack_enforce: bool,
+    /// Address players reach this host at, used in the URL of a local resource pack
+    #[arg(long, value_name = "HOST", requires = "resource_pack")]
+    resource_pack_host: Option<String>,
+    /// Write the compose file to <DIR>/<name>/docker-compose.yml instead of the data directory
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+    /// Add an optional companion service, started with 'start --profile <companion>' (repeatable)
+    #[arg(long = "with", value_name = "COMPANION")]
+    companions: Vec<Companion>,
+    /// Performance preset scaled to the server's memory and this host's CPUs
+    #[arg(long, value_enum)]
+    tuning: Option<Tuning>,
+    /// World generator of the new world (asked for when creating interactively)
+    #[arg(long, value_enum)]
+    level_type: Option<LevelType>,
+    /// Generator settings JSON for flat and single-biome worlds, e.g. '{"biome":"minecraft:desert"}'
+    #[arg(long, value_name = "JSON", requires = "level_type")]
+    generator_settings: Option<String>,
+    /// Pin the server image to a digest (sha256:... or itzg/minecraft-server@sha256:...), so it
+    /// never changes when the java tag moves; the pinned image decides the Java version
+    #[arg(long, value_name = "DIGEST")]
+    image_digest: Option<String>,
+    /// Run scheduled, save-aware backups in an itzg/mc-backup container next to the server
+    #[arg(long)]
+    managed_backups: bool,
+    /// Time between managed backups, e.g. 6h or 1d
+    #[arg(long, value_name = "INTERVAL", default_value = DEFAULT_MANAGED_BACKUP_INTERVAL, requires = "managed_backups")]
+    backup_interval: String,
+    /// Days managed backups are kept for
+    #[arg(long, value_name = "DAYS", default_value_t = DEFAULT_MANAGED_BACKUP_PRUNE_DAYS, requires = "managed_backups")]
+    backup_prune_days: u32,
+}
+
+/// Global settings stored in `settings.toml`
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Settings {
+    defaults: ServerDefaults,
+    notifications: Notifications,
+    ports: PortSettings,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct PortSettings {
+    /// Host ports kept for non-Minecraft services, refused for game, companion and pack ports
+    reserved: BTreeSet<u16>,
+}
+
+/// Chat webhook that gets a message when servers start, stop or go down
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Notifications {
+    /// Discord or Slack incoming webhook URL; nothing is sent when unset
+    webhook_url: Option<String>,
+}
+
+/// Values new servers start from, so fleet conventions don't have to be retyped
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct ServerDefaults {
+    server_type: String,
+    version: String,
+    memory: String,
+    java: Option<u8>,
+    restart_policy: String,
+    /// Number of backups kept per server in the backups directory, all are kept when unset
+    backup_retention: Option<usize>,
+}
+
+impl Default for ServerDefaults {
+    fn default() -> Self {
+        ServerDefaults {
+            server_type: "VANILLA".to_string(),
+            version: "LATEST".to_string(),
+            memory: "2G".to_string(),
+            java: None,
+            restart_policy: DEFAULT_RESTART_POLICY.to_string(),
+            backup_retention: None,
+        }
+    }
+}
+
+fn lenient_timestamp<'de, D>(deserializer: D) -> std::result::Result<chrono::DateTime<chrono::Utc>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    let parsed = value
+        .as_str()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&chrono::Utc));
+    Ok(parsed.unwrap_or_else(|| {
+        warn_bad_timestamp(&format!("invalid created_at {}", value));
+        chrono::DateTime::UNIX_EPOCH
+    }))
+}
+
+fn missing_timestamp() -> chrono::DateTime<chrono::Utc> {
+    warn_bad_timestamp("missing created_at");
+    chrono::DateTime::UNIX_EPOCH
+}
+
+/// The config gets loaded several times per command, so only say this once
+fn warn_bad_timestamp(problem: &str) {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        eprintln!("{}", format!("Warning: {} in servers.json, using the Unix epoch", problem).yellow());
+    });
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ManagedBackups {
+    /// BACKUP_INTERVAL of the backup container
+    interval: String,
+    prune_days: u32,
+    /// The sidecar pauses saving over RCON, so both containers are given this password
+    rcon_password: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ServerConfig {
+    /// Ordered by name, so every listing and the saved file come out the same on each run
+    servers: BTreeMap<String, ServerInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct ServerInfo {
+    version: String,
+    port: String,
+    memory: String,
+    /// Set when `memory` was resolved from a percentage of host RAM
+    #[serde(default)]
+    memory_percent: Option<u8>,
+    data_path: String,
+    server_type: String,
+    mod_loader: Option<String>,
+    mod_loader_version: Option<String>,
+    java_args: Option<String>,
+    /// Java version of the image, inferred from `version` when unset
+    #[serde(default)]
+    java_version: Option<u8>,
+    /// User and group the container runs the server as
+    #[serde(default)]
+    uid: Option<u32>,
+    #[serde(default)]
+    gid: Option<u32>,
+    #[serde(default)]
+    read_only: bool,
+    /// Back up on every stop, before the container goes down
+    #[serde(default)]
+    auto_backup: bool,
+    /// Docker restart policy, `unless-stopped` when unset
+    #[serde(default)]
+    restart_policy: Option<String>,
+    /// Falls back to the Unix epoch when missing or unparseable, rather than failing the whole load
+    #[serde(default = "missing_timestamp", deserialize_with = "lenient_timestamp")]
+    created_at: chrono::DateTime<chrono::Utc>,
+    last_started: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    last_backup: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Servers a bulk start brings up (and waits for) before this one
+    #[serde(default)]
+    depends_on: Vec<String>,
+    /// World folders in the data dir, for servers with more than the default world (e.g. Multiverse)
+    #[serde(default)]
+    worlds: Vec<String>,
+    /// Additional container environment variables, addressed as `env.<KEY>`
+    #[serde(default)]
+    extra_env: BTreeMap<String, String>,
+    /// Optional services in the compose file, each behind its own profile
+    #[serde(default)]
+    companions: Vec<Companion>,
+    /// Host ports of companion services that don't use their default port, keyed by profile
+    #[serde(default)]
+    companion_ports: BTreeMap<String, u16>,
+    /// Absolute path of the compose file when it's kept outside the data directory
+    #[serde(default)]
+    compose_path: Option<String>,
+    /// Docker log rotation, `DEFAULT_LOG_MAX_SIZE` / `DEFAULT_LOG_MAX_FILES` when unset
+    #[serde(default)]
+    log_max_size: Option<String>,
+    #[serde(default)]
+    log_max_files: Option<u32>,
+    /// `max-tick-time` in milliseconds, -1 when the watchdog is off; the image's default when unset
+    #[serde(default)]
+    max_tick_time: Option<i64>,
+    /// Replaces the image's healthcheck when set
+    #[serde(default)]
+    healthcheck_cmd: Option<String>,
+    /// Resource pack URL players are sent on join
+    #[serde(default)]
+    resource_pack: Option<String>,
+    #[serde(default)]
+    resource_pack_sha1: Option<String>,
+    #[serde(default)]
+    resource_pack_enforce: bool,
+    /// Host port of the bundled web server when the pack is a local file
+    #[serde(default)]
+    resource_pack_port: Option<u16>,
+    /// server.properties values set with `properties --import`
+    #[serde(default)]
+    property_overrides: BTreeMap<String, String>,
+    /// Shell command run before every start
+    #[serde(default)]
+    before_start: Option<String>,
+    /// Shell command run after every stop
+    #[serde(default)]
+    after_stop: Option<String>,
+    /// Left out of bulk `start`, for archived or test servers
+    #[serde(default)]
+    disabled: bool,
+    /// Data directory packed into `archive_path`, set by `archive`
+    #[serde(default)]
+    archived: bool,
+    /// rsync target of the last `sync`, reused when none is given
+    #[serde(default)]
+    sync_target: Option<String>,
+    /// Schedule of the itzg/mc-backup sidecar, when the server has one
+    #[serde(default)]
+    managed_backups: Option<ManagedBackups>,
+    /// Performance preset; its settings are recomputed whenever the compose file is written
+    #[serde(default)]
+    tuning: Option<Tuning>,
+    /// Generator of newly created worlds, the image's default (normal) when unset
+    #[serde(default)]
+    level_type: Option<LevelType>,
+    #[serde(default)]
+    generator_settings: Option<String>,
+    /// Host address the game port is published on; all interfaces when unset
+    #[serde(default)]
+    bind_address: Option<IpAddr>,
+    /// Digest (`sha256:...`) of the server image used instead of the `java<N>` tag
+    #[serde(default)]
+    image_digest: Option<String>,
+    #[serde(default)]
+    last_sync: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ServerInfo {
+    fn logging(&self) -> Logging {
+        let mut options = BTreeMap::new();
+        options.insert(
+            "max-size".to_string(),
+            self.log_max_size.clone().unwrap_or_else(|| DEFAULT_LOG_MAX_SIZE.to_string()),
+        );
+        options.insert(
+            "max-file".to_string(),
+            self.log_max_files.unwrap_or(DEFAULT_LOG_MAX_FILES).to_string(),
+        );
+        Logging {
+            driver: "json-file".to_string(),
+            options,
+        }
+    }
+
+    fn image(&self) -> String {
+        match &self.image_digest {
+            Some(digest) => format!("{}@{}", SERVER_IMAGE, digest),
+            None => format!(
+                "{}:java{}",
+                SERVER_IMAGE,
+                self.java_version.unwrap_or_else(|| infer_java_version(&self.version))
+            ),
+        }
+    }
+
+    fn compose_file(&self) -> PathBuf {
+        match &self.compose_path {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(&self.data_path).join("docker-compose.yml"),
+        }
+    }
+
+    /// Data directory as written in the compose file. Relative paths resolve against the compose
+    /// file's directory, so a compose file kept elsewhere gets the absolute path.
+    fn compose_data_path(&self) -> String {
+        match &self.compose_path {
+            Some(_) => std::path::absolute(&self.data_path)
+                .map_or_else(|_| self.data_path.clone(), |p| p.to_string_lossy().to_string()),
+            None => self.data_path.clone(),
+        }
+    }
+
+    /// Host side of the game port mapping: `25565`, `127.0.0.1:25565` or `[::1]:25565`
+    fn published_port(&self) -> String {
+        match self.bind_address {
+            Some(IpAddr::V4(ip)) => format!("{}:{}", ip, self.port),
+            Some(IpAddr::V6(ip)) => format!("[{}]:{}", ip, self.port),
+            None => self.port.clone(),
+        }
+    }
+
+    fn companion_port(&self, companion: Companion) -> u16 {
+        self.companion_ports
+            .get(companion.profile())
+            .copied()
+            .unwrap_or_else(|| companion.default_port())
+    }
+
+    /// `--profile` arguments that make docker-compose see every companion service
+    fn profile_args(&self) -> Vec<String> {
+        self.companions
+            .iter()
+            .flat_map(|c| ["--profile".to_string(), c.profile().to_string()])
+            .collect()
+    }
+
+    /// The server's own container and its companion, resource pack and backup containers
+    fn container_names(&self, name: &str) -> Vec<String> {
+        std::iter::once(format!("mc-{}", name))
+            .chain(self.companions.iter().map(|c| format!("mc-{}-{}", name, c.profile())))
+            .chain(self.resource_pack_port.map(|_| format!("mc-{}-resourcepack", name)))
+            .chain(self.managed_backups.as_ref().map(|_| format!("mc-{}-backup", name)))
+            .collect()
+    }
+}
+
+/// Per-server playtime recorded by `monitor --track-activity`
+#[derive(Serialize, Deserialize, Default)]
+struct ActivityLog {
+    players: HashMap<String, PlayerActivity>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PlayerActivity {
+    seconds_played: u64,
+    first_seen: chrono::DateTime<chrono::Utc>,
+    last_seen: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ComposeConfig {
+    version: String,
+    services: BTreeMap<String, MinecraftService>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MinecraftService {
+    image: String,
+    container_name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ports: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    environment: Vec<String>,
+    volumes: Vec<String>,
+    restart: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    stdin_open: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    tty: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    read_only: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tmpfs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    command: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    profiles: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    logging: Option<Logging>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    healthcheck: Option<Healthcheck>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Healthcheck {
+    test: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Logging {
+    driver: String,
+    options: BTreeMap<String, String>,
+}
+
+const CONFIG_DIR: &str = ".mc-servers";
+const SERVER_IMAGE: &str = "itzg/minecraft-server";
+const CONFIG_FILE: &str = "servers.json";
+const SETTINGS_FILE: &str = "settings.toml";
+const BACKUP_DIR: &str = "backups";
+/// Where `archive` keeps data directories, one `<name>.tar.gz` per server
+const ARCHIVE_DIR: &str = "archived";
+/// Extension added to encrypted archives, which are in the age format
+const ENCRYPTED_EXTENSION: &str = "age";
+const BACKUP_PASSPHRASE_ENV: &str = "BLOCKOPS_BACKUP_PASSPHRASE";
+const BACKUP_PASSPHRASE_ACCOUNT: &str = "backup_passphrase";
+/// Length of the `%Y%m%d_%H%M%S` timestamp in backup file names
+const BACKUP_TIMESTAMP_LEN: usize = 15;
+const MAX_BACKUP_LABEL_LEN: usize = 40;
+/// `backup --estimate` compresses this much of the data, in chunks spread evenly across it
+const BACKUP_SAMPLE_SIZE: u64 = 16 * 1024 * 1024;
+const BACKUP_SAMPLE_CHUNK: u64 = 256 * 1024;
+const HISTORY_DIR: &str = "history";
+const RCON_HISTORY_LIMIT: usize = 50;
+/// Vanilla, Paper, Forge and Fabric print "Done (12.3s)!", BungeeCord prints "Listening on /0.0.0.0:25577"
+const DEFAULT_READY_PATTERN: &str = r"Done \([0-9.,]+m?s\)!|Listening on /";
+const STARTUP_CRASH_PATTERN: &str = r"Failed to start the minecraft server|Exception in server tick loop|This crash report has been saved to|Encountered an unexpected exception";
+const DEFAULT_READY_TIMEOUT: u64 = 300;
+const DEFAULT_DRAIN_TIMEOUT: u64 = 300;
+const DEFAULT_RESTART_WARNING: u64 = 30;
+/// Seconds `down` gives a server to save its worlds before killing it; Compose's own default of
+/// 10 can cut a large save short
+const STOP_TIMEOUT: u64 = 60;
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How often players are reminded of the coming stop while draining
+const DRAIN_REMINDER_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_KICK_MESSAGE: &str = "Server is going down for maintenance";
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Probe the itzg image's own healthcheck runs
+const DEFAULT_HEALTHCHECK_CMD: &str = "mc-health";
+/// UID and GID the itzg image runs the server as when none are configured
+const IMAGE_DEFAULT_ID: u32 = 1000;
+const DEFAULT_LOG_MAX_SIZE: &str = "10m";
+const DEFAULT_LOG_MAX_FILES: u32 = 3;
+/// Shortest `max-tick-time` accepted; chunk generation and saves can hold a tick for seconds
+const MIN_MAX_TICK_TIME: i64 = 10_000;
+const ACTIVITY_DIR: &str = "activity";
+const DEFAULT_DETACH_KEYS: &str = "ctrl-p,ctrl-q";
+const DEFAULT_MANAGED_BACKUP_INTERVAL: &str = "24h";
+const DEFAULT_MANAGED_BACKUP_PRUNE_DAYS: u32 = 7;
+/// Where the backup sidecar writes, under the backups directory, one folder per server
+const MANAGED_BACKUP_DIR: &str = "managed";
+/// Where `player reset` keeps the files it deletes, under the backups directory, one folder per
+/// server. Apart from the server's own backups, so they're never listed, restored or pruned as one.
+const PLAYER_BACKUP_DIR: &str = "players";
+/// Seconds of container log shown before a crash report's write time by `logs --crash`
+const CRASH_LOG_WINDOW: i64 = 60;
+/// Rough space a fresh server needs for the server jar, libraries and a new world
+const MIN_SERVER_SPACE: u64 = 1024 * 1024 * 1024;
+const SERVER_TYPES: [&str; 6] = ["VANILLA", "PAPER", "FORGE", "FABRIC", "SPIGOT", "PURPUR"];
+/// Shown by `versions`, in the same order as `SERVER_TYPES`
+const SERVER_TYPE_DESCRIPTIONS: [&str; 6] = [
+    "Vanilla Minecraft server",
+    "High performance fork of Spigot",
+    "Modded Minecraft server",
+    "Lightweight mod loader",
+    "Fork of CraftBukkit",
+    "Performance-focused server",
+];
+const VERSION_EXAMPLES: [(&str, &str); 3] = [
+    ("LATEST", "always uses the latest release"),
+    ("1.20.2", "specific version"),
+    ("SNAPSHOT", "latest snapshot version"),
+];
+/// Server type, then the version keyword and an example version of its loader
+const MOD_LOADER_EXAMPLES: [(&str, [&str; 2]); 2] =
+    [("FORGE", ["RECOMMENDED", "47.1.0"]), ("FABRIC", ["LATEST", "0.14.21"])];
+/// Environment variables generated from dedicated `ServerInfo` fields, which can't be set via `env.*`
+const MANAGED_ENV: &[&str] = &[
+    "EULA",
+    "MEMORY",
+    "VERSION",
+    "TYPE",
+    "JVM_OPTS",
+    "UID",
+    "GID",
+    "RESOURCE_PACK",
+    "RESOURCE_PACK_SHA1",
+    "RESOURCE_PACK_ENFORCE",
+    "MAX_TICK_TIME",
+    "LEVEL_TYPE",
+    "GENERATOR_SETTINGS",
+    // Shared with the managed backup sidecar, which has to know the server's password
+    "RCON_PASSWORD",
+];
+/// Keys of a vanilla server.properties, used to flag typos and foreign keys on import
+const KNOWN_PROPERTIES: &[&str] = &[
+    "accepts-transfers",
+    "allow-flight",
+    "allow-nether",
+    "broadcast-console-to-ops",
+    "broadcast-rcon-to-ops",
+    "bug-report-link",
+    "difficulty",
+    "enable-command-block",
+    "enable-jmx-monitoring",
+    "enable-query",
+    "enable-rcon",
+    "enable-status",
+    "enforce-secure-profile",
+    "enforce-whitelist",
+    "entity-broadcast-range-percentage",
+    "force-gamemode",
+    "function-permission-level",
+    "gamemode",
+    "generate-structures",
+    "generator-settings",
+    "hardcore",
+    "hide-online-players",
+    "initial-disabled-packs",
+    "initial-enabled-packs",
+    "level-name",
+    "level-seed",
+    "level-type",
+    "log-ips",
+    "max-chained-neighbor-updates",
+    "max-players",
+    "max-tick-time",
+    "max-world-size",
+    "motd",
+    "network-compression-threshold",
+    "online-mode",
+    "op-permission-level",
+    "pause-when-empty-seconds",
+    "player-idle-timeout",
+    "prevent-proxy-connections",
+    "previews-chat",
+    "pvp",
+    "query.port",
+    "rate-limit",
+    "rcon.password",
+    "rcon.port",
+    "region-file-compression",
+    "require-resource-pack",
+    "resource-pack",
+    "resource-pack-id",
+    "resource-pack-prompt",
+    "resource-pack-sha1",
+    "server-ip",
+    "server-name",
+    "server-port",
+    "simulation-distance",
+    "snooper-enabled",
+    "spawn-animals",
+    "spawn-monsters",
+    "spawn-npcs",
+    "spawn-protection",
+    "sync-chunk-writes",
+    "text-filtering-config",
+    "text-filtering-version",
+    "use-native-transport",
+    "view-distance",
+    "white-list",
+];
+/// Properties the image sets up so port mapping and RCON keep working, which an import mustn't change
+const MANAGED_PROPERTIES: &[&str] = &["server-port", "enable-rcon", "rcon.port", "rcon.password"];
+const WHITELIST_FILE: &str = "whitelist.json";
+const OPS_FILE: &str = "ops.json";
+const MOJANG_PROFILE_URL: &str = "https://api.mojang.com/users/profiles/minecraft/";
+/// Every Forge build, keyed by the Minecraft version it's for
+const FORGE_VERSIONS_URL: &str = "https://maven.minecraftforge.net/net/minecraftforge/forge/maven-metadata.json";
+const FORGE_PROMOTIONS_URL: &str = "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
+/// Fabric loaders usable with a Minecraft version (appended), newest first; empty for unsupported versions
+const FABRIC_LOADERS_URL: &str = "https://meta.fabricmc.net/v2/versions/loader/";
+/// Where a local resource pack is copied, relative to the data directory
+const RESOURCE_PACK_DIR: &str = "resourcepack";
+const RESOURCE_PACK_FILE: &str = "pack.zip";
+const RESOURCE_PACK_PORT: u16 = 8090;
+/// Offered by interactive create, or the next free port after it
+const DEFAULT_PORT: u16 = 25565;
+const JAVA_VERSIONS: [u8; 4] = [8, 11, 17, 21];
+/// Paths the itzg entrypoint writes outside /data, mounted as tmpfs for read-only containers
+const READ_ONLY_TMPFS: &[&str] = &["/tmp"];
+/// Minor versions a server can move up without being asked for confirmation
+const MAJOR_VERSION_JUMP: u32 = 2;
+const RESTART_POLICIES: [&str; 4] = ["no", "always", "on-failure", "unless-stopped"];
+const DEFAULT_RESTART_POLICY: &str = "unless-stopped";
+const SETTINGS_KEYS: [&str; 7] = [
+    "defaults.server_type",
+    "defaults.version",
+    "defaults.memory",
+    "defaults.java",
+    "defaults.restart_policy",
+    "defaults.backup_retention",
+    "notifications.webhook_url",
+];
+const SETTING_KEYS: &[&str] = &[
+    "version",
+    "port",
+    "memory",
+    "memory_percent",
+    "server_type",
+    "mod_loader_version",
+    "java_args",
+    "java_version",
+    "uid",
+    "gid",
+    "restart_policy",
+    "log_max_size",
+    "log_max_files",
+    "max_tick_time",
+    "healthcheck_cmd",
+    "resource_pack",
+    "resource_pack_sha1",
+    "resource_pack_enforce",
+    "read_only",
+    "auto_backup",
+    "companions",
+    "tuning",
+    "level_type",
+    "generator_settings",
+    "image_digest",
+    "bind_address",
+    "description",
+    "tags",
+    "depends_on",
+    "worlds",
+    "data_path",
+    "compose_path",
+    "created_at",
+    "last_started",
+    "last_backup",
+];
+
+/// Set by `--cron`, read by `create_spinner` and `print_status` which are called from everywhere
+static CRON_MODE: AtomicBool = AtomicBool::new(false);
+/// Set by `--use-keyring`, read by `store_secret`
+static USE_KEYRING: AtomicBool = AtomicBool::new(false);
+/// Set by `backup --stdout`, whose archive owns stdout
+static STREAMING: AtomicBool = AtomicBool::new(false);
+
+fn main() {
+    let cli = Cli::parse();
+    let json = cli.json;
+    let cron = cli.cron;
+    let result = run(cli);
+    if cron {
+        match &result {
+            Ok(()) => println!("{} Finished successfully", cron_timestamp()),
+            Err(e) => eprintln!("{} Failed (exit code {}): {}", cron_timestamp(), e.exit_code(), e),
+        }
+    }
+    if let Err(e) = result {
+        if json {
+            eprintln!("{}", e.to_json());
+        } else if cron {
+            // Already reported above
+        } else {
+            // Same output as returning the error from main, but with an exit code per outcome
+            eprintln!("Error: {:?}", e);
+        }
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let streaming = matches!(&cli.command, Some(Commands::Backup { options: BackupOptions { stdout: true, .. }, .. }));
+    if streaming && cli.cron {
+        return Err(ServerError::InvalidValue(
+            "stdout".to_string(),
+            "--cron logs to stdout, which --stdout needs for the archive".to_string(),
+        ));
+    }
+    if cli.no_color {
+        colored::control::set_override(false);
+    }
+    USE_KEYRING.store(cli.use_keyring, Ordering::Relaxed);
+    STREAMING.store(streaming, Ordering::Relaxed);
+    if cli.cron {
+        colored::control::set_override(false);
+        CRON_MODE.store(true, Ordering::Relaxed);
+        println!("{} blockops {}", cron_timestamp(), std::env::args().skip(1).collect::<Vec<_>>().join(" "));
+    }
+    let templated = matches!(&cli.command, Some(Commands::List { options: ListOptions { template: Some(_), .. } }));
+    // The banner would get in the way of programs reading the output
+    if !cli.json && !cli.cron && !streaming && !templated {
+        print_banner();
+        warn_if_root();
+    }
+
+    ensure_config_dir()?;
+    recover_server_config()?;
+    absolutize_data_paths()?;
+    repair_duplicate_environment()?;
+    if let Some(legacy) = legacy_config_dir().filter(|_| !matches!(&cli.command, Some(Commands::Migrate { .. }))) {
+        eprintln!(
+            "{} {} holds servers from an older version, run 'migrate' to move them to {}",
+            "Note:".yellow(),
+            legacy.display(),
+            config_dir().display()
+        );
+    }
+
+    if !check_docker_installed() {
+        install_docker()?;
+    }
+
+    // A command's own timeout flag wins over the global one
+    let wait_timeout = cli.wait_timeout;
+    match cli.command.unwrap_or(Commands::List { options: ListOptions::default() }) {
+        Commands::Create { start, no_start, options } => create_server(start, no_start, &options)?,
+        Commands::BatchCreate {
+            base_name,
+            count,
+            start_port,
+            server_type,
+            version,
+            memory,
+            loader_version,
+            java_args,
+            accept_eula,
+            options,
+        } => batch_create_servers(
+            &base_name,
+            count,
+            start_port,
+            server_type,
+            version,
+            memory,
+            loader_version,
+            java_args,
+            accept_eula,
+            &options,
+        )?,
+        Commands::List { options } => list_servers(&options, cli.json)?,
+        Commands::Summary => fleet_summary()?,
+        Commands::Start {
+            name,
+            parallel,
+            profiles,
+            wait,
+            ignore_hook_errors,
+            force_recreate,
+            no_recreate,
+            only_errors,
+            compose_args,
+        } => {
+            let args = ComposeArgs {
+                profiles,
+                passthrough: compose_args,
+                ignore_hook_errors,
+                force_recreate,
+                no_recreate,
+                ready_timeout: wait.timeout.or(wait_timeout),
+                only_errors,
+                ..Default::default()
+            };
+            start_servers(name.clone(), parallel, &args)?;
+            if wait.wait || wait.wait_healthy {
+                let wait = WaitOptions { timeout: wait.timeout.or(wait_timeout), ..wait };
+                wait_for_servers(name, &wait)?;
+            }
+        }
+        Commands::Stop {
+            name,
+            parallel,
+            ignore_hook_errors,
+            volumes,
+            remove_orphans,
+            only_errors,
+            drain,
+            compose_args,
+        } => {
+            if volumes {
+                println!(
+                    "{} --volumes deletes the containers' anonymous volumes. The data directory is a bind mount \
+                     and stays, but anything an image keeps in its own volumes is gone for good.",
+                    "Warning:".yellow()
+                );
+            }
+            let args = ComposeArgs {
+                passthrough: compose_args,
+                ignore_hook_errors,
+                volumes,
+                remove_orphans,
+                only_errors,
+                ..Default::default()
+            };
+            if drain.drain {
+                let drain = DrainOptions { drain_timeout: drain.drain_timeout.or(wait_timeout), ..drain };
+                drain_servers(name.as_deref(), &drain)?;
+            }
+            stop_servers(name, parallel, &args)?
+        }
+        Commands::Restart { name, rolling, tag, warning, wait_healthy, ready_pattern, timeout } => {
+            let timeout = timeout.or(wait_timeout);
+            let wait = WaitOptions { wait: !wait_healthy, wait_healthy, ready_pattern, timeout };
+            restart_servers(name.as_deref(), tag.as_deref(), rolling, warning, &wait)?
+        }
+        Commands::Logs { name, options } => {
+            show_logs(&name, &LogOptions { timeout: options.timeout.or(wait_timeout), ..options })?
+        }
+        Commands::Archive { name } => archive_server(&name)?,
+        Commands::Unarchive { name } => unarchive_server(&name)?,
+        Commands::Remove { name, force, no_backup, keep_data } => remove_server(&name, force, !no_backup, keep_data)?,
+        Commands::Reinstall { name, backup_before } => reinstall_server(&name, backup_before)?,
+        Commands::ShowCompose { name, regenerate } => show_compose(&name, regenerate)?,
+        Commands::Diff { a, b } => diff_servers(&a, &b, cli.json)?,
+        Commands::Reconcile => reconcile_servers()?,
+        Commands::Health { name } => check_health(&name)?,
+        Commands::VersionCheck { name } => version_check(name.as_deref())?,
+        Commands::Doctor { name, fix_permissions, force } => run_doctor(&name, fix_permissions, force)?,
+        Commands::Map { name, kind, enable, disable, port } => configure_map(&name, kind, enable, disable, port)?,
+        Commands::Console { name, detach_keys, tee } => attach_console(&name, &detach_keys, tee.as_deref())?,
+        Commands::Exec { name, args } => exec_in_server(&name, &args)?,
+        Commands::Versions => list_versions(cli.json),
+        Commands::Context => show_context(cli.json)?,
+        Commands::Backup { name, estimate: true, options, .. } => estimate_backups(name.as_deref(), &options)?,
+        Commands::Backup { name: Some(name), options, .. } => backup_server(&name, &options)?,
+        Commands::Backup { name: None, parallel, since_last_backup, options, .. } => {
+            backup_all_servers(&options, parallel, since_last_backup)?
+        }
+        Commands::Restore { name, path, backup_before } => {
+            let path = match path {
+                Some(path) => path,
+                None => pick_backup(&name)?,
+            };
+            restore_server(&name, &path, backup_before)?
+        }
+        Commands::Backups { name } => show_backups(&name)?,
+        Commands::Get { name, key } => get_setting(&name, &key)?,
+        Commands::Set { name, key, value, force, dry_run } => set_setting(&name, &key, &value, force, dry_run)?,
+        Commands::Pregen { name, radius } => pregen_world(&name, radius, wait_timeout)?,
+        Commands::Enable { name } => set_enabled(&name, true)?,
+        Commands::Disable { name } => set_enabled(&name, false)?,
+        Commands::Sync { name, target, pull, dry_run } => sync_server(&name, target, pull, dry_run)?,
+        Commands::Migrate { from } => migrate_config(from)?,
+        Commands::Hooks { name, before_start, after_stop, clear } => {
+            configure_hooks(&name, before_start, after_stop, clear)?
+        }
+        Commands::Properties { name, import: None } => show_properties(&name)?,
+        Commands::Properties { name, import: Some(path) } => import_properties(&name, &path)?,
+        Commands::ResetDimension { name, dimension, world, force } => {
+            reset_dimension(&name, dimension, world.as_deref(), force)?
+        }
+        Commands::Whitelist { name, action } => edit_access_list(&name, WHITELIST_FILE, action)?,
+        Commands::Ops { name, action } => edit_access_list(&name, OPS_FILE, action)?,
+        Commands::Player { name, action } => match action {
+            PlayerAction::Inspect { player } => inspect_player(&name, &player)?,
+            PlayerAction::Reset { player, force } => reset_player(&name, &player, force)?,
+        },
+        Commands::Bundle { name, lines, out } => create_bundle(&name, lines, out)?,
+        Commands::Monitor { name, interval, track_activity } => monitor_server(&name, interval, track_activity)?,
+        Commands::Activity { name, top } => show_activity(&name, top)?,
+        Commands::Notify { test } => check_notifications(test)?,
+        Commands::Settings { action } => match action {
+            SettingsAction::Get { key } => get_global_setting(key.as_deref())?,
+            SettingsAction::Set { key, value } => set_global_setting(&key, &value)?,
+        },
+        Commands::Ports { action } => match action {
+            PortsAction::List => list_reserved_ports(cli.json)?,
+            PortsAction::Reserve { port } => reserve_port(port)?,
+            PortsAction::Release { port } => release_port(port)?,
+        },
+        Commands::Verify { name } => verify_world(&name)?,
+        Commands::Ping { name, address } => ping_server(&name, address.as_deref())?,
+        Commands::Tps { name } => show_tps(&name, cli.json)?,
+        Commands::Rcon { name, command, history, repeat } => rcon_command(&name, &command, history, repeat)?,
+    }
+
+    Ok(())
+}
+
+fn print_banner() {
+    println!("{}", r#"
+╔╦╗╔═╗  ╔═╗╔═╗╦═╗╦  ╦╔═╗╦═╗
+║║║║    ╚═╗║╣ ╠╦╝╚╗╔╝║╣ ╠╦╝
+╩ ╩╚═╝  ╚═╝╚═╝╩╚═ ╚╝ ╚═╝╩╚═
+    "#.bright_green());
+    println!("{}", "Production Minecraft Server Manager".bright_blue().bold());
+    println!("{}", "=================================".bright_blue());
+}
+
+/// BlockOps' config, data and backups: `.mc-servers` in the user's home directory, the same
+/// whichever directory the tool runs from
+fn config_dir() -> &'static Path {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+        let dir = home.map_or_else(|| PathBuf::from(CONFIG_DIR), |home| PathBuf::from(home).join(CONFIG_DIR));
+        std::path::absolute(&dir).unwrap_or(dir)
+    })
+}
+
+/// Older versions kept the config in `.mc-servers` under whatever directory they ran in
+fn legacy_config_dir() -> Option<PathBuf> {
+    let legacy = std::path::absolute(CONFIG_DIR).ok()?;
+    let same = |a: &Path, b: &Path| a == b || a.canonicalize().ok().is_some_and(|a| b.canonicalize().is_ok_and(|b| a == b));
+    (legacy.join(CONFIG_FILE).is_file() && !same(&legacy, config_dir())).then_some(legacy)
+}
+
+fn ensure_config_dir() -> Result<()> {
+    let config_dir = config_dir();
+    if !config_dir.exists() {
+        fs::create_dir_all(config_dir)?;
+        fs::create_dir_all(config_dir.join(BACKUP_DIR))?;
+        save_server_config(&ServerConfig {
+            servers: BTreeMap::new(),
+        })?;
+    }
+    Ok(())
+}
+
+/// Renames `from` to `to`, copying instead when they're on different filesystems
+fn move_path(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_recursive(from, to)?;
+            if from.is_dir() {
+                fs::remove_dir_all(from)?;
+            } else {
+                fs::remove_file(from)?;
+            }
+            Ok(())
+        }
+        result => Ok(result?),
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// Pulls the servers of a legacy config directory, by default the one in the current directory,
+/// into `config_dir`. Data dirs, backups and history files are moved, data paths are rewritten
+/// as absolute paths, compose files are regenerated and migrated entries are dropped from the
+/// old servers.json.
+fn migrate_config(from: Option<PathBuf>) -> Result<()> {
+    let from = from.unwrap_or_else(|| PathBuf::from("."));
+    let legacy = if from.join(CONFIG_DIR).is_dir() { from.join(CONFIG_DIR) } else { from };
+    let legacy_config_path = legacy.join(CONFIG_FILE);
+    if !legacy_config_path.is_file() {
+        return Err(ServerError::InvalidValue(
+            "from".to_string(),
+            format!("no {} in {}", CONFIG_FILE, legacy.display()),
+        ));
+    }
+    let legacy = legacy.canonicalize()?;
+    let current = config_dir().canonicalize()?;
+    if legacy == current {
+        return Err(ServerError::InvalidValue(
+            "from".to_string(),
+            format!("{} is already the config directory in use", legacy.display()),
+        ));
+    }
+    // Relative data paths were relative to the directory the tool ran in back then
+    let legacy_root = legacy.parent().unwrap_or(&legacy).to_path_buf();
+
+    let mut legacy_config: ServerConfig = serde_json::from_str(&fs::read_to_string(&legacy_config_path)?)?;
+    let mut config = load_server_config()?;
+    println!("\n{}", format!("Migrating servers from {}:", legacy.display()).bright_cyan());
+
+    let mut names: Vec<String> = legacy_config.servers.keys().cloned().collect();
+    names.sort();
+    let mut migrated = 0;
+    for name in names {
+        if config.servers.contains_key(&name) {
+            println!("{}", format!("- {}: skipped, a server with that name already exists here", name).yellow());
+            continue;
+        }
+        if is_server_running(&name)? {
+            println!("{}", format!("- {}: skipped, stop it before moving its data", name).yellow());
+            continue;
+        }
+        let mut info = legacy_config.servers[&name].clone();
+        let mut moved = Vec::new();
+
+        let old_data = legacy_root.join(&info.data_path);
+        let new_data = config_dir().join(&name);
+        if old_data.is_dir() {
+            move_path(&old_data, &new_data)?;
+            moved.push(format!("data ({})", new_data.display()));
+        } else if !info.archived {
+            fs::create_dir_all(&new_data)?;
+        }
+        info.data_path = new_data.to_string_lossy().to_string();
+
+        // An archived server's data is in its archive until `unarchive`
+        let old_archive = legacy.join(ARCHIVE_DIR).join(format!("{}.tar.gz", name));
+        if old_archive.is_file() {
+            move_path(&old_archive, &archive_path(&name))?;
+            moved.push(format!("archive ({})", archive_path(&name).display()));
+        } else if info.archived {
+            moved.push(format!("archive {} is missing", old_archive.display()).yellow().to_string());
+        }
+        let old_managed = legacy.join(BACKUP_DIR).join(MANAGED_BACKUP_DIR).join(&name);
+        if old_managed.is_dir() {
+            move_path(&old_managed, &managed_backup_dir(&name))?;
+            moved.push("managed backups".to_string());
+        }
+
+        let backups: Vec<PathBuf> = fs::read_dir(legacy.join(BACKUP_DIR))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.file_name().and_then(|f| f.to_str()).is_some_and(|f| is_backup_of(f, &name)))
+            .collect();
+        for backup in &backups {
+            move_path(backup, &config_dir().join(BACKUP_DIR).join(backup.file_name().unwrap_or_default()))?;
+        }
+        if !backups.is_empty() {
+            moved.push(format!("{} backup(s)", backups.len()));
+        }
+
+        for (dir, file) in [(HISTORY_DIR, format!("{}.txt", name)), (ACTIVITY_DIR, format!("{}.json", name))] {
+            let old = legacy.join(dir).join(&file);
+            if old.is_file() {
+                move_path(&old, &config_dir().join(dir).join(&file))?;
+                moved.push(format!("{} file", dir));
+            }
+        }
+
+        if let Some((owner, _)) = config.servers.iter().find(|(_, other)| other.port == info.port) {
+            moved.push(format!("port {} clashes with '{}', change it with 'set {} port'", info.port, owner, name).yellow().to_string());
+        }
+
+        // `unarchive` writes it, along with the data dir it usually lives in
+        if !info.archived {
+            write_compose_file(&name, &info)?;
+        }
+        config.servers.insert(name.clone(), info);
+        legacy_config.servers.remove(&name);
+        // Saved as we go, so an error part way through never leaves a server in both configs
+        save_server_config(&config)?;
+        fs::write(&legacy_config_path, serde_json::to_string_pretty(&legacy_config)?)?;
+
+        if moved.is_empty() {
+            println!("- {}: config only", name.bright_green());
+        } else {
+            println!("- {}: {}", name.bright_green(), moved.join(", "));
+        }
+        migrated += 1;
+    }
+
+    let legacy_settings = legacy.join(SETTINGS_FILE);
+    let settings = config_dir().join(SETTINGS_FILE);
+    if legacy_settings.is_file() && !settings.exists() {
+        move_path(&legacy_settings, &settings)?;
+        println!("- {} moved", SETTINGS_FILE);
+    }
+
+    println!("{}", format!("Migrated {} server(s).", migrated).green());
+    if legacy_config.servers.is_empty() {
+        println!("Nothing is left in {}, it can be deleted.", legacy.display());
+    }
+    Ok(())
+}
+
+/// Older versions stored data dirs relative to the directory holding the config directory.
+/// Rewrites them as absolute paths so they resolve from any working directory.
+fn absolutize_data_paths() -> Result<()> {
+    let mut config = load_server_config()?;
+    let root = config_dir().parent().unwrap_or(config_dir());
+    let mut changed = false;
+    for info in config.servers.values_mut() {
+        if Path::new(&info.data_path).is_relative() {
+            info.data_path = root.join(&info.data_path).to_string_lossy().to_string();
+            changed = true;
+        }
+    }
+    if changed {
+        save_server_config(&config)?;
+    }
+    Ok(())
+}
+
+fn load_server_config() -> Result<ServerConfig> {
+    let config_path = config_dir().join(CONFIG_FILE);
+    if config_path.exists() {
+        let content = fs::read_to_string(config_path)?;
+        Ok(serde_json::from_str(&content)?)
+    } else {
+        Ok(ServerConfig {
+            servers: BTreeMap::new(),
+        })
+    }
+}
+
+/// A `servers.json` that doesn't parse would stop every command. Keeps a copy of the broken file
+/// and, when someone is there to choose, rebuilds the config or starts over.
+fn recover_server_config() -> Result<()> {
+    let config_path = config_dir().join(CONFIG_FILE);
+    let Ok(content) = fs::read_to_string(&config_path) else {
+        return Ok(());
+    };
+    let Err(e) = serde_json::from_str::<ServerConfig>(&content) else {
+        return Ok(());
+    };
+
+    let backup_path = config_path.with_extension("json.bak");
+    fs::copy(&config_path, &backup_path)?;
+    print_status(format!("{} could not be parsed: {}", CONFIG_FILE, e).red());
+    print_status(format!("A copy of the broken file was saved to {}", backup_path.display()));
+    if !is_interactive() {
+        return Err(e.into());
+    }
+
+    let choice = Select::new()
+        .with_prompt("How do you want to continue?")
+        .items(&[
+            "Recover what's readable from servers.json and the servers' compose files",
+            "Start with an empty server list",
+            "Exit without changing anything",
+        ])
+        .default(0)
+        .interact()?;
+    let config = match choice {
+        0 => recovered_config(&content),
+        1 => ServerConfig {
+            servers: BTreeMap::new(),
+        },
+        _ => return Err(e.into()),
+    };
+    save_server_config(&config)?;
+    print_status(format!("{} rewritten with {} server(s).", CONFIG_FILE, config.servers.len()).green());
+    Ok(())
+}
+
+fn recovered_config(content: &str) -> ServerConfig {
+    let mut servers = BTreeMap::new();
+
+    // Entries that still parse on their own are kept as they are
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(content) {
+        if let Some(entries) = value["servers"].as_object() {
+            for (name, entry) in entries {
+                if let Ok(info) = serde_json::from_value::<ServerInfo>(entry.clone()) {
+                    servers.insert(name.clone(), info);
+                }
+            }
+        }
+    }
+
+    // Everything else is rebuilt from its compose file
+    for dir in fs::read_dir(config_dir()).into_iter().flatten().flatten() {
+        let name = dir.file_name().to_string_lossy().to_string();
+        if servers.contains_key(&name) {
+            continue;
+        }
+        if let Some(info) = server_info_from_compose(&name, &dir.path()) {
+            print_status(format!("Rebuilt '{}' from its compose file", name));
+            servers.insert(name, info);
+        }
+    }
+    ServerConfig { servers }
+}
+
+/// Reverses `build_compose_config` as far as the compose file allows
+fn server_info_from_compose(name: &str, dir: &Path) -> Option<ServerInfo> {
+    let content = fs::read_to_string(dir.join("docker-compose.yml")).ok()?;
+    let compose: ComposeConfig = serde_yaml::from_str(&content).ok()?;
+    let service = compose.services.get(name)?;
+    let env: BTreeMap<&str, &str> = service.environment.iter().filter_map(|e| e.split_once('=')).collect();
+
+    let version = env.get("VERSION").copied().unwrap_or("LATEST").to_string();
+    let server_type = env.get("TYPE").copied().unwrap_or("VANILLA").to_string();
+    let mod_loader = matches!(server_type.as_str(), "FORGE" | "FABRIC").then(|| server_type.clone());
+    let java_version = service
+        .image
+        .rsplit_once(":java")
+        .and_then(|(_, java)| java.parse().ok())
+        .filter(|java| *java != infer_java_version(&version));
+    let created_at = fs::metadata(dir)
+        .and_then(|m| m.modified())
+        .map_or_else(|_| chrono::Utc::now(), chrono::DateTime::from);
+
+    Some(ServerInfo {
+        port: service.ports.first()?.split(':').next()?.to_string(),
+        memory: env.get("MEMORY").copied().unwrap_or("2G").to_string(),
+        data_path: config_dir().join(name).to_string_lossy().to_string(),
+        mod_loader_version: env
+            .get("FORGE_VERSION")
+            .or_else(|| env.get("FABRIC_LOADER_VERSION"))
+            .map(|v| v.to_string()),
+        mod_loader,
+        java_args: env.get("JVM_OPTS").map(|v| v.to_string()),
+        java_version,
+        uid: env.get("UID").and_then(|v| v.parse().ok()),
+        gid: env.get("GID").and_then(|v| v.parse().ok()),
+        read_only: service.read_only,
+        restart_policy: Some(service.restart.clone()),
+        extra_env: env
+            .iter()
+            .filter(|(key, _)| {
+                !MANAGED_ENV.contains(key) && !matches!(**key, "FORGE_VERSION" | "FABRIC_LOADER_VERSION")
+            })
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        companions: compose
+            .services
+            .values()
+            .flat_map(|s| &s.profiles)
+            .filter_map(|p| Companion::from_str(p, true).ok())
+            .collect(),
+        resource_pack: env.get("RESOURCE_PACK").map(|v| v.to_string()),
+        resource_pack_sha1: env.get("RESOURCE_PACK_SHA1").map(|v| v.to_string()),
+        resource_pack_enforce: env.get("RESOURCE_PACK_ENFORCE") == Some(&"TRUE"),
+        max_tick_time: env.get("MAX_TICK_TIME").and_then(|v| v.parse().ok()),
+        level_type: env.get("LEVEL_TYPE").and_then(|v| LevelType::from_property(v)),
+        generator_settings: env.get("GENERATOR_SETTINGS").map(|v| v.to_string()),
+        image_digest: service.image.split_once('@').map(|(_, digest)| digest.to_string()),
+        resource_pack_port: compose
+            .services
+            .get(&format!("{}-resourcepack", name))
+            .and_then(|s| s.ports.first()?.split(':').next()?.parse().ok()),
+        version,
+        server_type,
+        created_at,
+        ..Default::default()
+    })
+}
+
+fn save_server_config(config: &ServerConfig) -> Result<()> {
+    let config_path = config_dir().join(CONFIG_FILE);
+    let content = serde_json::to_string_pretty(config)?;
+    fs::write(config_path, content)?;
+    Ok(())
+}
+
+/// Compose files written by older versions could list the same environment variable twice
+/// (notably `TYPE=`), which leaves the effective value up to Docker. Rewrites those files with
+/// one entry per variable.
+fn repair_duplicate_environment() -> Result<()> {
+    let config = load_server_config()?;
+    let mut repaired = Vec::new();
+
+    for (name, info) in &config.servers {
+        let compose_path = info.compose_file();
+        let Ok(content) = fs::read_to_string(&compose_path) else {
+            continue;
+        };
+        // Hand-edited files that no longer match our schema are left alone
+        let Ok(mut compose) = serde_yaml::from_str::<ComposeConfig>(&content) else {
+            continue;
+        };
+
+        let preferred_type = info.mod_loader.as_deref().unwrap_or(&info.server_type);
+        let mut changed = false;
+        for service in compose.services.values_mut() {
+            if let Some(environment) = dedupe_environment(&service.environment, preferred_type) {
+                service.environment = environment;
+                changed = true;
+            }
+        }
+
+        if changed {
+            fs::write(&compose_path, serde_yaml::to_string(&compose)?)?;
+            repaired.push(name.as_str());
+        }
+    }
+
+    if !repaired.is_empty() {
+        repaired.sort();
+        print_status(format!("Repaired duplicate environment entries for: {}", repaired.join(", ")).yellow());
+    }
+    Ok(())
+}
+
+/// Returns the environment with one entry per key, or `None` if there were no duplicates.
+/// For `TYPE` the entry matching `preferred_type` wins, otherwise the last entry does.
+fn dedupe_environment(environment: &[String], preferred_type: &str) -> Option<Vec<String>> {
+    let key_of = |entry: &str| entry.split('=').next().unwrap_or_default().to_string();
+
+    let mut keep: Vec<&String> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for entry in environment {
+        let key = key_of(entry);
+        match positions.get(&key) {
+            Some(&pos) => {
+                let keeps_current = key == "TYPE" && *keep[pos] == format!("TYPE={}", preferred_type);
+                if !keeps_current {
+                    keep[pos] = entry;
+                }
+            }
+            None => {
+                positions.insert(key, keep.len());
+                keep.push(entry);
+            }
+        }
+    }
+
+    (keep.len() != environment.len()).then(|| keep.into_iter().cloned().collect())
+}
+
+fn load_settings() -> Result<Settings> {
+    let settings_path = config_dir().join(SETTINGS_FILE);
+    if settings_path.exists() {
+        Ok(toml::from_str(&fs::read_to_string(settings_path)?)?)
+    } else {
+        Ok(Settings::default())
+    }
+}
+
+fn save_settings(settings: &Settings) -> Result<()> {
+    let settings_path = config_dir().join(SETTINGS_FILE);
+    fs::write(settings_path, toml::to_string_pretty(settings)?)?;
+    Ok(())
+}
+
+fn check_docker_installed() -> bool {
+    ProcessCommand::new("docker")
+        .arg("--version")
+        .output()
+        .is_ok()
+}
+
+fn install_docker() -> Result<()> {
+    print_status("\nDocker not found! Installing Docker...".yellow());
+
+    #[cfg(target_os = "linux")]
+    {
+        let pb = create_spinner("Installing Docker on Linux...");
+        let output = ProcessCommand::new("sh")
+            .arg("-c")
+            .arg("curl -fsSL https://get.docker.com | sh")
+            .output()?;
+
+        if !output.status.success() {
+            return Err(ServerError::DockerCommandFailed(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+        pb.finish_with_message("Docker engine installed");
+        ensure_docker_group()?;
+    }
+
+    // `brew install docker` only gets the CLI, a daemon has to come from Docker Desktop or colima
+    #[cfg(target_os = "macos")]
+    {
+        require_interactive("install Docker Desktop or colima and start it, then run this again")?;
+        let options = [
+            "Docker Desktop (brew install --cask docker)",
+            "colima, a lightweight command line VM (brew install colima docker)",
+            "I'll install Docker myself",
+        ];
+        let choice = Select::new()
+            .with_prompt("Docker needs a daemon on macOS. How would you like to install it?")
+            .items(&options)
+            .default(0)
+            .interact()?;
+        let commands: &[&[&str]] = match choice {
+            0 => &[&["brew", "install", "--cask", "docker"], &["open", "-a", "Docker"]],
+            1 => &[&["brew", "install", "colima", "docker", "docker-compose"], &["colima", "start"]],
+            _ => {
+                print_status("Get Docker Desktop from https://www.docker.com/products/docker-desktop");
+                print_status("or run 'brew install colima docker docker-compose && colima start'.");
+                if !Confirm::new().with_prompt("Is Docker installed and running?").interact()? {
+                    return Err(ServerError::DockerNotInstalled);
+                }
+                &[]
+            }
+        };
+        for command in commands {
+            let pb = create_spinner(&format!("Running {}...", command.join(" ")));
+            let output = ProcessCommand::new(command[0]).args(&command[1..]).output()?;
+            if !output.status.success() {
+                return Err(ServerError::DockerCommandFailed(
+                    String::from_utf8_lossy(&output.stderr).to_string(),
+                ));
+            }
+            pb.finish_and_clear();
+        }
+        if choice == 0 {
+            print_status("Docker Desktop is starting, finish its first-run setup before continuing.".yellow());
+            Confirm::new().with_prompt("Is Docker Desktop running?").interact()?;
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        require_interactive("install and start Docker Desktop, then run this again")?;
+        print_status("\nPlease download and install Docker Desktop from:".yellow());
+        print_status("https://www.docker.com/products/docker-desktop");
+        if !Confirm::new().with_prompt("Have you installed and started Docker Desktop?").interact()? {
+            return Err(ServerError::DockerNotInstalled);
+        }
+    }
+
+    check_docker_daemon()?;
+    print_status("Docker installed successfully!".green());
+    Ok(())
+}
+
+/// Confirms the CLI can actually reach a daemon, which an install alone doesn't guarantee
+fn check_docker_daemon() -> Result<()> {
+    let output = ProcessCommand::new("docker").arg("info").output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    match docker_error(&output.stderr) {
+        ServerError::DockerCommandFailed(message) => Err(ServerError::DockerDaemonUnavailable(message.trim().to_string())),
+        error => Err(error),
+    }
+}
+
+/// The Linux install script doesn't add the user to the docker group, without which every
+/// docker call needs sudo
+#[cfg(target_os = "linux")]
+fn ensure_docker_group() -> Result<()> {
+    if is_root() {
+        return Ok(());
+    }
+    let Ok(user) = std::env::var("USER") else {
+        return Ok(());
+    };
+    let in_group = |user: &str| {
+        ProcessCommand::new("id")
+            .args(["-nG", user])
+            .output()
+            .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).split_whitespace().any(|g| g == "docker"))
+    };
+    if in_group(&user) {
+        return Ok(());
+    }
+
+    print_status(format!("Adding '{}' to the docker group...", user));
+    let status = ProcessCommand::new("sudo").args(["usermod", "-aG", "docker", &user]).status()?;
+    if !status.success() || !in_group(&user) {
+        print_status(format!("Couldn't add '{}' to the docker group, run 'sudo usermod -aG docker {}' yourself.", user, user).yellow());
+    } else {
+        print_status("Log out and back in (or run 'newgrp docker') for the group change to apply.".yellow());
+    }
+    Ok(())
+}
+
+fn cron_timestamp() -> String {
+    chrono::Local::now().format("[%Y-%m-%d %H:%M:%S]").to_string()
+}
+
+/// Prints a status line, timestamped under `--cron` like the spinner messages and on stderr while
+/// a backup streams to stdout
+fn print_status(line: impl std::fmt::Display) {
+    if STREAMING.load(Ordering::Relaxed) {
+        eprintln!("{}", line);
+    } else if CRON_MODE.load(Ordering::Relaxed) {
+        println!("{} {}", cron_timestamp(), line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Draw target for `--cron` that turns each new spinner message into a timestamped line
+#[derive(Debug, Default)]
+struct CronLog {
+    last: Mutex<String>,
+}
+
+impl TermLike for CronLog {
+    fn width(&self) -> u16 {
+        u16::MAX
+    }
+
+    fn move_cursor_up(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_down(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_right(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_left(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_line(&self, s: &str) -> io::Result<()> {
+        self.write_str(s)
+    }
+
+    fn write_str(&self, s: &str) -> io::Result<()> {
+        let line = s.trim();
+        let mut last = self.last.lock().unwrap();
+        if !line.is_empty() && *last != line {
+            println!("{} {}", cron_timestamp(), line);
+            *last = line.to_string();
+        }
+        Ok(())
+    }
+
+    fn clear_line(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+fn create_spinner(msg: &str) -> ProgressBar {
+    if CRON_MODE.load(Ordering::Relaxed) {
+        let pb = ProgressBar::with_draw_target(None, ProgressDrawTarget::term_like(Box::new(CronLog::default())));
+        pb.set_style(ProgressStyle::default_spinner().template("{msg}").unwrap());
+        pb.set_message(msg.to_string());
+        return pb;
+    }
+    // Every spinner shares one display, so the ones of parallel workers each get their own line
+    static SPINNERS: OnceLock<MultiProgress> = OnceLock::new();
+    let pb = SPINNERS.get_or_init(MultiProgress::new).add(ProgressBar::new_spinner());
+    pb.set_style(
         ProgressStyle::default_spinner()
             .template("{spinner:.green} {msg}")
             .unwrap(),
     );
-    pb.set_message(msg.to_string());
-    pb.enable_steady_tick(Duration::from_millis(100));
-    pb
+    pb.set_message(msg.to_string());
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb
+}
+
+/// Trimmed stdout of a command, or `None` when it's missing or fails
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = ProcessCommand::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|out| !out.is_empty())
+}
+
+fn show_context(json: bool) -> Result<()> {
+    let config = load_server_config()?;
+    let running = running_containers().unwrap_or_default();
+    let running_servers = config.servers.keys().filter(|name| running.contains(&format!("mc-{}", name))).count();
+    let config_dir = config_dir();
+    let docker_host = std::env::var("DOCKER_HOST").ok();
+
+    let context = serde_json::json!({
+        "blockops": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "cpus": default_parallelism(),
+        "memory": host_memory_bytes(),
+        "user": current_user_ids().map(|(uid, gid)| format!("{}:{}", uid, gid)),
+        "config_dir": config_dir,
+        "docker_client": command_output("docker", &["version", "--format", "{{.Client.Version}}"]),
+        // Empty when the daemon can't be reached, which is worth knowing on its own
+        "docker_server": command_output("docker", &["version", "--format", "{{.Server.Version}}"]),
+        "docker_context": command_output("docker", &["context", "show"]),
+        "docker_host": docker_host,
+        "compose": command_output("docker-compose", &["version", "--short"]),
+        "compose_plugin": command_output("docker", &["compose", "version", "--short"]),
+        "compose_in_use": ComposeVariant::detect().label(),
+        "servers": config.servers.len(),
+        "running": running_servers,
+    });
+    if json {
+        println!("{}", serde_json::to_string_pretty(&context).unwrap_or_default());
+        return Ok(());
+    }
+
+    let show = |value: &serde_json::Value| match value {
+        serde_json::Value::Null => "unavailable".bright_black().to_string(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    println!("\n{}", "BlockOps context".bright_cyan());
+    println!("{}", "================".bright_cyan());
+    println!("BlockOps: {}", show(&context["blockops"]));
+    println!(
+        "OS: {} {}, {} CPU(s), {} memory",
+        show(&context["os"]),
+        show(&context["arch"]),
+        show(&context["cpus"]),
+        host_memory_bytes().map_or("unknown".to_string(), format_size)
+    );
+    println!("User: {}", show(&context["user"]));
+    println!("Config dir: {}", config_dir.display());
+    println!("Docker client: {}", show(&context["docker_client"]));
+    println!("Docker server: {}", show(&context["docker_server"]));
+    println!(
+        "Docker context: {}{}",
+        show(&context["docker_context"]),
+        context["docker_host"].as_str().map_or(String::new(), |host| format!(" (DOCKER_HOST={})", host))
+    );
+    println!("docker-compose: {}", show(&context["compose"]));
+    println!("docker compose plugin: {}", show(&context["compose_plugin"]));
+    println!("Compose in use: {}", show(&context["compose_in_use"]));
+    println!("Servers: {} configured, {} running", config.servers.len(), running_servers);
+    Ok(())
+}
+
+fn list_versions(json: bool) {
+    if json {
+        let catalog = serde_json::json!({
+            "server_types": SERVER_TYPES
+                .iter()
+                .zip(SERVER_TYPE_DESCRIPTIONS)
+                .map(|(name, description)| serde_json::json!({ "name": name, "description": description }))
+                .collect::<Vec<_>>(),
+            "versions": VERSION_EXAMPLES
+                .iter()
+                .map(|(version, description)| serde_json::json!({ "version": version, "description": description }))
+                .collect::<Vec<_>>(),
+            "mod_loaders": MOD_LOADER_EXAMPLES
+                .iter()
+                .map(|(loader, versions)| serde_json::json!({ "type": loader, "versions": versions }))
+                .collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&catalog).unwrap_or_default());
+        return;
+    }
+
+    println!("\n{}", "Available Minecraft Server Types:".bright_cyan());
+    println!("{}", "============================".bright_cyan());
+    for (name, description) in SERVER_TYPES.iter().zip(SERVER_TYPE_DESCRIPTIONS) {
+        println!("- {}: {}", name.bright_green(), description);
+    }
+
+    println!("\n{}", "Version Format Examples:".bright_yellow());
+    for (version, description) in VERSION_EXAMPLES {
+        println!("- {} ({})", version, description);
+    }
+    
+    println!("\n{}", "Mod Loader Examples:".bright_yellow());
+    for (loader, [keyword, example]) in MOD_LOADER_EXAMPLES {
+        let (first, rest) = loader.split_at(1);
+        println!("- {}{}: {} or specific version (e.g., {})", first, rest.to_lowercase(), keyword, example);
+    }
+}
+
+fn create_server(start: bool, no_start: bool, options: &CreateOptions) -> Result<()> {
+    options.validate()?;
+    require_interactive("create asks for the server's settings, use batch-create to create servers from flags")?;
+    let settings = load_settings()?;
+    let defaults = &settings.defaults;
+    println!("\n{}", "Let's configure a new Minecraft server!".bright_cyan());
+
+    // Server Name
+    let server_name: String = Input::new()
+        .with_prompt("Enter server name (alphanumeric only)")
+        .validate_with(|input: &String| validate_server_name(input).map_err(|e| e.to_string()))
+        .interact_text()?;
+
+    let mut config = load_server_config()?;
+    if config.servers.contains_key(&server_name) {
+        return Err(ServerError::ServerExists(server_name));
+    }
+
+    // Server Type Selection
+    let server_type_idx = Select::new()
+        .with_prompt("Select server type")
+        .items(&SERVER_TYPES)
+        .default(SERVER_TYPES.iter().position(|t| *t == defaults.server_type).unwrap_or(0))
+        .interact()?;
+    
+    let server_type = SERVER_TYPES[server_type_idx];
+
+    // Version Input
+    let version: String = Input::new()
+        .with_prompt("Enter Minecraft version (e.g., LATEST, 1.20.2, SNAPSHOT)")
+        .default(defaults.version.clone())
+        .interact_text()?;
+
+    // World Type
+    let level_type = match options.level_type {
+        Some(level_type) => level_type,
+        None => {
+            let types = LevelType::value_variants();
+            let idx = Select::new()
+                .with_prompt("Select world type")
+                .items(&types.iter().map(|t| t.name()).collect::<Vec<_>>())
+                .default(0)
+                .interact()?;
+            types[idx]
+        }
+    };
+
+    // Mod Loader Configuration
+    let (mod_loader, mod_loader_version) = match server_type {
+        "FORGE" => {
+            let version = Input::new()
+                .with_prompt("Enter Forge version (e.g., 47.1.0, RECOMMENDED)")
+                .default("RECOMMENDED".into())
+                .interact_text()?;
+            (Some("FORGE".to_string()), Some(version))
+        },
+        "FABRIC" => {
+            let version = Input::new()
+                .with_prompt("Enter Fabric Loader version (e.g., 0.14.21, LATEST)")
+                .default("LATEST".into())
+                .interact_text()?;
+            (Some("FABRIC".to_string()), Some(version))
+        },
+        _ => (None, None)
+    };
+
+    // Memory Configuration
+    let memory: String = Input::new()
+        .with_prompt("Enter server memory (e.g., 2G, 4G, 50%)")
+        .default(defaults.memory.clone())
+        .validate_with(|input: &String| resolve_memory(input).map(|_| ()).map_err(|e| e.to_string()))
+        .interact_text()?;
+    let (memory, memory_percent) = resolve_memory(&memory)?;
+
+    // Server Port
+    let port: String = Input::new()
+        .with_prompt("Enter server port")
+        .default(next_free_port(DEFAULT_PORT, &config)?.unwrap_or(DEFAULT_PORT).to_string())
+        .validate_with(|input: &String| check_port_available(input, &config).map_err(|e| e.to_string()))
+        .interact_text()?;
+
+    // Java Arguments (Optional)
+    let java_args: Option<String> = if Confirm::new()
+        .with_prompt("Would you like to customize Java arguments?")
+        .interact()?
+    {
+        Some(
+            Input::new()
+                .with_prompt("Enter custom Java arguments")
+                .default("-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200".into())
+                .interact_text()?,
+        )
+    } else {
+        None
+    };
+
+    ensure_loader_compatible(mod_loader.as_deref(), mod_loader_version.as_deref(), &version, false)?;
+
+    // EULA Agreement
+    if !confirm_eula()? {
+        println!("{}", "EULA must be accepted to continue.".red());
+        return Ok(());
+    }
+
+    let mut info = ServerInfo {
+        version,
+        port,
+        memory,
+        memory_percent,
+        data_path: config_dir().join(&server_name).to_string_lossy().to_string(),
+        server_type: server_type.to_string(),
+        mod_loader,
+        mod_loader_version,
+        java_args,
+        level_type: Some(level_type),
+        created_at: chrono::Utc::now(),
+        ..Default::default()
+    };
+    options.apply(&server_name, &mut info, &settings, &config)?;
+    if options.resource_pack.is_none()
+        && Confirm::new()
+            .with_prompt("Would you like to set a resource pack?")
+            .default(false)
+            .interact()?
+    {
+        prompt_resource_pack(&mut info, &config)?;
+    }
+    check_free_space(config_dir(), MIN_SERVER_SPACE)?;
+    provision_server(&mut config, &server_name, info)?;
+    save_server_config(&config)?;
+
+    println!("{}", "\nServer configuration saved successfully!".green());
+    // Only ask when someone is there to answer, scripts default to not starting
+    let start_now = start
+        || (!no_start
+            && is_interactive()
+            && Confirm::new()
+                .with_prompt("Would you like to start the server now?")
+                .interact()?);
+    if start_now {
+        start_servers(Some(server_name), None, &ComposeArgs::default())?;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn batch_create_servers(
+    base_name: &str,
+    count: usize,
+    start_port: u16,
+    server_type: Option<String>,
+    version: Option<String>,
+    memory: Option<String>,
+    loader_version: Option<String>,
+    java_args: Option<String>,
+    accept_eula: bool,
+    options: &CreateOptions,
+) -> Result<()> {
+    validate_server_name(base_name)?;
+    options.validate()?;
+    let settings = load_settings()?;
+    let server_type = server_type.unwrap_or_else(|| settings.defaults.server_type.clone()).to_uppercase();
+    let version = version.unwrap_or_else(|| settings.defaults.version.clone());
+    let memory = memory.unwrap_or_else(|| settings.defaults.memory.clone());
+    if !SERVER_TYPES.contains(&server_type.as_str()) {
+        return Err(ServerError::InvalidServerType(server_type));
+    }
+    if count == 0 {
+        println!("{}", "Nothing to create: count is 0.".yellow());
+        return Ok(());
+    }
+
+    let (memory, memory_percent) = resolve_memory(&memory)?;
+    let mut config = load_server_config()?;
+
+    // Check every name and port up front so a conflict doesn't leave a half-created batch
+    let mut planned = Vec::with_capacity(count);
+    for i in 0..count {
+        let name = format!("{}{}", base_name, i + 1);
+        if config.servers.contains_key(&name) {
+            return Err(ServerError::ServerExists(name));
+        }
+        let port = u32::from(start_port) + i as u32;
+        if port > u32::from(u16::MAX) {
+            return Err(ServerError::InvalidPort(port.to_string()));
+        }
+        let port = port.to_string();
+        check_port_available(&port, &config)?;
+        planned.push((name, port));
+    }
+    // Every server of the batch needs its own share of the space
+    check_free_space(config_dir(), MIN_SERVER_SPACE * count as u64)?;
+
+    let (mod_loader, mod_loader_version) = match server_type.as_str() {
+        "FORGE" => (Some("FORGE".to_string()), Some(loader_version.unwrap_or_else(|| "RECOMMENDED".into()))),
+        "FABRIC" => (Some("FABRIC".to_string()), Some(loader_version.unwrap_or_else(|| "LATEST".into()))),
+        _ => (None, None),
+    };
+    ensure_loader_compatible(mod_loader.as_deref(), mod_loader_version.as_deref(), &version, false)?;
+
+    if !accept_eula && !confirm_eula()? {
+        println!("{}", "EULA must be accepted to continue.".red());
+        return Ok(());
+    }
+
+    println!("\n{}", format!("Creating {} servers:", count).bright_cyan());
+    for (name, port) in planned {
+        let mut info = ServerInfo {
+            version: version.clone(),
+            port: port.clone(),
+            memory: memory.clone(),
+            memory_percent,
+            data_path: config_dir().join(&name).to_string_lossy().to_string(),
+            server_type: server_type.clone(),
+            mod_loader: mod_loader.clone(),
+            mod_loader_version: mod_loader_version.clone(),
+            java_args: java_args.clone(),
+            created_at: chrono::Utc::now(),
+            ..Default::default()
+        };
+        options.apply(&name, &mut info, &settings, &config)?;
+        provision_server(&mut config, &name, info)?;
+        save_server_config(&config)?;
+        println!("- {} on port {}", name.bright_green(), port);
+    }
+
+    println!("{}", "\nBatch created successfully!".green());
+    Ok(())
+}
+
+impl CreateOptions {
+    fn validate(&self) -> Result<()> {
+        if let Some(java) = self.java {
+            validate_java_version(java)?;
+        }
+        if let Some(size) = &self.log_max_size {
+            validate_log_size(size)?;
+        }
+        if let Some(files) = self.log_max_files {
+            validate_log_files(files)?;
+        }
+        if let Some(ms) = self.watchdog {
+            validate_max_tick_time(ms)?;
+        }
+        if let Some(json) = &self.generator_settings {
+            validate_generator_settings(json)?;
+        }
+        if let Some(digest) = &self.image_digest {
+            parse_image_digest(digest)?;
+        }
+        if let Some(pack) = &self.resource_pack {
+            if !is_url(pack) && !Path::new(pack).is_file() {
+                return Err(ServerError::InvalidValue(
+                    "resource_pack".to_string(),
+                    format!("'{}' is neither an http(s) URL nor an existing file", pack),
+                ));
+            }
+            if !is_url(pack) && self.resource_pack_host.is_none() {
+                return Err(ServerError::InvalidValue(
+                    "resource_pack".to_string(),
+                    "a local pack needs --resource-pack-host, the address players reach this machine at".to_string(),
+                ));
+            }
+        }
+        if let Some(sha1) = &self.resource_pack_sha1 {
+            validate_sha1(sha1)?;
+        }
+        if self.managed_backups {
+            validate_backup_interval(&self.backup_interval)?;
+        }
+        if let Some(address) = self.bind {
+            validate_bind_address(address)?;
+        }
+        if !self.depends_on.is_empty() {
+            let config = load_server_config()?;
+            if let Some(missing) = self.depends_on.iter().find(|d| !config.servers.contains_key(*d)) {
+                return Err(ServerError::ServerNotFound(missing.clone()));
+            }
+        }
+        if let Some(source) = &self.inherit_env_from {
+            if !load_server_config()?.servers.contains_key(source) {
+                return Err(ServerError::ServerNotFound(source.clone()));
+            }
+        }
+        for var in &self.env_from_host {
+            validate_env_key(var)?;
+            if std::env::var_os(var).is_none() {
+                return Err(ServerError::HostEnvMissing(var.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&self, name: &str, info: &mut ServerInfo, settings: &Settings, config: &ServerConfig) -> Result<()> {
+        info.java_version = self.java.or(settings.defaults.java);
+        info.restart_policy = Some(settings.defaults.restart_policy.clone());
+        let (uid, gid) = current_user_ids().unzip();
+        info.uid = self.uid.or(uid);
+        info.gid = self.gid.or(gid);
+        info.read_only = self.read_only;
+        info.auto_backup = self.auto_backup;
+        info.log_max_size = self.log_max_size.clone();
+        info.log_max_files = self.log_max_files;
+        info.max_tick_time = self.watchdog;
+        info.healthcheck_cmd = self.healthcheck_cmd.clone();
+        info.tuning = self.tuning;
+        // The flag wins over a prompted world type. Normal is the image's default, so it isn't written out
+        info.level_type = self.level_type.or(info.level_type).filter(|l| *l != LevelType::Normal);
+        info.generator_settings = self.generator_settings.clone();
+        validate_level_type(info)?;
+        info.image_digest = self.image_digest.as_deref().map(parse_image_digest).transpose()?;
+        info.bind_address = self.bind;
+        info.depends_on = self.depends_on.clone();
+        for companion in &self.companions {
+            if !info.companions.contains(companion) {
+                info.companions.push(*companion);
+            }
+        }
+        // Checked like the game port. A taken default moves to the next free port, so servers of a
+        // batch each get their own; `map --port` changes it later.
+        for companion in info.companions.clone() {
+            let wanted = info.companion_port(companion);
+            let own_ports: Vec<u16> = info
+                .port
+                .parse()
+                .into_iter()
+                .chain(info.companions.iter().filter(|c| **c != companion).map(|c| info.companion_port(*c)))
+                .collect();
+            let port = (wanted..=u16::MAX)
+                .find(|&port| !own_ports.contains(&port) && check_port_free(port, config, &settings.ports.reserved).is_ok())
+                .ok_or_else(|| ServerError::PortUnavailable(wanted.to_string()))?;
+            if port != wanted {
+                println!("Port {} is taken, the {} map of '{}' uses {}.", wanted, companion.profile(), name, port);
+                info.companion_ports.insert(companion.profile().to_string(), port);
+            }
+        }
+        if let Some(source) = &self.inherit_env_from {
+            inherit_env(name, info, source, config)?;
+        }
+        for var in &self.env_from_host {
+            if let Ok(value) = std::env::var(var) {
+                info.extra_env.insert(var.clone(), store_secret(&format!("{}.env.{}", name, var), &value));
+            }
+        }
+        if let Some(pack) = &self.resource_pack {
+            configure_resource_pack(
+                info,
+                config,
+                pack,
+                self.resource_pack_sha1.as_deref(),
+                self.resource_pack_enforce,
+                self.resource_pack_host.as_deref(),
+            )?;
+        }
+        if let Some(dir) = &self.output_dir {
+            let compose_file = std::path::absolute(dir)?.join(name).join("docker-compose.yml");
+            info.compose_path = Some(compose_file.to_string_lossy().to_string());
+        }
+        if self.managed_backups {
+            info.managed_backups = Some(ManagedBackups {
+                interval: self.backup_interval.clone(),
+                prune_days: self.backup_prune_days,
+                rcon_password: store_secret(&format!("{}.rcon_password", name), &random_password()?),
+            });
+            // Docker would create a missing bind mount source owned by root
+            fs::create_dir_all(managed_backup_dir(name))?;
+        }
+        Ok(())
+    }
+}
+
+/// Copies `source`'s extra environment into `name`'s `info`, leaving out values that have to differ
+/// per server (ports, the server name) and secrets. Keyring values get an entry of their own.
+fn inherit_env(name: &str, info: &mut ServerInfo, source: &str, config: &ServerConfig) -> Result<()> {
+    let source_info = config
+        .servers
+        .get(source)
+        .ok_or_else(|| ServerError::ServerNotFound(source.to_string()))?;
+
+    let (skipped, candidates): (Vec<_>, Vec<_>) = source_info
+        .extra_env
+        .iter()
+        .partition(|(key, _)| key.contains("PORT") || *key == "SERVER_NAME" || is_secret_key(key));
+    let mut inherited = Vec::new();
+    let mut unavailable = Vec::new();
+    for (key, value) in candidates {
+        // Sharing the placeholder would tie both servers to the source's keyring entry
+        let value = match secrets::account(value) {
+            Some(_) => match resolve_secret(value) {
+                Ok(secret) => store_secret(&format!("{}.env.{}", name, key), &secret),
+                Err(_) => {
+                    unavailable.push(key.as_str());
+                    continue;
+                }
+            },
+            None => value.clone(),
+        };
+        info.extra_env.insert(key.clone(), value);
+        inherited.push(key.as_str());
+    }
+
+    if inherited.is_empty() {
+        println!("{}", format!("'{}' has no environment variables to inherit.", source).yellow());
+    } else {
+        println!("Inherited from '{}': {}", source, inherited.join(", "));
+    }
+    if !skipped.is_empty() {
+        let names: Vec<&str> = skipped.iter().map(|(key, _)| key.as_str()).collect();
+        println!("Not inherited (server-specific or secret): {}", names.join(", "));
+    }
+    if !unavailable.is_empty() {
+        println!("Not inherited (keyring entry unavailable): {}", unavailable.join(", "));
+    }
+    Ok(())
+}
+
+/// Real user and group id of the invoking user, so container-created files stay editable
+fn current_user_ids() -> Option<(u32, u32)> {
+    #[cfg(unix)]
+    {
+        // SAFETY: getuid and getgid can't fail and have no preconditions
+        Some(unsafe { (libc::getuid(), libc::getgid()) })
+    }
+
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Whether the tool runs with root's effective user id
+fn is_root() -> bool {
+    #[cfg(unix)]
+    {
+        // SAFETY: geteuid can't fail and has no preconditions
+        unsafe { libc::geteuid() == 0 }
+    }
+
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
+
+fn validate_server_name(name: &str) -> Result<()> {
+    if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        Ok(())
+    } else {
+        Err(ServerError::InvalidServerName(name.to_string()))
+    }
+}
+
+/// Server whose game, companion or resource pack port this is
+fn port_owner(port: u16, config: &ServerConfig) -> Option<&String> {
+    config.servers.iter().find_map(|(name, info)| {
+        (info.port == port.to_string()
+            || info.companions.iter().any(|c| info.companion_port(*c) == port)
+            || info.resource_pack_port == Some(port))
+        .then_some(name)
+    })
+}
+
+/// First port from `start` up that's neither used, reserved nor held by another process
+fn next_free_port(start: u16, config: &ServerConfig) -> Result<Option<u16>> {
+    let reserved = load_settings()?.ports.reserved;
+    Ok((start..=u16::MAX).find(|&port| check_port_free(port, config, &reserved).is_ok()))
+}
+
+fn check_port_available(port: &str, config: &ServerConfig) -> Result<()> {
+    let port_num: u16 = port
+        .parse()
+        .map_err(|_| ServerError::InvalidPort(port.to_string()))?;
+    if port_num == 0 {
+        return Err(ServerError::InvalidPort(port.to_string()));
+    }
+    check_port_free(port_num, config, &load_settings()?.ports.reserved)
+}
+
+/// The checks of [`check_port_available`] past parsing, with the reserved ports loaded once by the caller
+fn check_port_free(port: u16, config: &ServerConfig, reserved: &BTreeSet<u16>) -> Result<()> {
+    if let Some(owner) = port_owner(port, config) {
+        return Err(ServerError::PortConflict(port.to_string(), owner.clone()));
+    }
+    if reserved.contains(&port) {
+        return Err(ServerError::PortReserved(port.to_string()));
+    }
+
+    // Catch ports held by anything else running on this host
+    if TcpListener::bind(("0.0.0.0", port)).is_err() {
+        return Err(ServerError::PortUnavailable(port.to_string()));
+    }
+    Ok(())
+}
+
+/// Accepts IPv6 addresses with or without the brackets used in URLs
+fn parse_bind_address(value: &str) -> std::result::Result<IpAddr, String> {
+    let address = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')).unwrap_or(value);
+    address
+        .parse()
+        .map_err(|_| format!("'{}' is not an IPv4 or IPv6 address", value))
+}
+
+/// Docker can only publish on addresses assigned to one of this host's interfaces
+fn validate_bind_address(address: IpAddr) -> Result<()> {
+    if TcpListener::bind((address, 0)).is_err() {
+        return Err(ServerError::InvalidValue(
+            "bind_address".to_string(),
+            format!("{} isn't an address of this host", address),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that `key` can be used as an extra environment variable
+fn validate_env_key(key: &str) -> Result<()> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') {
+        return Err(ServerError::InvalidValue(
+            format!("env.{}", key),
+            "environment variable names must be uppercase letters, digits and underscores".to_string(),
+        ));
+    }
+    if MANAGED_ENV.contains(&key) {
+        return Err(ServerError::InvalidValue(
+            format!("env.{}", key),
+            "this variable is managed by the tool, set the matching server setting instead".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_restart_policy(policy: &str) -> Result<()> {
+    if RESTART_POLICIES.contains(&policy) {
+        Ok(())
+    } else {
+        Err(ServerError::InvalidValue(
+            "restart_policy".to_string(),
+            format!("'{}' is not one of {}", policy, RESTART_POLICIES.join(", ")),
+        ))
+    }
+}
+
+/// mc-backup sleeps for BACKUP_INTERVAL between runs, so it takes `sleep`'s `<n><unit>` format
+fn validate_backup_interval(interval: &str) -> Result<()> {
+    let valid = interval
+        .strip_suffix(['s', 'm', 'h', 'd'])
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) && n != "0");
+    if valid {
+        Ok(())
+    } else {
+        Err(ServerError::InvalidValue(
+            "backup_interval".to_string(),
+            format!("'{}' should be a duration like 30m, 6h or 1d", interval),
+        ))
+    }
+}
+
+/// 32 hex characters from the OS random number generator
+fn random_password() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Docker's json-file sizes: a number with a k, m or g unit
+fn validate_log_size(size: &str) -> Result<()> {
+    let valid = size
+        .strip_suffix(['k', 'm', 'g'])
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) && n != "0");
+    if valid {
+        Ok(())
+    } else {
+        Err(ServerError::InvalidValue(
+            "log_max_size".to_string(),
+            format!("'{}' should be a size like 10m or 1g", size),
+        ))
+    }
+}
+
+/// The level type has to exist in the server's Minecraft version
+fn validate_level_type(info: &ServerInfo) -> Result<()> {
+    match info.level_type {
+        Some(level_type) if level_type.property(&info.version).is_none() => Err(ServerError::InvalidValue(
+            "level_type".to_string(),
+            format!("{} worlds need Minecraft 1.19 or newer, not {}", level_type.name(), info.version),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_generator_settings(json: &str) -> Result<()> {
+    match serde_json::from_str::<serde_json::Value>(json) {
+        Ok(serde_json::Value::Object(_)) => Ok(()),
+        Ok(_) => Err(ServerError::InvalidValue(
+            "generator_settings".to_string(),
+            "expected a JSON object".to_string(),
+        )),
+        Err(e) => Err(ServerError::InvalidValue("generator_settings".to_string(), format!("invalid JSON: {}", e))),
+    }
+}
+
+/// Digest part of a pinned image, given on its own or as a full `itzg/minecraft-server@` reference
+fn parse_image_digest(value: &str) -> Result<String> {
+    let digest = value.strip_prefix(SERVER_IMAGE).and_then(|rest| rest.strip_prefix('@')).unwrap_or(value);
+    let valid = digest
+        .strip_prefix("sha256:")
+        .is_some_and(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+    if !valid {
+        return Err(ServerError::InvalidValue(
+            "image_digest".to_string(),
+            format!("expected sha256: and 64 hex digits, as listed by 'docker images --digests {}'", SERVER_IMAGE),
+        ));
+    }
+    Ok(digest.to_lowercase())
+}
+
+fn validate_max_tick_time(ms: i64) -> Result<()> {
+    if ms != -1 && ms < MIN_MAX_TICK_TIME {
+        return Err(ServerError::InvalidValue(
+            "max_tick_time".to_string(),
+            format!("use -1 to turn the watchdog off, or at least {} ms", MIN_MAX_TICK_TIME),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_log_files(files: u32) -> Result<()> {
+    if files == 0 {
+        return Err(ServerError::InvalidValue(
+            "log_max_files".to_string(),
+            "at least one log file must be kept".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn is_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+fn validate_sha1(sha1: &str) -> Result<()> {
+    if sha1.len() == 40 && sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(ServerError::InvalidValue(
+            "resource_pack_sha1".to_string(),
+            format!("'{}' is not a 40 character hex SHA-1", sha1),
+        ))
+    }
+}
+
+/// Points the server at a resource pack. A local file is copied into the data directory and
+/// served by a small web server container on the first free port from `RESOURCE_PACK_PORT`.
+fn configure_resource_pack(
+    info: &mut ServerInfo,
+    config: &ServerConfig,
+    source: &str,
+    sha1: Option<&str>,
+    enforce: bool,
+    host: Option<&str>,
+) -> Result<()> {
+    info.resource_pack_enforce = enforce;
+    if is_url(source) {
+        info.resource_pack = Some(source.to_string());
+        info.resource_pack_sha1 = sha1.map(str::to_lowercase);
+        info.resource_pack_port = None;
+        return Ok(());
+    }
+
+    let host = host.ok_or_else(|| {
+        ServerError::InvalidValue("resource_pack".to_string(), "a local pack needs a host players can reach".to_string())
+    })?;
+    let data = fs::read(source)?;
+    let pack_dir = Path::new(&info.data_path).join(RESOURCE_PACK_DIR);
+    fs::create_dir_all(&pack_dir)?;
+    fs::write(pack_dir.join(RESOURCE_PACK_FILE), &data)?;
+
+    let port = next_free_port(RESOURCE_PACK_PORT, config)?
+        .ok_or_else(|| ServerError::PortUnavailable(RESOURCE_PACK_PORT.to_string()))?;
+    info.resource_pack = Some(format!("http://{}:{}/{}", host, port, RESOURCE_PACK_FILE));
+    info.resource_pack_sha1 = Some(sha1.map_or_else(|| sha1_smol::Sha1::from(&data).digest().to_string(), str::to_lowercase));
+    info.resource_pack_port = Some(port);
+    println!("{}", format!("Serving the resource pack at {}", info.resource_pack.as_deref().unwrap_or_default()).green());
+    Ok(())
+}
+
+fn prompt_resource_pack(info: &mut ServerInfo, config: &ServerConfig) -> Result<()> {
+    let source: String = Input::new()
+        .with_prompt("Resource pack URL or path to a local zip")
+        .validate_with(|input: &String| {
+            if is_url(input) || Path::new(input).is_file() {
+                Ok(())
+            } else {
+                Err("enter an http(s) URL or the path of an existing file")
+            }
+        })
+        .interact_text()?;
+    let host: Option<String> = if is_url(&source) {
+        None
+    } else {
+        Some(
+            Input::new()
+                .with_prompt("Address players reach this machine at")
+                .interact_text()?,
+        )
+    };
+    let sha1: String = Input::new()
+        .with_prompt("SHA-1 of the pack (leave empty to skip)")
+        .allow_empty(true)
+        .validate_with(|input: &String| {
+            if input.is_empty() {
+                Ok(())
+            } else {
+                validate_sha1(input).map_err(|e| e.to_string())
+            }
+        })
+        .interact_text()?;
+    let enforce = Confirm::new()
+        .with_prompt("Kick players who decline the pack?")
+        .default(false)
+        .interact()?;
+    configure_resource_pack(info, config, &source, (!sha1.is_empty()).then_some(sha1.as_str()), enforce, host.as_deref())
+}
+
+fn validate_java_version(java: u8) -> Result<()> {
+    if JAVA_VERSIONS.contains(&java) {
+        Ok(())
+    } else {
+        Err(ServerError::InvalidValue(
+            "java".to_string(),
+            format!("{} is not one of {:?}", java, JAVA_VERSIONS),
+        ))
+    }
+}
+
+/// Parses a release version like `1.20.4` into `(major, minor, patch)`.
+/// Returns `None` for `LATEST`, `SNAPSHOT` and snapshot ids.
+fn parse_mc_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = match parts.next() {
+        Some(p) => p.parse().ok()?,
+        None => 0,
+    };
+    parts.next().is_none().then_some((major, minor, patch))
+}
+
+/// One-way changes between two versions worth warning about, empty for small upgrades.
+/// Versions that don't parse (LATEST, snapshots) aren't compared.
+fn version_jump_warnings(info: &ServerInfo, new: &str) -> Vec<String> {
+    let (Some(old_version), Some(new_version)) = (parse_mc_version(&info.version), parse_mc_version(new)) else {
+        return Vec::new();
+    };
+    let mut warnings = Vec::new();
+    if new_version < old_version {
+        warnings.push("Downgrading isn't supported, the world will likely fail to load or be corrupted".to_string());
+        return warnings;
+    }
+    if new_version.0 == old_version.0 && new_version.1 < old_version.1 + MAJOR_VERSION_JUMP {
+        return warnings;
+    }
+
+    let crosses = |version: (u32, u32, u32)| old_version < version && new_version >= version;
+    if crosses((1, 13, 0)) {
+        warnings.push("1.13 flattened block and item ids; the world is converted and can't be opened by older versions".to_string());
+    }
+    if crosses((1, 18, 0)) {
+        warnings.push("1.18 changed world height and terrain generation; existing chunks are upgraded in place".to_string());
+    }
+    if crosses((1, 20, 5)) {
+        warnings.push("1.20.5 moved item data to components; items are converted when chunks load".to_string());
+    }
+    if infer_java_version(&info.version) != infer_java_version(new) && info.java_version.is_none() {
+        warnings.push(format!("The server will run on Java {} instead of Java {}", infer_java_version(new), infer_java_version(&info.version)));
+    }
+    if info.mod_loader.is_some() || matches!(info.server_type.as_str(), "PAPER" | "SPIGOT" | "PURPUR") {
+        warnings.push(format!("Mods and plugins built for {} usually won't load on {}", info.version, new));
+    }
+    warnings.push("Chunks are upgraded as they load, so the world can't go back to the old version afterwards".to_string());
+    warnings
+}
+
+/// Warns about large version jumps, asks for confirmation and takes a backup before continuing.
+/// Returns whether a backup was taken.
+fn confirm_version_change(name: &str, info: &ServerInfo, new: &str, force: bool) -> Result<bool> {
+    let warnings = version_jump_warnings(info, new);
+    if warnings.is_empty() {
+        return Ok(false);
+    }
+
+    println!("{}", format!("\nChanging '{}' from {} to {}:", name, info.version, new).yellow());
+    for warning in &warnings {
+        println!("- {}", warning);
+    }
+    if !force {
+        let hint = format!("{} -> {} is a large version change, pass --force to confirm", info.version, new);
+        require_interactive(&hint)?;
+        if !Confirm::new().with_prompt("Continue with this version change?").default(false).interact()? {
+            return Err(ServerError::InvalidValue("version".to_string(), "version change cancelled".to_string()));
+        }
+    }
+
+    backup_before(name, info, "upgrade")?;
+    Ok(true)
+}
+
+/// Picks the Java version Mojang ships for a Minecraft version
+fn infer_java_version(version: &str) -> u8 {
+    match parse_mc_version(version) {
+        Some((1, minor, _)) if minor < 17 => 8,
+        Some((1, minor, patch)) if minor < 20 || (minor == 20 && patch < 5) => 17,
+        _ => 21,
+    }
+}
+
+/// Oldest Java a Minecraft version runs on; older ones crash at startup with
+/// "unsupported class file version"
+fn min_java_version(version: &str) -> u8 {
+    match parse_mc_version(version) {
+        Some((1, minor, _)) if minor < 17 => 8,
+        Some((1, 17, _)) => 16,
+        Some((1, minor, patch)) if minor < 20 || (minor == 20 && patch < 5) => 17,
+        _ => 21,
+    }
+}
+
+/// Java major version the server's container actually runs, from `java -version`
+fn container_java_version(name: &str) -> Result<u8> {
+    let output = ProcessCommand::new("docker")
+        .args(["exec", &format!("mc-{}", name), "java", "-version"])
+        .output()?;
+    if !output.status.success() {
+        return Err(docker_error(&output.stderr));
+    }
+    // Printed to stderr as `openjdk version "21.0.2" ...`, or "1.8.0_392" for Java 8
+    let text = String::from_utf8_lossy(&output.stderr);
+    let version = Regex::new(r#"version "(?:1\.)?(\d+)"#)?
+        .captures(&text)
+        .and_then(|c| c[1].parse().ok())
+        .ok_or_else(|| ServerError::DockerCommandFailed(format!("unexpected java -version output: {}", text.trim())))?;
+    Ok(version)
+}
+
+/// GET a JSON document with curl
+fn fetch_json(url: &str) -> Result<serde_json::Value> {
+    let output = ProcessCommand::new("curl").args(["-fsSL", "--max-time", "10", url]).output()?;
+    if !output.status.success() {
+        return Err(ServerError::Io(io::Error::other(format!(
+            "couldn't fetch {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Why a pinned Forge or Fabric version can't run the given Minecraft version, from the loader's
+/// own metadata. RECOMMENDED/LATEST loaders and LATEST/SNAPSHOT games are resolved by the image
+/// at boot, so only concrete pairs are checked.
+fn loader_mismatch(mod_loader: Option<&str>, loader_version: Option<&str>, version: &str) -> Result<Option<String>> {
+    let (Some(loader), Some(loader_version)) = (mod_loader, loader_version) else {
+        return Ok(None);
+    };
+    if parse_mc_version(version).is_none()
+        || loader_version.eq_ignore_ascii_case("RECOMMENDED")
+        || loader_version.eq_ignore_ascii_case("LATEST")
+    {
+        return Ok(None);
+    }
+    match loader {
+        "FORGE" => {
+            let builds = fetch_json(FORGE_VERSIONS_URL)?;
+            // Builds are "<minecraft>-<forge>", some old ones with a branch suffix after that
+            let forge_builds = |mc: &str| -> Vec<String> {
+                builds[mc]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|entry| entry.as_str()?.strip_prefix(mc)?.strip_prefix('-')?.split('-').next())
+                    .map(str::to_string)
+                    .collect()
+            };
+            let for_version = forge_builds(version);
+            if for_version.is_empty() {
+                return Ok(Some(format!("Forge has no builds for Minecraft {}", version)));
+            }
+            if for_version.iter().any(|build| build == loader_version) {
+                return Ok(None);
+            }
+            let built_for = builds.as_object().and_then(|all| {
+                all.keys().find(|mc| forge_builds(mc).iter().any(|build| build == loader_version))
+            });
+            let promotions = fetch_json(FORGE_PROMOTIONS_URL).unwrap_or_default();
+            let suggestion = ["recommended", "latest"]
+                .iter()
+                .find_map(|kind| promotions["promos"][format!("{}-{}", version, kind)].as_str().map(str::to_string))
+                .or_else(|| for_version.last().cloned());
+            let mut message = match built_for {
+                Some(mc) => format!("Forge {} is for Minecraft {}, not {}", loader_version, mc, version),
+                None => format!("there's no Forge {}", loader_version),
+            };
+            if let Some(suggestion) = suggestion {
+                message.push_str(&format!("; try {} for Minecraft {}", suggestion, version));
+            }
+            Ok(Some(message))
+        }
+        "FABRIC" => {
+            let loaders = fetch_json(&format!("{}{}", FABRIC_LOADERS_URL, version))?;
+            let loaders: Vec<(&str, bool)> = loaders
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| {
+                    Some((entry["loader"]["version"].as_str()?, entry["loader"]["stable"].as_bool().unwrap_or(false)))
+                })
+                .collect();
+            if loaders.is_empty() {
+                return Ok(Some(format!("Fabric doesn't support Minecraft {}", version)));
+            }
+            if loaders.iter().any(|(v, _)| *v == loader_version) {
+                return Ok(None);
+            }
+            let suggestion = loaders.iter().find(|(_, stable)| *stable).unwrap_or(&loaders[0]).0;
+            Ok(Some(format!(
+                "there's no Fabric loader {} for Minecraft {}; try {} (or LATEST)",
+                loader_version, version, suggestion
+            )))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Refuses a loader/Minecraft pair that would fail at boot, or only warns with `force`. Not being
+/// able to reach the metadata doesn't block anything.
+fn ensure_loader_compatible(
+    mod_loader: Option<&str>,
+    loader_version: Option<&str>,
+    version: &str,
+    force: bool,
+) -> Result<()> {
+    match loader_mismatch(mod_loader, loader_version, version) {
+        Ok(None) => Ok(()),
+        Ok(Some(message)) if force => {
+            println!("{}", format!("Warning: {}", message).yellow());
+            Ok(())
+        }
+        Ok(Some(message)) => Err(ServerError::InvalidValue("mod_loader_version".to_string(), message)),
+        Err(e) => {
+            let warning = format!("Warning: couldn't check the loader version against Minecraft {}: {}", version, e);
+            println!("{}", warning.yellow());
+            Ok(())
+        }
+    }
+}
+
+/// Warning for a server whose container runs a Java too old for its Minecraft version
+fn java_version_warning(name: &str, info: &ServerInfo) -> Result<Option<String>> {
+    let java = container_java_version(name)?;
+    let required = min_java_version(&info.version);
+    if java >= required {
+        return Ok(None);
+    }
+    let suggested = infer_java_version(&info.version).max(required);
+    Ok(Some(format!(
+        "'{}' runs Java {}, but Minecraft {} needs Java {} or newer. Switch to the \
+         itzg/minecraft-server:java{} image with 'set {} java_version {}'",
+        name, java, info.version, required, suggested, name, suggested
+    )))
+}
+
+fn run_doctor(name: &str, fix_permissions: bool, force: bool) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    println!("{}", format!("\nChecking server '{}':", name).bright_cyan());
+
+    let mut problems = 0;
+    if is_server_running(name)? {
+        match java_version_warning(name, info) {
+            Ok(Some(warning)) => {
+                problems += 1;
+                println!("- {}", warning.yellow());
+            }
+            Ok(None) => println!("- Java: {}", "ok".green()),
+            Err(e) => println!("- Java: couldn't check: {}", e),
+        }
+    } else {
+        println!("- Java: skipped, the server isn't running");
+    }
+    problems += check_permissions(name, info, fix_permissions, force)?;
+
+    if problems == 0 {
+        println!("{}", "No problems found.".green());
+    }
+    Ok(())
+}
+
+/// Reports data files not owned by the user the container runs as, which the server can't write
+/// and non-root backups can't read, and chowns them when asked to. Returns the problems left.
+#[cfg(unix)]
+fn check_permissions(name: &str, info: &ServerInfo, fix: bool, force: bool) -> Result<usize> {
+    use std::os::unix::fs::MetadataExt;
+
+    fn foreign_files(path: &Path, owner: (u32, u32), found: &mut Vec<PathBuf>) -> Result<()> {
+        let metadata = fs::symlink_metadata(path)?;
+        if (metadata.uid(), metadata.gid()) != owner {
+            found.push(path.to_path_buf());
+        }
+        if metadata.is_dir() {
+            let entries = match fs::read_dir(path) {
+                Ok(entries) => entries,
+                // Typically written by the container as root; chowning it is what makes it readable
+                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                    if found.last().map(PathBuf::as_path) != Some(path) {
+                        found.push(path.to_path_buf());
+                    }
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            };
+            for entry in entries {
+                foreign_files(&entry?.path(), owner, found)?;
+            }
+        }
+        Ok(())
+    }
+
+    let (uid, gid) = (info.uid.unwrap_or(IMAGE_DEFAULT_ID), info.gid.unwrap_or(IMAGE_DEFAULT_ID));
+    let mut problems = 0;
+    if let Some((user_uid, user_gid)) = current_user_ids().filter(|(user_uid, _)| *user_uid != 0 && *user_uid != uid) {
+        problems += 1;
+        println!(
+            "- {}",
+            format!(
+                "The container runs as {}:{} but you're {}:{}, so you can't edit or back up what it writes. \
+                 Run 'set {} uid {}' and 'set {} gid {}' first to have it run as you.",
+                uid, gid, user_uid, user_gid, name, user_uid, name, user_gid
+            )
+            .yellow()
+        );
+    }
+
+    let mut found = Vec::new();
+    foreign_files(Path::new(&info.data_path), (uid, gid), &mut found)?;
+    if found.is_empty() {
+        println!("- Ownership: {}, everything belongs to {}:{}", "ok".green(), uid, gid);
+        return Ok(problems);
+    }
+    println!(
+        "- {}",
+        format!("Ownership: {} file(s) don't belong to {}:{}, e.g. {}", found.len(), uid, gid, found[0].display()).yellow()
+    );
+    if !fix {
+        println!("  Fix with: mc-server doctor {} --fix-permissions", name);
+        return Ok(problems + 1);
+    }
+
+    // Files owned by someone else can only be handed over by root
+    let as_root = is_root();
+    let mut command = ProcessCommand::new(if as_root { "chown" } else { "sudo" });
+    if !as_root {
+        command.arg("chown");
+    }
+    command.args(["-R", &format!("{}:{}", uid, gid)]).arg(&info.data_path);
+    if !force {
+        require_interactive("pass --force to change ownership")?;
+        let prompt = format!(
+            "Run '{}chown -R {}:{} {}'?",
+            if as_root { "" } else { "sudo " },
+            uid,
+            gid,
+            info.data_path
+        );
+        if !Confirm::new().with_prompt(prompt).default(false).interact()? {
+            return Ok(problems + 1);
+        }
+    }
+    let status = command.status()?;
+    if !status.success() {
+        return Err(ServerError::Io(io::Error::other(format!("chown failed ({})", status))));
+    }
+    println!("{}", format!("  Changed the owner of {} to {}:{}", info.data_path, uid, gid).green());
+    Ok(problems)
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_name: &str, _info: &ServerInfo, _fix: bool, _force: bool) -> Result<usize> {
+    println!("- Ownership: skipped, Docker Desktop maps file ownership on this platform");
+    Ok(0)
+}
+
+fn version_check(name: Option<&str>) -> Result<()> {
+    let config = load_server_config()?;
+    let servers: Vec<(String, ServerInfo)> = match name {
+        Some(name) => {
+            let info = config
+                .servers
+                .get(name)
+                .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+            if !is_server_running(name)? {
+                return Err(ServerError::ServerNotRunning(name.to_string()));
+            }
+            vec![(name.to_string(), info.clone())]
+        }
+        None => {
+            let running = running_containers()?;
+            sorted_servers(&config)
+                .into_iter()
+                .filter(|(name, _)| running.contains(&format!("mc-{}", name)))
+                .collect()
+        }
+    };
+    if servers.is_empty() {
+        println!("{}", "No running servers to check.".yellow());
+        return Ok(());
+    }
+
+    for (name, info) in &servers {
+        match java_version_warning(name, info) {
+            Ok(Some(warning)) => println!("{} {}", "Warning:".yellow(), warning),
+            Ok(None) => println!("{}", format!("'{}': Java is recent enough for {}", name, info.version).green()),
+            Err(e) => println!("{} Couldn't check '{}': {}", "Warning:".yellow(), name, e),
+        }
+    }
+    Ok(())
+}
+
+fn validate_memory(memory: &str) -> Result<()> {
+    let digits = memory.trim_end_matches(['M', 'm', 'G', 'g']);
+    if !digits.is_empty()
+        && digits.len() + 1 >= memory.len()
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && digits.parse::<u64>().is_ok_and(|n| n > 0)
+    {
+        Ok(())
+    } else {
+        Err(ServerError::InvalidValue(
+            "memory".to_string(),
+            format!("'{}' is not a size like 2G or 512M", memory),
+        ))
+    }
+}
+
+/// Resolves a memory setting to an absolute size, accepting a percentage of host RAM like `50%`.
+/// Returns the absolute size and the percentage it was computed from, if any.
+fn resolve_memory(memory: &str) -> Result<(String, Option<u8>)> {
+    let Some(percent) = memory.strip_suffix('%') else {
+        validate_memory(memory)?;
+        return Ok((memory.to_string(), None));
+    };
+
+    let percent: u8 = percent
+        .parse()
+        .ok()
+        .filter(|p| (1..=100).contains(p))
+        .ok_or_else(|| {
+            ServerError::InvalidValue("memory".to_string(), format!("'{}' is not a percentage between 1% and 100%", memory))
+        })?;
+    let host_bytes = host_memory_bytes().ok_or_else(|| {
+        ServerError::InvalidValue("memory".to_string(), "couldn't detect host memory, use an absolute size".to_string())
+    })?;
+
+    let megabytes = (host_bytes / 1024 / 1024 * u64::from(percent) / 100).max(1);
+    Ok((format!("{}M", megabytes), Some(percent)))
+}
+
+fn host_memory_bytes() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+        let line = meminfo.lines().find(|l| l.starts_with("MemTotal:"))?;
+        let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kilobytes * 1024)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = ProcessCommand::new("sysctl").args(["-n", "hw.memsize"]).output().ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let output = ProcessCommand::new("powershell")
+            .args(["-NoProfile", "-Command", "(Get-CimInstance Win32_ComputerSystem).TotalPhysicalMemory"])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Fails with `hint` (what to pass instead) before a prompt that would fail without a terminal
+fn require_interactive(hint: &str) -> Result<()> {
+    if is_interactive() {
+        Ok(())
+    } else {
+        Err(ServerError::NotInteractive(hint.to_string()))
+    }
+}
+
+fn confirm_eula() -> Result<bool> {
+    require_interactive("pass --accept-eula to accept the Minecraft EULA")?;
+    Ok(Confirm::new()
+        .with_prompt("Do you agree to the Minecraft EULA? (https://account.mojang.com/documents/minecraft_eula)")
+        .interact()?)
+}
+
+fn build_compose_config(name: &str, info: &ServerInfo) -> ComposeConfig {
+    let mut environment = vec![
+        "EULA=TRUE".to_string(),
+        format!("MEMORY={}", info.memory),
+        format!("VERSION={}", info.version),
+        format!("TYPE={}", info.server_type),
+    ];
+
+    match (info.mod_loader.as_deref(), &info.mod_loader_version) {
+        (Some("FORGE"), Some(loader_version)) => environment.push(format!("FORGE_VERSION={}", loader_version)),
+        (Some("FABRIC"), Some(loader_version)) => {
+            environment.push(format!("FABRIC_LOADER_VERSION={}", loader_version))
+        }
+        _ => {}
+    }
+
+    // Add Java arguments if specified
+    if let Some(args) = &info.java_args {
+        environment.push(format!("JVM_OPTS={}", args));
+    }
+
+    // Values set explicitly with `env.*` or `properties --import` win over the preset
+    if let Some(tuning) = info.tuning {
+        for (key, value) in tuning.environment(&info.memory) {
+            let property = key.to_lowercase().replace('_', "-");
+            if !info.extra_env.contains_key(key) && !info.property_overrides.contains_key(&property) {
+                environment.push(format!("{}={}", key, value));
+            }
+        }
+    }
+
+    if let Some(ms) = info.max_tick_time {
+        environment.push(format!("MAX_TICK_TIME={}", ms));
+    }
+    if let Some(level_type) = info.level_type.and_then(|l| l.property(&info.version)) {
+        environment.push(format!("LEVEL_TYPE={}", level_type));
+    }
+    if let Some(settings) = &info.generator_settings {
+        environment.push(format!("GENERATOR_SETTINGS={}", settings));
+    }
+
+    if let Some(uid) = info.uid {
+        environment.push(format!("UID={}", uid));
+    }
+    if let Some(gid) = info.gid {
+        environment.push(format!("GID={}", gid));
+    }
+
+    if let Some(pack) = &info.resource_pack {
+        environment.push(format!("RESOURCE_PACK={}", pack));
+        if let Some(sha1) = &info.resource_pack_sha1 {
+            environment.push(format!("RESOURCE_PACK_SHA1={}", sha1));
+        }
+        if info.resource_pack_enforce {
+            environment.push("RESOURCE_PACK_ENFORCE=TRUE".to_string());
+        }
+    }
+
+    if let Some(managed) = &info.managed_backups {
+        environment.push(format!("RCON_PASSWORD={}", compose_secret(&managed.rcon_password)));
+    }
+
+    // Imported properties are passed as the image's variables (`view-distance` as VIEW_DISTANCE),
+    // or it would write its own values over them on the next start. BlockOps' settings and
+    // `env.*` win; properties the image has no variable for are left in server.properties alone.
+    for (property, value) in &info.property_overrides {
+        let key = property.to_uppercase().replace('-', "_");
+        if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !MANAGED_ENV.contains(&key.as_str())
+            && !info.extra_env.contains_key(&key)
+        {
+            environment.push(format!("{}={}", key, value));
+        }
+    }
+
+    for (key, value) in &info.extra_env {
+        environment.push(format!("{}={}", key, compose_secret(value)));
+    }
+
+    let mut services = BTreeMap::new();
+    services.insert(
+        name.to_string(),
+        MinecraftService {
+            image: info.image(),
+            container_name: format!("mc-{}", name),
+            ports: vec![format!("{}:25565", info.published_port())],
+            environment,
+            volumes: vec![format!("{}:/data", info.compose_data_path())],
+            restart: info
+                .restart_policy
+                .clone()
+                .unwrap_or_else(|| DEFAULT_RESTART_POLICY.to_string()),
+            stdin_open: true,
+            tty: true,
+            read_only: info.read_only,
+            tmpfs: if info.read_only {
+                READ_ONLY_TMPFS.iter().map(|p| p.to_string()).collect()
+            } else {
+                Vec::new()
+            },
+            command: Vec::new(),
+            profiles: Vec::new(),
+            logging: Some(info.logging()),
+            healthcheck: info.healthcheck_cmd.as_ref().map(|cmd| Healthcheck {
+                test: vec!["CMD-SHELL".to_string(), cmd.clone()],
+            }),
+        },
+    );
+    for companion in &info.companions {
+        services.insert(format!("{}-{}", name, companion.profile()), companion.service(name, info));
+    }
+    if let Some(port) = info.resource_pack_port {
+        services.insert(
+            format!("{}-resourcepack", name),
+            MinecraftService {
+                image: "nginx:alpine".to_string(),
+                container_name: format!("mc-{}-resourcepack", name),
+                ports: vec![format!("{}:80", port)],
+                environment: Vec::new(),
+                volumes: vec![format!("{}/{}:/usr/share/nginx/html:ro", info.compose_data_path(), RESOURCE_PACK_DIR)],
+                restart: info.restart_policy.clone().unwrap_or_else(|| DEFAULT_RESTART_POLICY.to_string()),
+                stdin_open: false,
+                tty: false,
+                read_only: false,
+                tmpfs: Vec::new(),
+                command: Vec::new(),
+                profiles: Vec::new(),
+                logging: Some(info.logging()),
+                healthcheck: None,
+            },
+        );
+    }
+
+    if let Some(managed) = &info.managed_backups {
+        let backup_dir = std::path::absolute(managed_backup_dir(name))
+            .unwrap_or_else(|_| managed_backup_dir(name));
+        services.insert(
+            format!("{}-backup", name),
+            MinecraftService {
+                image: "itzg/mc-backup".to_string(),
+                container_name: format!("mc-{}-backup", name),
+                ports: Vec::new(),
+                environment: vec![
+                    format!("BACKUP_INTERVAL={}", managed.interval),
+                    format!("PRUNE_BACKUPS_DAYS={}", managed.prune_days),
+                    format!("RCON_HOST={}", name),
+                    format!("RCON_PASSWORD={}", compose_secret(&managed.rcon_password)),
+                    // Wait for the server's first start instead of backing up an empty world
+                    "INITIAL_DELAY=2m".to_string(),
+                ],
+                volumes: vec![
+                    format!("{}:/data:ro", info.compose_data_path()),
+                    format!("{}:/backups", backup_dir.display()),
+                ],
+                restart: info.restart_policy.clone().unwrap_or_else(|| DEFAULT_RESTART_POLICY.to_string()),
+                stdin_open: false,
+                tty: false,
+                read_only: false,
+                tmpfs: Vec::new(),
+                command: Vec::new(),
+                profiles: Vec::new(),
+                logging: Some(info.logging()),
+                healthcheck: None,
+            },
+        );
+    }
+
+    ComposeConfig {
+        version: "3.8".to_string(),
+        services,
+    }
+}
+
+/// Host directory the backup sidecar writes its archives to
+fn managed_backup_dir(name: &str) -> PathBuf {
+    config_dir().join(BACKUP_DIR).join(MANAGED_BACKUP_DIR).join(name)
+}
+
+fn write_compose_file(name: &str, info: &ServerInfo) -> Result<()> {
+    let compose_path = info.compose_file();
+    if let Some(parent) = compose_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let yaml = serde_yaml::to_string(&build_compose_config(name, info))?;
+    fs::write(compose_path, yaml)?;
+    Ok(())
+}
+
+fn configure_map(name: &str, kind: Companion, enable: bool, disable: bool, port: Option<u16>) -> Result<()> {
+    let mut config = load_server_config()?;
+    let mut info = config
+        .servers
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    let profile = kind.profile();
+    let enabled = info.companions.contains(&kind);
+
+    if disable {
+        if !enabled {
+            println!("{}", format!("Server '{}' has no {} map.", name, profile).yellow());
+            return Ok(());
+        }
+        // The container may not exist, so a failure here is not an error
+        ProcessCommand::new("docker")
+            .args(["rm", "-f", &format!("mc-{}-{}", name, profile)])
+            .output()?;
+        info.companions.retain(|c| *c != kind);
+        info.companion_ports.remove(profile);
+        write_compose_file(name, &info)?;
+        config.servers.insert(name.to_string(), info);
+        save_server_config(&config)?;
+        println!("{}", format!("Disabled the {} map for server '{}'.", profile, name).green());
+        return Ok(());
+    }
+
+    if enable || port.is_some() {
+        let port = port.unwrap_or_else(|| info.companion_port(kind));
+        if !enabled || port != info.companion_port(kind) {
+            // Checked without this server's own map so a port can be reused when re-enabling
+            let mut others = ServerConfig { servers: config.servers.clone() };
+            if let Some(own) = others.servers.get_mut(name) {
+                own.companions.retain(|c| *c != kind);
+            }
+            check_port_available(&port.to_string(), &others)?;
+        }
+        if !enabled {
+            info.companions.push(kind);
+        }
+        if port == kind.default_port() {
+            info.companion_ports.remove(profile);
+        } else {
+            info.companion_ports.insert(profile.to_string(), port);
+        }
+        write_compose_file(name, &info)?;
+
+        if is_server_running(name)? {
+            let mut cmd = captured_compose_command(&info)?;
+            compose_secrets(&mut cmd, &info)?;
+            let output = cmd.args(["--profile", profile, "up", "-d"]).output()?;
+            if !output.status.success() {
+                return Err(docker_error(&output.stderr));
+            }
+        } else {
+            println!("Start it with: mc-server start {} --profile {}", name, profile);
+        }
+        config.servers.insert(name.to_string(), info.clone());
+        save_server_config(&config)?;
+    } else if !enabled {
+        println!("{}", format!("Server '{}' has no {} map. Add one with --enable.", name, profile).yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("{} map for '{}': http://localhost:{}", profile, name, info.companion_port(kind)).green()
+    );
+    Ok(())
+}
+
+/// Names of every container, running or not, that looks like one of ours
+fn managed_containers() -> Result<Vec<String>> {
+    container_names(true)
+}
+
+fn running_containers() -> Result<Vec<String>> {
+    container_names(false)
+}
+
+fn container_names(all: bool) -> Result<Vec<String>> {
+    let output = ProcessCommand::new("docker")
+        .arg("ps")
+        .args(all.then_some("-a"))
+        .args(["--format", "{{.Names}}", "-f", "name=^mc-"])
+        .output()?;
+    if !output.status.success() {
+        return Err(docker_error(&output.stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+/// What's gone of a server's files. `stop` removes the container, so a missing one is normal and
+/// the next start recreates it; archived servers have no data dir until `unarchive`.
+fn missing_server_files(info: &ServerInfo) -> Vec<&'static str> {
+    if info.archived {
+        return Vec::new();
+    }
+    let mut missing = Vec::new();
+    if !Path::new(&info.data_path).is_dir() {
+        missing.push("data dir");
+    }
+    if !info.compose_file().is_file() {
+        missing.push("compose file");
+    }
+    missing
+}
+
+fn reconcile_servers() -> Result<()> {
+    let mut config = load_server_config()?;
+    let containers = managed_containers()?;
+
+    let missing: Vec<(String, Vec<&str>)> = sorted_servers(&config)
+        .into_iter()
+        .map(|(name, info)| (name, missing_server_files(&info)))
+        .filter(|(_, files)| !files.is_empty())
+        .collect();
+    let known: Vec<String> = config
+        .servers
+        .iter()
+        .flat_map(|(name, info)| info.container_names(name))
+        .collect();
+    let mut orphans: Vec<&String> = containers.iter().filter(|c| !known.contains(c)).collect();
+    orphans.sort();
+
+    if missing.is_empty() && orphans.is_empty() {
+        println!("{}", "Configured servers and containers are in sync.".green());
+        return Ok(());
+    }
+
+    if !missing.is_empty() {
+        println!("\n{}", "Configured servers with missing files:".bright_cyan());
+        for (name, files) in &missing {
+            println!("- {} (no {})", name.bright_green(), files.join(" or "));
+        }
+    }
+    if !orphans.is_empty() {
+        println!("\n{}", "Containers without a configured server:".bright_cyan());
+        for container in &orphans {
+            println!("- {}", container.yellow());
+        }
+        println!("Remove them with 'docker rm -f <container>' if they're no longer needed.");
+    }
+
+    if !is_interactive() {
+        return Ok(());
+    }
+
+    let mut changed = false;
+    for (name, files) in &missing {
+        let choice = Select::new()
+            .with_prompt(format!("Server '{}' has no {}", name, files.join(" or ")))
+            .items(&[
+                "Leave it",
+                "Recreate the missing files (a missing world starts over)",
+                "Remove it from the config (leaves its data dir, compose file and keyring entries behind)",
+            ])
+            .default(0)
+            .interact()?;
+        match choice {
+            1 => {
+                let info = &config.servers[name];
+                fs::create_dir_all(&info.data_path)?;
+                write_compose_file(name, info)?;
+                println!("{}", format!("Recreated the missing files of '{}'.", name).green());
+            }
+            2 => {
+                config.servers.remove(name);
+                changed = true;
+                println!("{}", format!("Removed '{}' from the config.", name).green());
+            }
+            _ => {}
+        }
+    }
+    if changed {
+        save_server_config(&config)?;
+    }
+    Ok(())
+}
+
+fn show_compose(name: &str, regenerate: bool) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+
+    let yaml = if regenerate {
+        serde_yaml::to_string(&build_compose_config(name, info))?
+    } else {
+        fs::read_to_string(info.compose_file())?
+    };
+    for line in yaml.lines() {
+        println!("{}", highlight_yaml_line(line));
+    }
+    Ok(())
+}
+
+/// Fields that differ between any two servers or are compared as part of the environment
+const DIFF_SKIPPED_FIELDS: &[&str] =
+    &["data_path", "compose_path", "created_at", "last_started", "last_backup", "extra_env"];
+
+/// Settings of a server as `key -> value`, nested values flattened to `parent.child` keys
+fn diff_fields(info: &ServerInfo) -> Result<BTreeMap<String, String>> {
+    fn flatten(prefix: &str, value: &serde_json::Value, fields: &mut BTreeMap<String, String>) {
+        match value {
+            serde_json::Value::Null => {}
+            serde_json::Value::Object(object) => {
+                for (key, value) in object {
+                    let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                    flatten(&key, value, fields);
+                }
+            }
+            serde_json::Value::String(text) => {
+                fields.insert(prefix.to_string(), text.clone());
+            }
+            value => {
+                fields.insert(prefix.to_string(), value.to_string());
+            }
+        }
+    }
+
+    let mut value = serde_json::to_value(info)?;
+    if let Some(object) = value.as_object_mut() {
+        object.retain(|key, _| !DIFF_SKIPPED_FIELDS.contains(&key.as_str()));
+    }
+    let mut fields = BTreeMap::new();
+    flatten("", &value, &mut fields);
+    Ok(fields)
+}
+
+/// Environment of the server's container, from its compose file or generated when there is none
+fn diff_environment(name: &str, info: &ServerInfo) -> Result<BTreeMap<String, String>> {
+    let compose = match fs::read_to_string(info.compose_file()) {
+        Ok(content) => serde_yaml::from_str(&content)?,
+        Err(_) => build_compose_config(name, info),
+    };
+    Ok(compose
+        .services
+        .get(name)
+        .map(|service| {
+            service
+                .environment
+                .iter()
+                .map(|entry| match entry.split_once('=') {
+                    Some((key, value)) => (key.to_string(), value.to_string()),
+                    None => (entry.clone(), String::new()),
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Keys whose values differ, with `None` where a server doesn't have the key. Secrets only show
+/// that they differ.
+fn diff_maps(
+    a: &BTreeMap<String, String>,
+    b: &BTreeMap<String, String>,
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    keys.into_iter()
+        .filter(|key| a.get(*key) != b.get(*key))
+        .map(|key| {
+            let shown = |value: Option<&String>| {
+                value.map(|v| if is_secret_key(key) { "<redacted>".to_string() } else { v.clone() })
+            };
+            (key.clone(), shown(a.get(key)), shown(b.get(key)))
+        })
+        .collect()
+}
+
+fn diff_servers(a: &str, b: &str, json: bool) -> Result<()> {
+    let config = load_server_config()?;
+    let get = |name: &str| {
+        config
+            .servers
+            .get(name)
+            .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))
+    };
+    let (info_a, info_b) = (get(a)?, get(b)?);
+    let sections = [
+        ("Settings", diff_maps(&diff_fields(info_a)?, &diff_fields(info_b)?)),
+        ("Environment", diff_maps(&diff_environment(a, info_a)?, &diff_environment(b, info_b)?)),
+    ];
+
+    if json {
+        let entries = |differences: &[(String, Option<String>, Option<String>)]| {
+            differences
+                .iter()
+                .map(|(key, va, vb)| serde_json::json!({ "key": key, "a": va, "b": vb }))
+                .collect::<Vec<_>>()
+        };
+        let report = serde_json::json!({
+            "a": a,
+            "b": b,
+            "settings": entries(&sections[0].1),
+            "environment": entries(&sections[1].1),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if sections.iter().all(|(_, differences)| differences.is_empty()) {
+        println!("{}", format!("'{}' and '{}' are configured the same.", a, b).green());
+        return Ok(());
+    }
+    const UNSET: &str = "(unset)";
+    for (title, differences) in &sections {
+        if differences.is_empty() {
+            continue;
+        }
+        let key_width = differences.iter().map(|(key, ..)| key.len()).max().unwrap_or_default().max(title.len());
+        let a_width = differences
+            .iter()
+            .map(|(_, va, _)| va.as_deref().unwrap_or(UNSET).chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(a.len());
+        println!("\n{:<key_width$}  {:<a_width$}  {}", title.bright_cyan(), a.bold(), b.bold());
+        for (key, va, vb) in differences {
+            let (va, vb) = (va.as_deref().unwrap_or(UNSET), vb.as_deref().unwrap_or(UNSET));
+            println!("{:<key_width$}  {:<a_width$}  {}", key.bright_blue(), va.red(), vb.green());
+        }
+    }
+    Ok(())
+}
+
+/// Prints a line diff of `old` and `new`, with changed lines colored and two lines of context
+fn print_line_diff(old: &str, new: &str) {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence table, plenty fast for config-sized inputs
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    if lines.iter().all(|(kind, _)| *kind == ' ') {
+        println!("(no changes)");
+        return;
+    }
+    let near_change = |index: usize| {
+        lines[index.saturating_sub(2)..(index + 3).min(lines.len())]
+            .iter()
+            .any(|(kind, _)| *kind != ' ')
+    };
+    let mut skipped = false;
+    for (index, (kind, line)) in lines.iter().enumerate() {
+        match kind {
+            '+' => println!("{}", format!("+{}", line).green()),
+            '-' => println!("{}", format!("-{}", line).red()),
+            _ if near_change(index) => println!(" {}", line),
+            _ => {
+                if !skipped {
+                    println!("{}", "...".bright_black());
+                }
+                skipped = true;
+                continue;
+            }
+        }
+        skipped = false;
+    }
+}
+
+/// Colors keys, list markers and comments; values are printed as-is
+fn highlight_yaml_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let (indent, rest) = line.split_at(line.len() - trimmed.len());
+    if trimmed.starts_with('#') {
+        return format!("{}{}", indent, trimmed.dimmed());
+    }
+    let (marker, rest) = match rest.strip_prefix("- ") {
+        Some(item) => ("- ".yellow().to_string(), item),
+        None => (String::new(), rest),
+    };
+    match rest.split_once(':') {
+        Some((key, value)) if !key.contains(' ') && !key.starts_with(['\'', '"']) => {
+            format!("{}{}{}:{}", indent, marker, key.bright_cyan(), value.green())
+        }
+        _ => format!("{}{}{}", indent, marker, rest.green()),
+    }
+}
+
+/// Creates the server's data directory and compose file and registers it in `config`.
+/// The caller is responsible for saving the config, and for checking the free space first.
+fn provision_server(config: &mut ServerConfig, name: &str, info: ServerInfo) -> Result<()> {
+    fs::create_dir_all(&info.data_path)?;
+    write_compose_file(name, &info)?;
+    config.servers.insert(name.to_string(), info);
+    Ok(())
+}
+
+/// Placeholders `list --template` fills in, documented on `ListOptions::template`
+const LIST_TEMPLATE_FIELDS: &[&str] = &[
+    "name",
+    "status",
+    "type",
+    "version",
+    "mod_loader",
+    "java",
+    "port",
+    "bind",
+    "memory",
+    "created",
+    "last_started",
+    "description",
+    "tags",
+    "depends_on",
+    "enabled",
+    "archived",
+];
+
+fn list_template_field(field: &str, name: &str, info: &ServerInfo, state: ServerState) -> String {
+    let date = |date: chrono::DateTime<chrono::Utc>| date.to_rfc3339();
+    match field {
+        "name" => name.to_string(),
+        "status" => state.label().to_string(),
+        "type" => info.server_type.clone(),
+        "version" => info.version.clone(),
+        "mod_loader" => info.mod_loader.clone().unwrap_or_default(),
+        "java" => info.java_version.unwrap_or_else(|| infer_java_version(&info.version)).to_string(),
+        "port" => info.port.clone(),
+        "bind" => info.bind_address.map(|ip| ip.to_string()).unwrap_or_default(),
+        "memory" => info.memory.clone(),
+        "created" => date(info.created_at),
+        "last_started" => info.last_started.map(date).unwrap_or_default(),
+        "description" => info.description.clone().unwrap_or_default(),
+        "tags" => info.tags.join(","),
+        "depends_on" => info.depends_on.join(","),
+        "enabled" => (!info.disabled).to_string(),
+        "archived" => info.archived.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Renders `template` with `{field}` placeholders replaced by `value_of`. Checked up front with a
+/// dummy `value_of`, so a typo fails before anything is printed.
+fn render_template(template: &str, value_of: impl Fn(&str) -> String) -> Result<String> {
+    let invalid = |message: String| ServerError::InvalidValue("template".to_string(), message);
+    let mut output = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                output.push(c);
+                chars.next();
+            }
+            ('\\', Some('t')) => {
+                output.push('\t');
+                chars.next();
+            }
+            ('\\', Some('n')) => {
+                output.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                output.push('\\');
+                chars.next();
+            }
+            ('{', _) => {
+                let mut field = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    field.push(c);
+                }
+                if !closed {
+                    return Err(invalid("unclosed '{', write {{ for a literal brace".to_string()));
+                }
+                if !LIST_TEMPLATE_FIELDS.contains(&field.as_str()) {
+                    return Err(invalid(format!(
+                        "unknown placeholder {{{}}} (known: {})",
+                        field,
+                        LIST_TEMPLATE_FIELDS.join(", ")
+                    )));
+                }
+                output.push_str(&value_of(&field));
+            }
+            ('}', _) => return Err(invalid("unmatched '}', write }} for a literal brace".to_string())),
+            _ => output.push(c),
+        }
+    }
+    Ok(output)
+}
+
+fn list_servers(options: &ListOptions, json: bool) -> Result<()> {
+    if let Some(template) = &options.template {
+        if json {
+            return Err(ServerError::InvalidValue(
+                "template".to_string(),
+                "--template and --json are separate output formats, pick one".to_string(),
+            ));
+        }
+        render_template(template, |_| String::new())?;
+    }
+    let config = load_server_config()?;
+    let configured = config.servers.len();
+    let mut servers = Vec::new();
+    for (name, info) in sorted_servers(&config) {
+        if info.archived && !options.archived {
+            continue;
+        }
+        let state = get_server_status(&name)?;
+        if options.matches(state) {
+            servers.push((name, info, state));
+        }
+    }
+    // Stable sorts on top of the name order, so ties stay alphabetical
+    match options.sort {
+        ListSort::Name => {}
+        ListSort::Created => servers.sort_by_key(|(_, info, _)| info.created_at),
+        ListSort::LastStarted => servers.sort_by_key(|(_, info, _)| info.last_started),
+        ListSort::Memory => servers.sort_by_key(|(_, info, _)| memory_bytes(&info.memory)),
+        ListSort::Status => servers.sort_by_key(|(_, _, state)| match state {
+            ServerState::Running => 0,
+            ServerState::Paused => 1,
+            ServerState::Stopped => 2,
+        }),
+    }
+    if options.reverse {
+        servers.reverse();
+    }
+
+    if let Some(template) = &options.template {
+        for (name, info, state) in &servers {
+            println!("{}", render_template(template, |field| list_template_field(field, name, info, *state))?);
+        }
+        return Ok(());
+    }
+
+    if json {
+        let servers: Vec<_> = servers
+            .iter()
+            .map(|(name, info, state)| {
+                serde_json::json!({
+                    "name": name,
+                    "status": state.label(),
+                    "enabled": !info.disabled,
+                    "archived": info.archived,
+                    "server_type": info.server_type,
+                    "version": info.version,
+                    "mod_loader": info.mod_loader,
+                    "java_version": info.java_version.unwrap_or_else(|| infer_java_version(&info.version)),
+                    "port": info.port,
+                    "bind_address": info.bind_address,
+                    "memory": info.memory,
+                    "created_at": info.created_at.to_rfc3339(),
+                    "last_started": info.last_started.map(|d| d.to_rfc3339()),
+                    "description": info.description,
+                    "tags": info.tags,
+                    "depends_on": info.depends_on,
+                    "auto_backup": info.auto_backup,
+                    "managed_backups": info.managed_backups.as_ref().map(|m| serde_json::json!({
+                        "interval": m.interval,
+                        "prune_days": m.prune_days,
+                        "status": managed_backup_state(name).label(),
+                    })),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&servers).unwrap_or_default());
+        return Ok(());
+    }
+
+    if servers.is_empty() {
+        if configured == 0 {
+            println!("{}", "\nNo servers configured yet. Use 'create' to add a server.".yellow());
+        } else if config.servers.values().all(|info| info.archived) && !options.archived {
+            println!("{}", "\nAll servers are archived, 'list --archived' shows them.".yellow());
+        } else {
+            println!("{}", "\nNo servers in that state.".yellow());
+        }
+        return Ok(());
+    }
+
+    println!("\n{}", "Configured Minecraft Servers:".bright_cyan());
+    println!("{}", "=========================".bright_cyan());
+
+    for (name, info, state) in servers {
+        let mod_info = info.mod_loader.as_ref().map_or("".to_string(), |m| format!(" ({})", m));
+        let java = info.java_version.unwrap_or_else(|| infer_java_version(&info.version));
+        let memory_info = info
+            .memory_percent
+            .map_or("".to_string(), |p| format!(" ({}% of host)", p));
+        
+        let disabled = match (info.archived, info.disabled) {
+            (true, _) => " ARCHIVED".bright_black().to_string(),
+            (false, true) => " DISABLED".bright_black().to_string(),
+            (false, false) => String::new(),
+        };
+        println!(
+            "{}: {}{} {}\n  Version: {}{}, Java {}\n  Port: {}, Memory: {}{}\n  Created: {}\n  Last Started: {}",
+            name.bright_green(),
+            state.colored(),
+            disabled,
+            info.server_type.bright_blue(),
+            info.version.bright_blue(),
+            mod_info.bright_blue(),
+            java,
+            info.published_port(),
+            info.memory,
+            memory_info,
+            info.created_at.format("%Y-%m-%d %H:%M:%S"),
+            info.last_started.map_or("Never".to_string(), |d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+        );
+        if let Some(description) = &info.description {
+            println!("  Description: {}", description);
+        }
+        if !info.tags.is_empty() {
+            println!("  Tags: {}", info.tags.join(", ").bright_magenta());
+        }
+        if !info.depends_on.is_empty() {
+            println!("  Depends on: {}", info.depends_on.join(", "));
+        }
+        if info.auto_backup {
+            println!("  Auto backup: on every stop");
+        }
+        if let Some(managed) = &info.managed_backups {
+            println!(
+                "  Managed backups: {} every {}, kept {} days",
+                managed_backup_state(&name).colored(),
+                managed.interval,
+                managed.prune_days
+            );
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Parses a memory size like `2G` or `1536M` into bytes
+fn memory_bytes(memory: &str) -> Option<u64> {
+    let (digits, unit) = memory.split_at(memory.len().checked_sub(1)?);
+    let value: u64 = digits.parse().ok()?;
+    match unit {
+        "G" | "g" => Some(value * 1024 * 1024 * 1024),
+        "M" | "m" => Some(value * 1024 * 1024),
+        _ => None,
+    }
+}
+
+fn fleet_summary() -> Result<()> {
+    let config = load_server_config()?;
+    if config.servers.is_empty() {
+        println!("{}", "\nNo servers configured yet. Use 'create' to add a server.".yellow());
+        return Ok(());
+    }
+    let servers = sorted_servers(&config);
+    let running = running_containers()?;
+    let now = chrono::Local::now().naive_local();
+
+    let mut running_count = 0;
+    let mut players = 0;
+    let mut unreachable = 0;
+    let mut memory = 0;
+    let mut data_size = 0;
+    let mut unknown_size = Vec::new();
+    let mut backup_size = 0;
+    let mut rows = Vec::new();
+
+    for (name, info) in &servers {
+        let is_running = running.iter().any(|c| *c == format!("mc-{}", name));
+        if is_running {
+            running_count += 1;
+            match online_players(name) {
+                Ok(online) => players += online.len(),
+                Err(_) => unreachable += 1,
+            }
+        }
+        memory += memory_bytes(&info.memory).unwrap_or_default();
+        let data_path = Path::new(&info.data_path);
+        if data_path.is_dir() {
+            // Files the container created can be unreadable to us, see `doctor --fix-permissions`
+            let (size, complete) = readable_dir_size(data_path);
+            data_size += size;
+            if !complete {
+                unknown_size.push(name.as_str());
+            }
+        }
+
+        let backups = list_backups(name)?;
+        for backup in &backups {
+            backup_size += fs::metadata(backup)?.len();
+        }
+        let age = |path: Option<&PathBuf>| {
+            path.and_then(|p| backup_time(name, p))
+                .map(|t| format!("{} ago", format_duration((now - t).num_seconds().max(0) as u64)))
+        };
+        let backup_info = match (age(backups.first()), age(backups.last())) {
+            (Some(oldest), Some(newest)) => format!("{} backup(s), newest {}, oldest {}", backups.len(), newest, oldest),
+            _ => "no backups".red().to_string(),
+        };
+        let status = if is_running { "RUNNING".bright_green() } else { "STOPPED".red() };
+        rows.push(format!("  {:<16} {:<8} {}", name, status, backup_info));
+    }
+
+    println!("\n{}", "Fleet Summary:".bright_cyan());
+    println!("{}", "=========================".bright_cyan());
+    println!(
+        "Servers: {} ({} running, {} stopped)",
+        servers.len(),
+        running_count,
+        servers.len() - running_count
+    );
+    let unreachable_note = if unreachable > 0 {
+        format!(" ({} server(s) didn't answer RCON)", unreachable)
+    } else {
+        String::new()
+    };
+    println!("Players online: {}{}", players, unreachable_note);
+    match host_memory_bytes() {
+        Some(host) => println!("Memory configured: {} of {} host", format_size(memory), format_size(host)),
+        None => println!("Memory configured: {}", format_size(memory)),
+    }
+    let unknown_note = if unknown_size.is_empty() {
+        String::new()
+    } else {
+        format!(" (at least; size of {} unknown, some files aren't readable)", unknown_size.join(", "))
+    };
+    println!("Disk used: {} data{}, {} backups", format_size(data_size), unknown_note, format_size(backup_size));
+    println!("\n{}", "Servers:".bright_cyan());
+    for row in rows {
+        println!("{}", row);
+    }
+    Ok(())
+}
+
+fn get_server_status(name: &str) -> Result<ServerState> {
+    let output = ProcessCommand::new("docker")
+        .args(["ps", "-a", "--format", "{{.State}}", "-f", &format!("name=^mc-{}$", name)])
+        .output()?;
+    if !output.status.success() {
+        return Err(docker_error(&output.stderr));
+    }
+
+    // No container at all counts as stopped, same as an exited one
+    Ok(match String::from_utf8_lossy(&output.stdout).trim() {
+        "running" | "restarting" => ServerState::Running,
+        "paused" => ServerState::Paused,
+        _ => ServerState::Stopped,
+    })
+}
+
+/// State of the itzg/mc-backup sidecar, stopped when docker can't be asked
+fn managed_backup_state(name: &str) -> ServerState {
+    get_server_status(&format!("{}-backup", name)).unwrap_or(ServerState::Stopped)
+}
+
+fn is_docker_permission_error(stderr: &[u8]) -> bool {
+    let stderr = String::from_utf8_lossy(stderr).to_lowercase();
+    stderr.contains("permission denied") && stderr.contains("docker daemon socket")
+}
+
+/// The installed Compose. Flags that are spelled differently between 1.x and 2.x are only
+/// ever emitted through [`ComposeVariant::flag`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ComposeVariant {
+    /// The Python `docker-compose` 1.x
+    V1,
+    /// Compose 2.x, as the standalone `docker-compose` binary or the `docker compose` plugin
+    V2 { plugin: bool },
+}
+
+/// Compose options whose spelling depends on the variant
+#[derive(Clone, Copy)]
+enum ComposeFlag {
+    /// Global: no colours or other control characters in captured output
+    NoAnsi,
+    /// `pull`: no per-layer progress in captured output
+    QuietPull,
+    /// `down`: seconds to wait for the server to save and exit before killing it
+    StopTimeout(u64),
+    /// `logs`: only the last lines
+    LogsTail(usize),
+    /// `logs`: plain lines without the coloured service prefix
+    LogsNoColor,
+    /// `logs`: only lines written from this time on
+    LogsSince(chrono::DateTime<chrono::Utc>),
+}
+
+impl ComposeVariant {
+    /// Detected once per run. The standalone binary is preferred, like before the plugin was
+    /// supported; with neither installed this stays `docker-compose` so the error names it.
+    fn detect() -> ComposeVariant {
+        static VARIANT: OnceLock<ComposeVariant> = OnceLock::new();
+        *VARIANT.get_or_init(|| {
+            // 1.x prints "1.29.2", the 2.x binary "2.24.5" or "v2.24.5"
+            if let Some(version) = command_output("docker-compose", &["version", "--short"]) {
+                return if version.trim_start_matches('v').starts_with("1.") {
+                    ComposeVariant::V1
+                } else {
+                    ComposeVariant::V2 { plugin: false }
+                };
+            }
+            if command_output("docker", &["compose", "version", "--short"]).is_some() {
+                return ComposeVariant::V2 { plugin: true };
+            }
+            ComposeVariant::V1
+        })
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ComposeVariant::V1 => "docker-compose (v1)",
+            ComposeVariant::V2 { plugin: false } => "docker-compose (v2)",
+            ComposeVariant::V2 { plugin: true } => "docker compose (v2 plugin)",
+        }
+    }
+
+    fn command(self) -> ProcessCommand {
+        match self {
+            ComposeVariant::V2 { plugin: true } => {
+                let mut cmd = ProcessCommand::new("docker");
+                cmd.arg("compose");
+                cmd
+            }
+            _ => ProcessCommand::new("docker-compose"),
+        }
+    }
+
+    /// Arguments for `flag` as this variant spells them
+    fn flag(self, flag: ComposeFlag) -> Vec<String> {
+        let v2 = self != ComposeVariant::V1;
+        let args: &[&str] = match flag {
+            // 1.28 deprecated --no-ansi in favour of --ansi, which older 1.x don't know
+            ComposeFlag::NoAnsi if v2 => &["--ansi", "never"],
+            ComposeFlag::NoAnsi => &["--no-ansi"],
+            // Rather than the global --progress, which only recent 2.x releases accept
+            ComposeFlag::QuietPull => &["--quiet"],
+            ComposeFlag::StopTimeout(secs) => return vec!["--timeout".to_string(), secs.to_string()],
+            ComposeFlag::LogsTail(lines) => return vec!["--tail".to_string(), lines.to_string()],
+            ComposeFlag::LogsNoColor => &["--no-color"],
+            ComposeFlag::LogsSince(time) if v2 => {
+                return vec!["--since".to_string(), time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)]
+            }
+            // 1.x has no --since, so it follows from now on. A line written between `up` and the
+            // stream starting is missed, which beats replaying the last run's Done or crash line.
+            ComposeFlag::LogsSince(_) => &["--tail", "0"],
+        };
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+}
+
+/// `docker-compose -f <compose file>` (or `docker compose`) for the server
+fn compose_command(info: &ServerInfo) -> Result<ProcessCommand> {
+    let mut cmd = ComposeVariant::detect().command();
+    cmd.arg("-f").arg(std::path::absolute(info.compose_file())?);
+    // The compose file only names the variables of keyring secrets. Compose wants them set on
+    // every call, but only creating containers needs the values (see `compose_secrets`), so
+    // `down` and `logs` still work with the keyring locked.
+    for value in info.extra_env.values().chain(info.managed_backups.iter().map(|m| &m.rcon_password)) {
+        if let Some(account) = secrets::account(value) {
+            cmd.env(secrets::env_var(account), "");
+        }
+    }
+    Ok(cmd)
+}
+
+/// Fills in the keyring secrets of a [`compose_command`] that creates containers (`up`)
+fn compose_secrets(cmd: &mut ProcessCommand, info: &ServerInfo) -> Result<()> {
+    for value in info.extra_env.values().chain(info.managed_backups.iter().map(|m| &m.rcon_password)) {
+        if let Some(account) = secrets::account(value) {
+            cmd.env(secrets::env_var(account), resolve_secret(value)?);
+        }
+    }
+    Ok(())
+}
+
+/// [`compose_command`] for invocations whose output is captured rather than shown as it happens
+fn captured_compose_command(info: &ServerInfo) -> Result<ProcessCommand> {
+    let variant = ComposeVariant::detect();
+    let mut cmd = compose_command(info)?;
+    cmd.args(variant.flag(ComposeFlag::NoAnsi));
+    Ok(cmd)
+}
+
+/// Turns a failed docker/docker-compose invocation into an error, recognising socket permission problems
+fn docker_error(stderr: &[u8]) -> ServerError {
+    if is_docker_permission_error(stderr) {
+        ServerError::DockerPermissionDenied
+    } else {
+        ServerError::DockerCommandFailed(String::from_utf8_lossy(stderr).to_string())
+    }
+}
+
+/// Files written by a root-run container end up owned by root and break later non-root runs
+fn warn_if_root() {
+    if is_root() {
+        println!(
+            "{}",
+            "Warning: running as root. Server files will be owned by root and later non-root backups may fail.\n\
+             Consider adding your user to the 'docker' group instead: sudo usermod -aG docker $USER"
+                .yellow()
+        );
+    }
+}
+
+fn is_server_running(name: &str) -> Result<bool> {
+    let output = ProcessCommand::new("docker")
+        .args(["ps", "-q", "-f", &format!("name=^mc-{}$", name)])
+        .output()?;
+    if is_docker_permission_error(&output.stderr) {
+        return Err(ServerError::DockerPermissionDenied);
+    }
+
+    Ok(!output.stdout.is_empty())
+}
+
+fn start_servers(name: Option<String>, parallel: Option<usize>, args: &ComposeArgs) -> Result<()> {
+    let config = load_server_config()?;
+    let pb = create_spinner("Starting server(s)");
+
+    match name {
+        Some(server_name) => {
+            if let Some(info) = config.servers.get(&server_name) {
+                if let Some(profile) = args
+                    .profiles
+                    .iter()
+                    .find(|p| !info.companions.iter().any(|c| c.profile() == p.as_str()))
+                {
+                    return Err(ServerError::InvalidValue(
+                        "profile".to_string(),
+                        format!("server '{}' has no '{}' companion", server_name, profile),
+                    ));
+                }
+                let running = running_containers()?;
+                let down: Vec<&str> = info
+                    .depends_on
+                    .iter()
+                    .filter(|d| !running.contains(&format!("mc-{}", d)))
+                    .map(String::as_str)
+                    .collect();
+                if !down.is_empty() {
+                    pb.suspend(|| {
+                        print_status(format!("{} '{}' depends on {}, which isn't running", "Warning:".yellow(), server_name, down.join(", ")))
+                    });
+                }
+                if info.archived {
+                    return Err(ServerError::ServerArchived(server_name));
+                }
+                start_single_server(&server_name, info, args, &pb)?;
+                update_last_started(&[server_name.as_str()])?;
+            } else {
+                return Err(ServerError::ServerNotFound(server_name));
+            }
+        }
+        None => {
+            if config.servers.is_empty() {
+                print_status("No servers configured!".yellow());
+                return Ok(());
+            }
+            let (servers, disabled): (Vec<_>, Vec<_>) =
+                sorted_servers(&config).into_iter().partition(|(_, info)| !info.disabled && !info.archived);
+            if !disabled.is_empty() && !args.only_errors {
+                let names: Vec<&str> = disabled.iter().map(|(name, _)| name.as_str()).collect();
+                print_status(format!("Skipping {} disabled or archived server(s): {}", disabled.len(), names.join(", ")));
+            }
+            let total = servers.len();
+            // Each wave only depends on earlier ones. Without dependencies everything is one wave.
+            let waves = start_waves(servers)?;
+            let mut started = Vec::new();
+            let mut failed = 0;
+            let mut outcome = Ok(());
+            // Servers that didn't start or come up, and those left out because they need one. A
+            // wave only depends on earlier ones, so this covers dependencies of dependencies too.
+            let mut unavailable: BTreeSet<String> = BTreeSet::new();
+            for (i, wave) in waves.iter().enumerate() {
+                let (wave, blocked): (Vec<_>, Vec<_>) = wave
+                    .iter()
+                    .cloned()
+                    .partition(|(_, info)| !info.depends_on.iter().any(|d| unavailable.contains(d)));
+                for (name, info) in blocked {
+                    let down: Vec<&str> =
+                        info.depends_on.iter().filter(|d| unavailable.contains(*d)).map(String::as_str).collect();
+                    print_status(format!("Not starting '{}', {} didn't come up", name, down.join(", ")).yellow());
+                    unavailable.insert(name);
+                }
+
+                let wave_started = chrono::Utc::now();
+                let results =
+                    run_parallel(&wave, parallel, |(name, info), pb| start_single_server(name, info, args, pb));
+                for ((name, _), result) in wave.iter().zip(&results) {
+                    if result.is_ok() {
+                        started.push(name.clone());
+                    } else {
+                        failed += 1;
+                        unavailable.insert(name.clone());
+                    }
+                }
+                let wave_outcome = first_failure(&wave, results);
+                outcome = outcome.and(wave_outcome);
+
+                let later = &waves[i + 1..];
+                let needed: Vec<(String, ServerInfo)> = wave
+                    .into_iter()
+                    .filter(|(name, _)| !unavailable.contains(name))
+                    .filter(|(name, _)| later.iter().flatten().any(|(_, info)| info.depends_on.contains(name)))
+                    .collect();
+                if !needed.is_empty() {
+                    let timeout = args.ready_timeout.unwrap_or(DEFAULT_READY_TIMEOUT);
+                    let results = wait_for_dependencies(&needed, timeout, wave_started);
+                    for ((name, _), result) in needed.iter().zip(&results) {
+                        if result.is_err() {
+                            unavailable.insert(name.clone());
+                        }
+                    }
+                    let wait_outcome = first_failure(&needed, results);
+                    outcome = outcome.and(wait_outcome);
+                }
+            }
+
+            // Recorded in one go, concurrent config writes would lose updates
+            update_last_started(&started.iter().map(String::as_str).collect::<Vec<_>>())?;
+            if args.only_errors {
+                // Whatever is neither started nor failed waited on a dependency that didn't come up
+                print_tally("started", started.len(), failed, total - started.len() - failed + disabled.len());
+            }
+            outcome?;
+        }
+    }
+    if args.only_errors {
+        pb.finish_and_clear();
+    } else {
+        pb.finish_with_message("Server start operation completed!");
+    }
+    Ok(())
+}
+
+/// Splits servers into start waves: each wave only depends on servers in earlier waves (or on
+/// servers outside the set, which aren't waited for). Fails on a dependency cycle.
+fn start_waves(mut pending: Vec<(String, ServerInfo)>) -> Result<Vec<Vec<(String, ServerInfo)>>> {
+    let names: Vec<String> = pending.iter().map(|(name, _)| name.clone()).collect();
+    let mut done: Vec<String> = Vec::new();
+    let mut waves = Vec::new();
+    while !pending.is_empty() {
+        let (ready, blocked): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(_, info)| {
+            info.depends_on
+                .iter()
+                .all(|d| !names.contains(d) || done.contains(d))
+        });
+        if ready.is_empty() {
+            let stuck: Vec<&str> = blocked.iter().map(|(name, _)| name.as_str()).collect();
+            return Err(ServerError::InvalidValue(
+                "depends_on".to_string(),
+                format!("no start order for {}, their dependencies contain a cycle", stuck.join(", ")),
+            ));
+        }
+        done.extend(ready.iter().map(|(name, _)| name.clone()));
+        waves.push(ready);
+        pending = blocked;
+    }
+    Ok(waves)
+}
+
+/// Waits for servers started at `since` to be ready, with one result per server
+fn wait_for_dependencies(
+    servers: &[(String, ServerInfo)],
+    timeout: u64,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Vec<Result<()>> {
+    run_parallel(servers, Some(servers.len()), |(name, info), pb| {
+        pb.set_message(format!("Waiting for {} before starting what depends on it...", name));
+        let ready = Regex::new(DEFAULT_READY_PATTERN)?;
+        wait_until_ready(name, info, &ready, timeout, false, Some(since)).map(|_| ())
+    })
+}
+
+/// Checks that `name` can depend on `depends_on`: the servers exist and none of them already
+/// depends on `name`, directly or through others
+fn validate_dependencies(name: &str, depends_on: &[String], config: &ServerConfig) -> Result<()> {
+    fn path_to(from: &str, target: &str, config: &ServerConfig, path: &mut Vec<String>) -> bool {
+        path.push(from.to_string());
+        if from == target {
+            return true;
+        }
+        let deps = config.servers.get(from).map(|info| info.depends_on.as_slice()).unwrap_or_default();
+        for dep in deps {
+            // Already on the path means an existing cycle elsewhere; bulk start reports those
+            if !path.contains(dep) && path_to(dep, target, config, path) {
+                return true;
+            }
+        }
+        path.pop();
+        false
+    }
+
+    for dep in depends_on {
+        if !config.servers.contains_key(dep) {
+            return Err(ServerError::ServerNotFound(dep.clone()));
+        }
+        let mut path = Vec::new();
+        if path_to(dep, name, config, &mut path) {
+            return Err(ServerError::InvalidValue(
+                "depends_on".to_string(),
+                format!("that would be a cycle: {} -> {}", name, path.join(" -> ")),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn set_enabled(name: &str, enabled: bool) -> Result<()> {
+    let mut config = load_server_config()?;
+    let info = config
+        .servers
+        .get_mut(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    info.disabled = !enabled;
+    save_server_config(&config)?;
+
+    if enabled {
+        println!("{}", format!("Server '{}' is included in bulk start again.", name).green());
+    } else {
+        println!("{}", format!("Server '{}' is disabled, bulk start will skip it.", name).green());
+    }
+    Ok(())
+}
+
+fn update_last_started(names: &[&str]) -> Result<()> {
+    let mut config = load_server_config()?;
+    let now = chrono::Utc::now();
+    for name in names {
+        if let Some(info) = config.servers.get_mut(*name) {
+            info.last_started = Some(now);
+        }
+    }
+    save_server_config(&config)?;
+    Ok(())
+}
+
+/// Servers ordered by name (the map's own order), for bulk operations and stable output
+fn sorted_servers(config: &ServerConfig) -> Vec<(String, ServerInfo)> {
+    config
+        .servers
+        .iter()
+        .map(|(name, info)| (name.clone(), info.clone()))
+        .collect()
+}
+
+fn default_parallelism() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Runs `op` on every item with at most `parallel` running at once, returning results in item order.
+/// Each item gets a spinner of its own, cleared when it's done either way.
+fn run_parallel<T, F>(items: &[T], parallel: Option<usize>, op: F) -> Vec<Result<()>>
+where
+    T: Sync,
+    F: Fn(&T, &ProgressBar) -> Result<()> + Sync,
+{
+    let workers = parallel.unwrap_or_else(default_parallelism).clamp(1, items.len().max(1));
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<()>>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let pb = create_spinner("");
+                let result = op(item, &pb);
+                pb.finish_and_clear();
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every item is processed"))
+        .collect()
+}
+
+/// Final line of a bulk operation run with `--only-errors`, e.g. "9 started, 1 failed"
+fn print_tally(verb: &str, done: usize, failed: usize, skipped: usize) {
+    let mut tally = format!("{} {}", done, verb);
+    if failed > 0 {
+        tally.push_str(&format!(", {} failed", failed));
+    }
+    if skipped > 0 {
+        tally.push_str(&format!(", {} skipped", skipped));
+    }
+    if failed > 0 {
+        print_status(tally.red());
+    } else {
+        print_status(tally.green());
+    }
+}
+
+/// Reports every failed server of a bulk operation and returns the first error
+fn first_failure(servers: &[(String, ServerInfo)], results: Vec<Result<()>>) -> Result<()> {
+    let mut first = None;
+    for ((name, _), result) in servers.iter().zip(results) {
+        if let Err(e) = result {
+            print_status(format!("Server '{}' failed: {}", name, e).red());
+            first.get_or_insert(e);
+        }
+    }
+    first.map_or(Ok(()), Err)
+}
+
+/// Starts the server and any companion services whose profile is in `profiles`
+fn start_single_server(name: &str, info: &ServerInfo, args: &ComposeArgs, pb: &ProgressBar) -> Result<()> {
+    if let Some(hook) = &info.before_start {
+        pb.set_message(format!("Running before_start hook of {}...", name));
+        run_hook(name, "before_start", hook, "starting", args.ignore_hook_errors)?;
+    }
+    if info.image_digest.is_some() {
+        pb.set_message(format!("Checking the pinned image of {}...", name));
+        ensure_pinned_image(name, info, pb)?;
+    }
+    pb.set_message(format!("Starting server {}...", name));
+    let mut cmd = captured_compose_command(info)?;
+    compose_secrets(&mut cmd, info)?;
+    let output = cmd
+        .args(args.profiles.iter().flat_map(|p| ["--profile", p.as_str()]))
+        .arg("up")
+        .arg("-d")
+        .args(args.force_recreate.then_some("--force-recreate"))
+        .args(args.no_recreate.then_some("--no-recreate"))
+        .args(&args.passthrough)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(docker_error(&output.stderr));
+    }
+
+    if !args.only_errors {
+        pb.suspend(|| print_status(format!("Server '{}' started successfully!", name).green()));
+    }
+    notify(&format!("Server '{}' started", name));
+    Ok(())
+}
+
+/// Whether the local image store has the image under the pinned `repo@digest` reference
+fn has_pinned_image(reference: &str) -> Result<bool> {
+    let output = ProcessCommand::new("docker")
+        .args(["image", "inspect", "--format", "{{join .RepoDigests \"\\n\"}}", reference])
+        .output()?;
+    if is_docker_permission_error(&output.stderr) {
+        return Err(ServerError::DockerPermissionDenied);
+    }
+    Ok(output.status.success() && String::from_utf8_lossy(&output.stdout).lines().any(|d| d.trim() == reference))
+}
+
+/// Pulls the pinned image when the local image store doesn't have it under that digest, and fails
+/// if the registry doesn't serve that exact image anymore
+fn ensure_pinned_image(name: &str, info: &ServerInfo, pb: &ProgressBar) -> Result<()> {
+    let reference = info.image();
+    if has_pinned_image(&reference)? {
+        return Ok(());
+    }
+    let message = format!("The pinned image of '{}' isn't available locally, pulling {}", name, reference);
+    pb.suspend(|| print_status(message.yellow()));
+    let output = ProcessCommand::new("docker").args(["pull", &reference]).output()?;
+    if !output.status.success() {
+        return Err(ServerError::DockerCommandFailed(format!(
+            "couldn't pull the pinned image {}: {}",
+            reference,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    if !has_pinned_image(&reference)? {
+        return Err(ServerError::DockerCommandFailed(format!(
+            "the pulled image doesn't carry the pinned digest {}",
+            reference
+        )));
+    }
+    Ok(())
+}
+
+fn stop_servers(name: Option<String>, parallel: Option<usize>, args: &ComposeArgs) -> Result<()> {
+    let config = load_server_config()?;
+    let pb = create_spinner("Stopping server(s)");
+
+    match name {
+        Some(server_name) => {
+            if let Some(info) = config.servers.get(&server_name) {
+                if stop_single_server(&server_name, info, args, &pb)? {
+                    record_backups(&[&server_name])?;
+                }
+            } else {
+                return Err(ServerError::ServerNotFound(server_name));
+            }
+        }
+        None => {
+            if config.servers.is_empty() {
+                print_status("No servers configured!".yellow());
+                return Ok(());
+            }
+            // One docker ps up front, so idle servers don't each get a docker-compose down
+            let running = running_containers()?;
+            let (servers, idle): (Vec<_>, Vec<_>) = sorted_servers(&config)
+                .into_iter()
+                .partition(|(name, info)| info.container_names(name).iter().any(|c| running.contains(c)));
+            if !idle.is_empty() && !args.only_errors {
+                let names: Vec<&str> = idle.iter().map(|(name, _)| name.as_str()).collect();
+                print_status(format!("Skipping {} server(s) that aren't running: {}", idle.len(), names.join(", ")));
+            }
+            // Recorded afterwards in one go, workers saving the config would overwrite each other
+            let backed_up = Mutex::new(Vec::new());
+            let results = run_parallel(&servers, parallel, |(name, info), pb| {
+                if stop_single_server(name, info, args, pb)? {
+                    backed_up.lock().unwrap().push(name.clone());
+                }
+                Ok(())
+            });
+            let backed_up = backed_up.into_inner().unwrap();
+            record_backups(&backed_up.iter().map(String::as_str).collect::<Vec<_>>())?;
+            if args.only_errors {
+                let failed = results.iter().filter(|result| result.is_err()).count();
+                print_tally("stopped", servers.len() - failed, failed, idle.len());
+            }
+            first_failure(&servers, results)?;
+        }
+    }
+    if args.only_errors {
+        pb.finish_and_clear();
+    } else {
+        pb.finish_with_message("Server stop operation completed!");
+    }
+    Ok(())
+}
+
+/// Stops one server and returns whether its auto backup was taken
+fn stop_single_server(name: &str, info: &ServerInfo, args: &ComposeArgs, pb: &ProgressBar) -> Result<bool> {
+    let backed_up = info.auto_backup && is_server_running(name)? && {
+        pb.set_message(format!("Backing up {} before stopping...", name));
+        auto_backup(name, info)
+    };
+    pb.set_message(format!("Stopping server {}...", name));
+    let own_timeout = args.passthrough.iter().any(|arg| arg.starts_with("-t") || arg.starts_with("--timeout"));
+    let stop_timeout = if own_timeout {
+        Vec::new()
+    } else {
+        ComposeVariant::detect().flag(ComposeFlag::StopTimeout(STOP_TIMEOUT))
+    };
+    // Every profile is enabled so companion containers are taken down too
+    let output = captured_compose_command(info)?
+        .args(info.profile_args())
+        .arg("down")
+        .args(stop_timeout)
+        .args(args.volumes.then_some("--volumes"))
+        .args(args.remove_orphans.then_some("--remove-orphans"))
+        .args(&args.passthrough)
+        .output()?;
+
+    if !output.status.success() {
+        // The auto backup paused saving, and the server is still up
+        if info.auto_backup {
+            let _ = run_rcon(name, "save-on");
+        }
+        return Err(docker_error(&output.stderr));
+    }
+
+    if !args.only_errors {
+        pb.suspend(|| print_status(format!("Server '{}' stopped successfully!", name).green()));
+    }
+    notify(&format!("Server '{}' stopped", name));
+    if let Some(hook) = &info.after_stop {
+        pb.set_message(format!("Running after_stop hook of {}...", name));
+        run_hook(name, "after_stop", hook, "stopped", args.ignore_hook_errors)?;
+    }
+    Ok(backed_up)
+}
+
+/// Backs up a running server that's about to stop. Saving is paused and flushed first so the
+/// archive holds a consistent world. A failed backup is reported but doesn't hold up the stop.
+fn auto_backup(name: &str, info: &ServerInfo) -> bool {
+    let options = BackupOptions { label: Some("auto".to_string()), ..BackupOptions::default() };
+    let result = run_rcon(name, "save-off")
+        .and_then(|_| run_rcon(name, "save-all flush"))
+        .and_then(|_| create_backup(name, info, &options, None));
+    if let Err(e) = &result {
+        print_status(format!("{} Auto backup of '{}' failed, stopping anyway: {}", "Warning:".yellow(), name, e));
+    }
+    result.is_ok()
+}
+
+/// Runs a hook through the shell with BLOCKOPS_SERVER, BLOCKOPS_HOOK and BLOCKOPS_STATUS set,
+/// printing whatever it writes
+fn run_hook(name: &str, hook: &str, command: &str, status: &str, ignore_errors: bool) -> Result<()> {
+    let output = ProcessCommand::new("sh")
+        .args(["-c", command])
+        .env("BLOCKOPS_SERVER", name)
+        .env("BLOCKOPS_HOOK", hook)
+        .env("BLOCKOPS_STATUS", status)
+        .output()?;
+
+    let mut captured = String::from_utf8_lossy(&output.stdout).to_string();
+    captured.push_str(&String::from_utf8_lossy(&output.stderr));
+    for line in captured.lines() {
+        print_status(format!("  [{} {}] {}", name, hook, line));
+    }
+
+    if !output.status.success() {
+        let reason = match output.status.code() {
+            Some(code) => format!("exited with code {}", code),
+            None => "was killed by a signal".to_string(),
+        };
+        if !ignore_errors {
+            return Err(ServerError::HookFailed(name.to_string(), hook.to_string(), reason));
+        }
+        print_status(format!("{} hook of '{}' {}, continuing", hook, name, reason).yellow());
+    }
+    Ok(())
+}
+
+fn configure_hooks(name: &str, before_start: Option<String>, after_stop: Option<String>, clear: bool) -> Result<()> {
+    let mut config = load_server_config()?;
+    let info = config
+        .servers
+        .get_mut(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+
+    let optional = |value: String| (!value.is_empty()).then_some(value);
+    let changed = clear || before_start.is_some() || after_stop.is_some();
+    if clear {
+        info.before_start = None;
+        info.after_stop = None;
+    }
+    if let Some(command) = before_start {
+        info.before_start = optional(command);
+    }
+    if let Some(command) = after_stop {
+        info.after_stop = optional(command);
+    }
+
+    println!("{}", format!("Hooks of '{}':", name).bright_cyan());
+    println!("  before_start: {}", info.before_start.as_deref().unwrap_or("(none)"));
+    println!("  after_stop:   {}", info.after_stop.as_deref().unwrap_or("(none)"));
+    if changed {
+        save_server_config(&config)?;
+    }
+    Ok(())
+}
+
+fn show_logs(name: &str, options: &LogOptions) -> Result<()> {
+    let config = load_server_config()?;
+    if let Some(info) = config.servers.get(name) {
+        println!("{}", format!("\nShowing logs for server '{}':", name).bright_cyan());
+
+        if options.crash {
+            return show_crash_report(name, info);
+        }
+        if let Some(pattern) = &options.follow_until {
+            return follow_logs_until(info, &Regex::new(pattern)?, options.timeout, options.timestamps);
+        }
+        if let Some(pattern) = &options.alert {
+            println!("{}", "Press Ctrl+C to exit".bright_yellow());
+            return follow_logs_with_alerts(name, info, &Regex::new(pattern)?, options);
+        }
+        if options.wait_ready {
+            let pattern = Regex::new(options.ready_pattern.as_deref().unwrap_or(DEFAULT_READY_PATTERN))?;
+            let timeout = options.timeout.unwrap_or(DEFAULT_READY_TIMEOUT);
+            let elapsed = wait_until_ready(name, info, &pattern, timeout, true, current_run_start(name))?;
+            println!("{}", format!("Server '{}' is ready ({:.1}s)", name, elapsed.as_secs_f64()).green());
+            return Ok(());
+        }
+        
+        let mut cmd = if options.container {
+            let mut cmd = ProcessCommand::new("docker");
+            cmd.args(["logs", &format!("mc-{}", name)]);
+            cmd
+        } else {
+            let mut cmd = compose_command(info)?;
+            cmd.arg("logs");
+            cmd
+        };
+
+        // -f and -t are spelled the same by `docker logs` and both Compose variants
+        if options.follow {
+            println!("{}", "Press Ctrl+C to exit".bright_yellow());
+            cmd.arg("-f");
+        }
+        if options.timestamps {
+            cmd.arg("-t");
+        }
+        cmd.args(&options.compose_args);
+
+        let status = cmd.status()?;
+        if !status.success() {
+            if !options.container && !info.compose_file().exists() {
+                return Err(ServerError::DockerCommandFailed(format!(
+                    "Failed to show logs, {} is missing. Try 'logs {} --container'",
+                    info.compose_file().display(),
+                    name
+                )));
+            }
+            return Err(ServerError::DockerCommandFailed("Failed to show logs".to_string()));
+        }
+    } else {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Prints the newest file in `crash-reports/` and the container's log lines around the time it
+/// was written, which usually show what led up to the crash
+fn show_crash_report(name: &str, info: &ServerInfo) -> Result<()> {
+    let crash_dir = Path::new(&info.data_path).join("crash-reports");
+    let newest = fs::read_dir(&crash_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "txt"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max();
+    let Some((written, path)) = newest else {
+        println!("{}", format!("No crash reports found for '{}'.", name).green());
+        return Ok(());
+    };
+
+    let written = chrono::DateTime::<chrono::Utc>::from(written);
+    let local = written.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+    println!("{}", format!("Crash report {} (written {}):", path.display(), local).bright_red());
+    println!("{}", fs::read_to_string(&path)?);
+
+    let since = written - chrono::Duration::seconds(CRASH_LOG_WINDOW);
+    let until = written + chrono::Duration::seconds(5);
+    let output = ProcessCommand::new("docker")
+        .args(["logs", "--timestamps", "--since", &since.to_rfc3339(), "--until", &until.to_rfc3339()])
+        .arg(format!("mc-{}", name))
+        .output()?;
+    if !output.status.success() {
+        if is_docker_permission_error(&output.stderr) {
+            return Err(ServerError::DockerPermissionDenied);
+        }
+        println!("{}", "The container that crashed is gone, so its logs can't be shown.".yellow());
+        return Ok(());
+    }
+
+    // docker logs replays the container's stderr on ours; the timestamps put both back in order
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut lines: Vec<&str> = stdout.lines().chain(stderr.lines()).collect();
+    lines.sort();
+    println!("{}", format!("Log lines from the {}s before the crash:", CRASH_LOG_WINDOW).bright_cyan());
+    if lines.is_empty() {
+        println!("{}", "(none, the logs may have been rotated or the container recreated since)".yellow());
+    }
+    for line in lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Follows the server's logs in the background, sending each line through the returned channel.
+/// With `since`, earlier lines (a reused container's previous runs) are left out. The channel
+/// disconnects once the log stream ends; kill the child to stop following early.
+fn spawn_log_stream(
+    info: &ServerInfo,
+    timestamps: bool,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<(Child, Receiver<String>)> {
+    let variant = ComposeVariant::detect();
+    let mut cmd = compose_command(info)?;
+    cmd.args(["logs", "-f"]).args(variant.flag(ComposeFlag::LogsNoColor));
+    if let Some(since) = since {
+        cmd.args(variant.flag(ComposeFlag::LogsSince(since)));
+    }
+    if timestamps {
+        cmd.arg("-t");
+    }
+    let mut child = cmd.stdout(Stdio::piped()).spawn()?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    Ok((child, rx))
+}
+
+fn follow_logs_until(info: &ServerInfo, pattern: &Regex, timeout: Option<u64>, timestamps: bool) -> Result<()> {
+    let (mut child, lines) = spawn_log_stream(info, timestamps, None)?;
+    let deadline = timeout.map(|secs| Instant::now() + Duration::from_secs(secs));
+    let mut last_line = String::new();
+
+    let result = loop {
+        let line = match next_log_line(&lines, deadline) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => {
+                break Err(ServerError::Timeout(
+                    timeout.unwrap_or_default(),
+                    format!("'{}'", pattern),
+                    last_log_line(&last_line),
+                ))
+            }
+            Err(RecvTimeoutError::Disconnected) => break Err(log_stream_ended(pattern)),
+        };
+
+        println!("{}", line);
+        if pattern.is_match(&line) {
+            break Ok(());
+        }
+        last_line = line;
+    };
+
+    let _ = child.kill();
+    let _ = child.wait();
+    result
+}
+
+/// Watches a just-started server's logs, from `since` on, until `ready` matches. Returns how long
+/// that took, or `Timeout` / `StartupCrashed` if the deadline passes or the server dies first.
+fn wait_until_ready(
+    name: &str,
+    info: &ServerInfo,
+    ready: &Regex,
+    timeout: u64,
+    echo: bool,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Duration> {
+    let crash = Regex::new(STARTUP_CRASH_PATTERN)?;
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(timeout);
+    let (mut child, lines) = spawn_log_stream(info, false, since)?;
+    let mut last_line = String::new();
+
+    let result = loop {
+        match next_log_line(&lines, Some(deadline)) {
+            Ok(line) => {
+                if echo {
+                    print_status(&line);
+                }
+                match classify_startup_line(&line, ready, &crash) {
+                    StartupLine::Crashed => {
+                        break Err(ServerError::StartupCrashed(name.to_string(), line.trim().to_string()))
+                    }
+                    StartupLine::Ready => break Ok(started.elapsed()),
+                    StartupLine::Waiting => last_line = line,
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                break Err(ServerError::Timeout(timeout, format!("'{}' to be ready", name), last_log_line(&last_line)))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                let reason = match is_server_running(name) {
+                    Ok(false) => "container exited".to_string(),
+                    _ => "log stream ended".to_string(),
+                };
+                break Err(ServerError::StartupCrashed(name.to_string(), reason));
+            }
+        }
+    };
+
+    let _ = child.kill();
+    let _ = child.wait();
+    result
+}
+
+/// When the server's container last started, so waiting on an already started server skips the
+/// logs of its earlier runs. 1.x Compose can only follow from now on, which would miss a server
+/// that's already up, so it reads the whole history instead.
+fn current_run_start(name: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if ComposeVariant::detect() == ComposeVariant::V1 {
+        return None;
+    }
+    let output = command_output("docker", &["inspect", "-f", "{{.State.StartedAt}}", &format!("mc-{}", name)])?;
+    chrono::DateTime::parse_from_rfc3339(output.trim()).ok().map(|t| t.with_timezone(&chrono::Utc))
+}
+
+/// Next line from [`spawn_log_stream`], waiting at most until `deadline` when there is one. Past
+/// the deadline this times out even with lines queued, or a server that keeps logging never would.
+fn next_log_line(lines: &Receiver<String>, deadline: Option<Instant>) -> std::result::Result<String, RecvTimeoutError> {
+    match deadline {
+        Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+            Some(left) if !left.is_zero() => lines.recv_timeout(left),
+            _ => Err(RecvTimeoutError::Timeout),
+        },
+        None => lines.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    }
+}
+
+/// What a log line says about a server that's starting up
+#[derive(Debug, PartialEq, Eq)]
+enum StartupLine {
+    Ready,
+    Crashed,
+    Waiting,
+}
+
+/// A line matching both patterns counts as a crash, the server won't stay up after it
+fn classify_startup_line(line: &str, ready: &Regex, crash: &Regex) -> StartupLine {
+    if crash.is_match(line) {
+        StartupLine::Crashed
+    } else if ready.is_match(line) {
+        StartupLine::Ready
+    } else {
+        StartupLine::Waiting
+    }
+}
+
+/// Describes the last log line seen before a wait timed out
+fn last_log_line(line: &str) -> String {
+    match line.trim() {
+        "" => "no log output".to_string(),
+        line => format!("log line '{}'", line),
+    }
+}
+
+/// Polls the container's healthcheck (the itzg image ships one) until it reports healthy
+fn wait_until_healthy(name: &str, timeout: u64) -> Result<Duration> {
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(timeout);
+    let container = format!("mc-{}", name);
+    let mut last_check = String::new();
+
+    loop {
+        let output = ProcessCommand::new("docker")
+            .args(["inspect", "-f", "{{json .State}}", &container])
+            .output()?;
+        if !output.status.success() {
+            return Err(docker_error(&output.stderr));
+        }
+        let state: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+
+        if state["Running"] == false {
+            return Err(ServerError::StartupCrashed(name.to_string(), "container exited".to_string()));
+        }
+        let health = &state["Health"];
+        if health.is_null() {
+            return Err(ServerError::DockerCommandFailed(format!("container {} has no healthcheck", container)));
+        }
+        if let Some(log) = health["Log"].as_array().and_then(|log| log.last()) {
+            last_check = log["Output"].as_str().unwrap_or_default().trim().to_string();
+        }
+
+        let status = health["Status"].as_str().unwrap_or("unknown");
+        match status {
+            "healthy" => return Ok(started.elapsed()),
+            "unhealthy" => {
+                return Err(ServerError::StartupCrashed(
+                    name.to_string(),
+                    format!("healthcheck failed: {}", last_check),
+                ))
+            }
+            _ => {}
+        }
+
+        if Instant::now() >= deadline {
+            let seen = match last_check.as_str() {
+                "" => format!("health {}, no checks yet", status),
+                check => format!("health {}, last check '{}'", status, check),
+            };
+            return Err(ServerError::Timeout(timeout, format!("'{}' to become healthy", name), seen));
+        }
+        std::thread::sleep(HEALTH_POLL_INTERVAL);
+    }
+}
+
+fn check_health(name: &str) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    if !is_server_running(name)? {
+        return Err(ServerError::ServerNotRunning(name.to_string()));
+    }
+
+    let container = format!("mc-{}", name);
+    let probe = info.healthcheck_cmd.as_deref().unwrap_or(DEFAULT_HEALTHCHECK_CMD);
+    println!("{}", format!("\nRunning '{}' in {}:", probe, container).bright_cyan());
+    let output = ProcessCommand::new("docker")
+        .args(["exec", &container, "sh", "-c", probe])
+        .output()?;
+    let result = String::from_utf8_lossy(&output.stdout) + String::from_utf8_lossy(&output.stderr);
+    if !result.trim().is_empty() {
+        println!("{}", result.trim_end());
+    }
+    if output.status.success() {
+        println!("{}", "Healthcheck passed".green());
+    } else {
+        println!("{}", format!("Healthcheck failed ({})", output.status).red());
+    }
+
+    let output = ProcessCommand::new("docker")
+        .args(["inspect", "-f", "{{json .State.Health}}", &container])
+        .output()?;
+    if !output.status.success() {
+        return Err(docker_error(&output.stderr));
+    }
+    let health: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    if health.is_null() {
+        println!("{}", "The container has no healthcheck configured.".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "\n{} {}",
+        "Docker health status:".bright_cyan(),
+        health["Status"].as_str().unwrap_or("unknown")
+    );
+    for entry in health["Log"].as_array().into_iter().flatten() {
+        let code = entry["ExitCode"].as_i64().unwrap_or(-1);
+        let marker = if code == 0 { "ok".green() } else { format!("exit {}", code).red() };
+        println!(
+            "[{}] {} {}",
+            entry["Start"].as_str().unwrap_or_default(),
+            marker,
+            entry["Output"].as_str().unwrap_or_default().trim()
+        );
+    }
+    Ok(())
+}
+
+fn wait_for_servers(name: Option<String>, options: &WaitOptions) -> Result<()> {
+    let config = load_server_config()?;
+    let ready = Regex::new(options.ready_pattern.as_deref().unwrap_or(DEFAULT_READY_PATTERN))?;
+    let servers: Vec<(String, ServerInfo)> = match name {
+        Some(name) => config.servers.get(&name).map(|info| (name, info.clone())).into_iter().collect(),
+        None => sorted_servers(&config),
+    };
+
+    // Waiting is idle work, so every server is watched at once
+    let results = run_parallel(&servers, Some(servers.len().max(1)), |(name, info), pb| {
+        pb.set_message(format!("Waiting for {} to be ready...", name));
+        let timeout = options.timeout.unwrap_or(DEFAULT_READY_TIMEOUT);
+        // A Java mismatch crashes the start, so say why before the wait runs into it. A container
+        // that already exited can't be asked, and the crash report covers that case.
+        if let Ok(Some(warning)) = java_version_warning(name, info) {
+            pb.suspend(|| println!("{} {}", "Warning:".yellow(), warning));
+        }
+        let elapsed = if options.wait_healthy {
+            wait_until_healthy(name, timeout)?
+        } else {
+            wait_until_ready(name, info, &ready, timeout, false, current_run_start(name))?
+        };
+        pb.suspend(|| println!("{}", format!("Server '{}' is ready ({:.1}s)", name, elapsed.as_secs_f64()).green()));
+        Ok(())
+    });
+    first_failure(&servers, results)
+}
+
+/// Restarts the running servers among `name` or those tagged `tag`, all at once or with `rolling`
+/// one after the other. A rolling restart stops at the first server that doesn't come back, so a
+/// bad change never takes down more than one server.
+fn restart_servers(
+    name: Option<&str>,
+    tag: Option<&str>,
+    rolling: bool,
+    warning: u64,
+    wait: &WaitOptions,
+) -> Result<()> {
+    let config = load_server_config()?;
+    let servers: Vec<(String, ServerInfo)> = match name {
+        Some(name) => {
+            let info = config
+                .servers
+                .get(name)
+                .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+            vec![(name.to_string(), info.clone())]
+        }
+        None => sorted_servers(&config)
+            .into_iter()
+            .filter(|(_, info)| tag.is_none_or(|tag| info.tags.iter().any(|t| t == tag)))
+            .collect(),
+    };
+    if let (Some(tag), true) = (tag, servers.is_empty()) {
+        return Err(ServerError::InvalidValue("tag".to_string(), format!("no server is tagged '{}'", tag)));
+    }
+    let running = running_containers()?;
+    let (servers, idle): (Vec<_>, Vec<_>) =
+        servers.into_iter().partition(|(name, _)| running.contains(&format!("mc-{}", name)));
+    if let (Some(name), true) = (name, servers.is_empty()) {
+        return Err(ServerError::ServerNotRunning(name.to_string()));
+    }
+    if !idle.is_empty() {
+        let names: Vec<&str> = idle.iter().map(|(name, _)| name.as_str()).collect();
+        println!("Skipping {} server(s) that aren't running: {}", idle.len(), names.join(", "));
+    }
+    if servers.is_empty() {
+        println!("{}", "No running servers to restart.".yellow());
+        return Ok(());
+    }
+
+    let ready = Regex::new(wait.ready_pattern.as_deref().unwrap_or(DEFAULT_READY_PATTERN))?;
+    let names: Vec<&str> = servers.iter().map(|(name, _)| name.as_str()).collect();
+    let started = Instant::now();
+    let mut timings = Vec::new();
+    if rolling {
+        println!("{}", format!("Rolling restart of {} server(s): {}", servers.len(), names.join(" -> ")).bright_cyan());
+        for (i, (name, info)) in servers.iter().enumerate() {
+            println!("\n{}", format!("[{}/{}] Restarting '{}'", i + 1, servers.len(), name).bright_cyan());
+            announce_restart(&[name.as_str()], warning);
+            match restart_one(name, info, wait, &ready) {
+                Ok(elapsed) => timings.push((name.clone(), elapsed)),
+                Err(e) => {
+                    let left = &names[i + 1..];
+                    if !left.is_empty() {
+                        let message = format!("Stopping the rolling restart, not restarted: {}", left.join(", "));
+                        println!("{}", message.red());
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    } else {
+        announce_restart(&names, warning);
+        let finished = Mutex::new(Vec::new());
+        let results = run_parallel(&servers, Some(servers.len()), |(name, info), _| {
+            let elapsed = restart_one(name, info, wait, &ready)?;
+            finished.lock().unwrap().push((name.clone(), elapsed));
+            Ok(())
+        });
+        timings = finished.into_inner().unwrap();
+        first_failure(&servers, results)?;
+    }
+
+    println!(
+        "\n{}",
+        format!("Restarted {} server(s) in {:.1}s:", timings.len(), started.elapsed().as_secs_f64()).green()
+    );
+    let width = timings.iter().map(|(name, _)| name.len()).max().unwrap_or_default();
+    for (name, elapsed) in timings {
+        println!("  {:<width$}  back after {:.1}s", name, elapsed.as_secs_f64());
+    }
+    Ok(())
+}
+
+/// Warns players of the servers in chat and gives them `warning` seconds. Servers whose RCON
+/// doesn't answer are restarted regardless.
+fn announce_restart(names: &[&str], warning: u64) {
+    if warning == 0 {
+        return;
+    }
+    let message = format!("say The server restarts in {}, you can rejoin in a minute", format_duration(warning));
+    for name in names {
+        if let Err(e) = run_rcon(name, &message) {
+            println!("{} couldn't warn the players of '{}': {}", "Warning:".yellow(), name, e);
+        }
+    }
+    println!("Warned players, restarting in {}...", format_duration(warning));
+    std::thread::sleep(Duration::from_secs(warning));
+}
+
+/// Stops and starts one server, then waits until it's ready; returns how long it took
+fn restart_one(name: &str, info: &ServerInfo, wait: &WaitOptions, ready: &Regex) -> Result<Duration> {
+    let started = Instant::now();
+    let args = ComposeArgs { ready_timeout: wait.timeout, ..Default::default() };
+    stop_servers(Some(name.to_string()), None, &args)?;
+    let since = chrono::Utc::now();
+    start_servers(Some(name.to_string()), None, &args)?;
+    let timeout = wait.timeout.unwrap_or(DEFAULT_READY_TIMEOUT);
+    if wait.wait_healthy {
+        wait_until_healthy(name, timeout)?;
+    } else {
+        wait_until_ready(name, info, ready, timeout, false, Some(since))?;
+    }
+    let elapsed = started.elapsed();
+    println!("{}", format!("Server '{}' is ready ({:.1}s)", name, elapsed.as_secs_f64()).green());
+    Ok(elapsed)
+}
+
+/// Waits for every running target server to empty, broadcasting reminders over RCON. All servers
+/// are drained at once; if any doesn't empty in time (and --kick isn't set) nothing gets stopped.
+fn drain_servers(name: Option<&str>, options: &DrainOptions) -> Result<()> {
+    let config = load_server_config()?;
+    let running = running_containers()?;
+    let servers: Vec<(String, ServerInfo)> = match name {
+        Some(name) => {
+            let info = config
+                .servers
+                .get(name)
+                .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+            vec![(name.to_string(), info.clone())]
+        }
+        None => sorted_servers(&config),
+    };
+    let servers: Vec<_> = servers
+        .into_iter()
+        .filter(|(name, _)| running.contains(&format!("mc-{}", name)))
+        .collect();
+
+    let results = run_parallel(&servers, Some(servers.len().max(1)), |(name, _), _| drain_server(name, options));
+    first_failure(&servers, results)
+}
+
+fn drain_server(name: &str, options: &DrainOptions) -> Result<()> {
+    let timeout = options.drain_timeout.unwrap_or(DEFAULT_DRAIN_TIMEOUT);
+    let deadline = Instant::now() + Duration::from_secs(timeout);
+    let mut next_reminder = Instant::now();
+    loop {
+        let players = online_players(name)?;
+        if players.is_empty() {
+            println!("{}", format!("Server '{}' is empty.", name).green());
+            return Ok(());
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            if !options.kick {
+                return Err(ServerError::Timeout(
+                    timeout,
+                    format!("{} player(s) to leave '{}'", players.len(), name),
+                    format!("still online: {}", players.join(", ")),
+                ));
+            }
+            for player in &players {
+                run_rcon(name, &format!("kick {} {}", player, options.kick_message))?;
+            }
+            println!("Kicked {} from '{}'.", players.join(", "), name);
+            return Ok(());
+        }
+        if now >= next_reminder {
+            let remaining = deadline.saturating_duration_since(now).as_secs_f64().ceil() as u64;
+            let ending = if options.kick { "remaining players will be kicked" } else { "please log out" };
+            run_rcon(
+                name,
+                &format!("say The server is stopping for maintenance in {}, {}", format_duration(remaining), ending),
+            )?;
+            println!("Waiting for {} to leave '{}' ({} left)", players.join(", "), name, format_duration(remaining));
+            next_reminder = now + DRAIN_REMINDER_INTERVAL;
+        }
+        std::thread::sleep(DRAIN_POLL_INTERVAL.min(deadline.saturating_duration_since(now)));
+    }
+}
+
+fn follow_logs_with_alerts(name: &str, info: &ServerInfo, pattern: &Regex, options: &LogOptions) -> Result<()> {
+    let (mut child, lines) = spawn_log_stream(info, options.timestamps, None)?;
+    for line in lines {
+        if pattern.is_match(&line) {
+            println!("{}", line.bright_red());
+            run_alert(name, &line, options.alert_cmd.as_deref());
+        } else {
+            println!("{}", line);
+        }
+    }
+    let _ = child.wait();
+    Ok(())
+}
+
+/// Runs the alert hook without blocking the log stream; failures are reported but not fatal
+fn run_alert(name: &str, line: &str, command: Option<&str>) {
+    let mut cmd = match command {
+        Some(command) => {
+            let mut cmd = ProcessCommand::new("sh");
+            cmd.args(["-c", command, "blockops-alert", line]);
+            cmd
+        }
+        None if cfg!(target_os = "macos") => {
+            let mut cmd = ProcessCommand::new("osascript");
+            let script = format!(
+                "display notification {:?} with title {:?}",
+                line,
+                format!("BlockOps: {}", name)
+            );
+            cmd.args(["-e", &script]);
+            cmd
+        }
+        None => {
+            let mut cmd = ProcessCommand::new("notify-send");
+            cmd.args([&format!("BlockOps: {}", name), line]);
+            cmd
+        }
+    };
+
+    let line = line.to_string();
+    match cmd.stdin(Stdio::piped()).stdout(Stdio::null()).spawn() {
+        Ok(mut child) => {
+            std::thread::spawn(move || {
+                if let Some(mut stdin) = child.stdin.take() {
+                    let _ = writeln!(stdin, "{}", line);
+                }
+                let _ = child.wait();
+            });
+        }
+        Err(e) => eprintln!("{}", format!("Alert command failed: {}", e).yellow()),
+    }
+}
+
+fn log_stream_ended(pattern: &Regex) -> ServerError {
+    ServerError::DockerCommandFailed(format!("log stream ended before a line matched '{}'", pattern))
+}
+
+fn attach_console(name: &str, detach_keys: &str, tee: Option<&Path>) -> Result<()> {
+    let config = load_server_config()?;
+    if config.servers.contains_key(name) {
+        if !is_server_running(name)? {
+            return Err(ServerError::ServerNotRunning(name.to_string()));
+        }
+        let keys = detach_keys
+            .split(',')
+            .map(|key| key.strip_prefix("ctrl-").map_or(key.to_string(), |k| format!("Ctrl+{}", k.to_uppercase())))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("{}", format!("\nAttaching to server '{}' console:", name).bright_cyan());
+        println!("{}", format!("Type 'exit' or press {} to detach", keys).bright_yellow());
+        // The console is rcon-cli in its own exec session, so Ctrl+C ends that and never reaches the server
+        println!("{}", "Ctrl+C only closes the console, the server keeps running".bright_yellow());
+
+        let args = ["exec", "-it", "--detach-keys", detach_keys, &format!("mc-{}", name), "rcon-cli"];
+        match tee {
+            Some(path) => record_console(&args, path)?,
+            None => {
+                let _ = ProcessCommand::new("docker").args(args).status()?;
+            }
+        }
+    } else {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Runs the docker console command under `script`, which sits between the terminal and docker
+/// on a PTY of its own and appends everything both ways to `path`, flushing after each write so
+/// the transcript is complete even if the session is killed
+fn record_console(docker_args: &[&str], path: &Path) -> Result<()> {
+    // Fail on an unwritable path before the console takes over the terminal
+    fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    let mut command = ProcessCommand::new("script");
+    if cfg!(target_os = "macos") {
+        command.args(["-q", "-a", "-F"]).arg(path).arg("docker").args(docker_args);
+    } else {
+        let docker = std::iter::once("docker")
+            .chain(docker_args.iter().copied())
+            .map(shell_quote)
+            .collect::<Vec<_>>()
+            .join(" ");
+        command.args(["-q", "-f", "-a", "-c", &docker]).arg(path);
+    }
+    let status = command.status().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => ServerError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            "--tee needs the 'script' tool (util-linux or bsdutils)",
+        )),
+        _ => ServerError::Io(e),
+    })?;
+    if status.success() {
+        println!("{}", format!("Transcript saved to {}", path.display()).green());
+    } else {
+        println!("{}", format!("Console exited with {}, transcript is in {}", status, path.display()).yellow());
+    }
+    Ok(())
+}
+
+/// Quotes `value` for `sh -c` unless it only holds characters the shell leaves alone
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "-_.,:/=@+".contains(c)) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+fn exec_in_server(name: &str, args: &[String]) -> Result<()> {
+    let config = load_server_config()?;
+    if !config.servers.contains_key(name) {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
+    if !is_server_running(name)? {
+        return Err(ServerError::ServerNotRunning(name.to_string()));
+    }
+
+    // Only ask for a TTY when we have one, so `exec` also works in pipes and scripts
+    let mut command = ProcessCommand::new("docker");
+    command.arg("exec").arg("-i");
+    if io::stdin().is_terminal() && io::stdout().is_terminal() {
+        command.arg("-t");
+    }
+    let status = command.arg(format!("mc-{}", name)).args(args).status()?;
+
+    // Hand the command's exit code straight back to the caller
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+fn run_rcon(name: &str, command: &str) -> Result<String> {
+    let output = ProcessCommand::new("docker")
+        .args(["exec", &format!("mc-{}", name), "rcon-cli"])
+        .args(command.split_whitespace())
+        .output()?;
+
+    if !output.status.success() {
+        if is_docker_permission_error(&output.stderr) {
+            return Err(ServerError::DockerPermissionDenied);
+        }
+        return Err(ServerError::RconFailed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(strip_color_codes(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Removes Minecraft `§x` formatting codes and ANSI escapes from RCON output
+fn strip_color_codes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '§' => {
+                chars.next();
+            }
+            '\x1b' => {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+fn backup_server(name: &str, options: &BackupOptions) -> Result<()> {
+    let config = load_server_config()?;
+    if let Some(info) = config.servers.get(name) {
+        if info.archived {
+            return Err(ServerError::ServerArchived(name.to_string()));
+        }
+        let passphrase = options.encrypt.then(|| backup_passphrase(true)).transpose()?;
+        if options.stdout {
+            stream_backup(info, options, passphrase.as_ref())?;
+        } else {
+            create_backup(name, info, options, passphrase.as_ref())?;
+        }
+        // A single-world backup doesn't cover the rest of the data dir
+        if options.worlds.is_empty() {
+            record_backups(&[name])?;
+        }
+    } else {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Passphrase for encrypting or decrypting backups. Only ever held in memory; taken from the
+/// environment or the OS keyring for unattended runs, and saved to the keyring with `--use-keyring`.
+fn backup_passphrase(confirm: bool) -> Result<SecretString> {
+    if let Ok(passphrase) = std::env::var(BACKUP_PASSPHRASE_ENV) {
+        return Ok(SecretString::from(passphrase));
+    }
+    if let Ok(Some(passphrase)) = secrets::load(BACKUP_PASSPHRASE_ACCOUNT) {
+        return Ok(SecretString::from(passphrase));
+    }
+    require_interactive(&format!(
+        "set {} to the passphrase, or save it once with --use-keyring",
+        BACKUP_PASSPHRASE_ENV
+    ))?;
+    let mut prompt = Password::new().with_prompt("Backup passphrase");
+    if confirm {
+        prompt = prompt.with_confirmation("Repeat passphrase", "Passphrases don't match");
+    }
+    let passphrase = prompt.interact()?;
+    // On stderr like the prompt, `backup --stdout` writes the archive to stdout
+    if USE_KEYRING.load(Ordering::Relaxed) {
+        match secrets::store(BACKUP_PASSPHRASE_ACCOUNT, &passphrase) {
+            Ok(()) => eprintln!("{}", "Saved the passphrase in the OS keyring, later backups won't ask for it.".green()),
+            Err(e) => eprintln!("{} couldn't save the passphrase in the OS keyring: {}", "Warning:".yellow(), e),
+        }
+    }
+    Ok(SecretString::from(passphrase))
+}
+
+/// Archives a server's data and returns the archive path, encrypting it when a passphrase is
+/// given. Doesn't touch the server config, so it can run for several servers at once.
+fn create_backup(
+    name: &str,
+    info: &ServerInfo,
+    options: &BackupOptions,
+    passphrase: Option<&SecretString>,
+) -> Result<PathBuf> {
+    let out = options.out.as_deref();
+    let contents = backup_contents(info, &options.worlds)?;
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let mut file_name = match &options.label {
+        Some(label) => format!("{}_{}_{}.tar.gz", name, timestamp, label),
+        None => format!("{}_{}.tar.gz", name, timestamp),
+    };
+    if passphrase.is_some() {
+        file_name = format!("{}.{}", file_name, ENCRYPTED_EXTENSION);
+    }
+    let backup_file = match out {
+        Some(out) if out.is_dir() => out.join(file_name),
+        Some(out) => out.to_path_buf(),
+        None => config_dir().join(BACKUP_DIR).join(file_name),
+    };
+
+    // tar runs inside the data directory, so the archive path must be absolute
+    let backup_dir = match backup_file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+        _ => std::env::current_dir()?,
+    };
+    let backup_file = backup_dir.join(backup_file.file_name().unwrap_or_default());
+
+    check_writable(&backup_dir)?;
+    let mut needed = 0;
+    for path in &contents {
+        let path = Path::new(&info.data_path).join(path);
+        needed += if path.is_dir() { dir_size(&path)? } else { fs::metadata(&path)?.len() };
+    }
+    check_free_space(&backup_dir, needed)?;
+
+    let pb = create_spinner("Creating backup");
+
+    // Create tar.gz archive; encrypted archives are streamed so the plain one never hits the disk
+    let (status, stderr) = match passphrase {
+        Some(passphrase) => {
+            let mut child = ProcessCommand::new("tar")
+                .current_dir(&info.data_path)
+                .args(["-czf", "-"])
+                .args(&contents)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+            let stderr = drain_stderr(&mut child);
+            let stdout = child.stdout.take().expect("stdout is piped");
+            let encrypted = File::create(&backup_file).and_then(|file| encrypt::encrypt(stdout, file, passphrase));
+            let status = child.wait()?;
+            if encrypted.is_err() || !status.success() {
+                let _ = fs::remove_file(&backup_file);
+            }
+            encrypted?;
+            (status, stderr.join().unwrap_or_default())
+        }
+        None => {
+            let output = ProcessCommand::new("tar")
+                .current_dir(&info.data_path)
+                .args(["-czf", backup_file.to_str().unwrap()])
+                .args(&contents)
+                .output()?;
+            (output.status, output.stderr)
+        }
+    };
+
+    if !status.success() {
+        return Err(ServerError::DockerCommandFailed(String::from_utf8_lossy(&stderr).to_string()));
+    }
+
+    pb.finish_with_message(format!("Backup created: {}", backup_file.display()));
+
+    // Retention only applies to the managed backups directory
+    if out.is_none() {
+        if let Some(keep) = load_settings()?.defaults.backup_retention {
+            prune_backups(name, keep)?;
+        }
+    }
+    Ok(backup_file)
+}
+
+fn estimate_backups(name: Option<&str>, options: &BackupOptions) -> Result<()> {
+    let config = load_server_config()?;
+    let servers = match name {
+        Some(name) => {
+            let info = config
+                .servers
+                .get(name)
+                .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+            if info.archived {
+                return Err(ServerError::ServerArchived(name.to_string()));
+            }
+            vec![(name.to_string(), info.clone())]
+        }
+        // Like `backup --all`, archived servers have nothing to back up
+        None => sorted_servers(&config).into_iter().filter(|(_, info)| !info.archived).collect(),
+    };
+
+    let (mut total_size, mut total_archive, mut total_seconds) = (0, 0, 0.0);
+    for (name, info) in &servers {
+        let files = backup_files(info, &options.worlds)?;
+        let size: u64 = files.iter().map(|(_, len)| len).sum();
+        let pb = create_spinner(&format!("Sampling {}", name));
+        let sample = sample_compression(&files, size);
+        pb.finish_and_clear();
+        let (sampled, compressed, elapsed) = sample?;
+
+        let ratio = if sampled == 0 { 1.0 } else { compressed as f64 / sampled as f64 };
+        let seconds = if sampled == 0 { 0.0 } else { elapsed.as_secs_f64() * size as f64 / sampled as f64 };
+        let archive = (size as f64 * ratio) as u64;
+        println!("\n{}", format!("Backup estimate for '{}' (nothing was archived):", name).bright_cyan());
+        println!("  Files: {}, {} uncompressed", files.len(), format_size(size));
+        println!(
+            "  Archive: ~{} ({:.0}% of the data, from a {} sample)",
+            format_size(archive),
+            ratio * 100.0,
+            format_size(sampled)
+        );
+        println!("  Duration: ~{}", format_duration(seconds.ceil() as u64));
+        total_size += size;
+        total_archive += archive;
+        total_seconds += seconds;
+    }
+    if servers.len() > 1 {
+        println!(
+            "\n{}",
+            format!(
+                "All {} servers: {} of data, ~{} archived, ~{} one after another",
+                servers.len(),
+                format_size(total_size),
+                format_size(total_archive),
+                format_duration(total_seconds.ceil() as u64)
+            )
+            .bright_green()
+        );
+    }
+    Ok(())
+}
+
+/// Every regular file a backup of `info` would contain, with its size. Links are stored as links
+/// and add nothing worth counting.
+fn backup_files(info: &ServerInfo, worlds: &[String]) -> Result<Vec<(PathBuf, u64)>> {
+    fn walk(path: &Path, files: &mut Vec<(PathBuf, u64)>) -> Result<()> {
+        let metadata = fs::symlink_metadata(path)?;
+        if metadata.is_dir() {
+            for entry in fs::read_dir(path)? {
+                walk(&entry?.path(), files)?;
+            }
+        } else if metadata.is_file() {
+            files.push((path.to_path_buf(), metadata.len()));
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    for path in backup_contents(info, worlds)? {
+        walk(&Path::new(&info.data_path).join(path), &mut files)?;
+    }
+    Ok(files)
+}
+
+/// Gzips a sample of `files` the way tar would and returns the bytes read, the compressed size
+/// and the time it took. Chunks are taken at even offsets through the data, so a world of
+/// already-compressed region files and a few large logs are both weighted by their size.
+fn sample_compression(files: &[(PathBuf, u64)], total: u64) -> Result<(u64, u64, Duration)> {
+    let started = Instant::now();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut sampled = 0;
+    if total <= BACKUP_SAMPLE_SIZE {
+        for (path, _) in files {
+            sampled += io::copy(&mut File::open(path)?, &mut encoder)?;
+        }
+    } else {
+        let chunks = BACKUP_SAMPLE_SIZE / BACKUP_SAMPLE_CHUNK;
+        let step = total / chunks;
+        let (mut index, mut file_start) = (0, 0);
+        for chunk in 0..chunks {
+            let offset = chunk * step;
+            while file_start + files[index].1 <= offset {
+                file_start += files[index].1;
+                index += 1;
+            }
+            let mut file = File::open(&files[index].0)?;
+            file.seek(SeekFrom::Start(offset - file_start))?;
+            sampled += io::copy(&mut file.take(BACKUP_SAMPLE_CHUNK), &mut encoder)?;
+        }
+    }
+    let compressed = encoder.finish()?.len() as u64;
+    Ok((sampled, compressed, started.elapsed()))
+}
+
+/// Writes the archive `create_backup` would make to stdout instead of a file. Retention and
+/// the free space check don't apply, as nothing is stored here.
+fn stream_backup(info: &ServerInfo, options: &BackupOptions, passphrase: Option<&SecretString>) -> Result<()> {
+    if io::stdout().is_terminal() {
+        return Err(ServerError::InvalidValue(
+            "stdout".to_string(),
+            "refusing to write an archive to a terminal, pipe or redirect it".to_string(),
+        ));
+    }
+    let contents = backup_contents(info, &options.worlds)?;
+    let pb = create_spinner("Streaming backup");
+    let mut child = ProcessCommand::new("tar")
+        .current_dir(&info.data_path)
+        .args(["-czf", "-"])
+        .args(&contents)
+        .stdout(if passphrase.is_some() { Stdio::piped() } else { Stdio::inherit() })
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stderr = drain_stderr(&mut child);
+    let encrypted = match passphrase {
+        Some(passphrase) => {
+            let stdout = child.stdout.take().expect("stdout is piped");
+            encrypt::encrypt(stdout, io::stdout().lock(), passphrase)
+        }
+        None => Ok(()),
+    };
+    let status = child.wait()?;
+    let stderr = stderr.join().unwrap_or_default();
+    pb.finish_and_clear();
+    encrypted?;
+    if !status.success() {
+        return Err(ServerError::DockerCommandFailed(String::from_utf8_lossy(&stderr).to_string()));
+    }
+    eprintln!("{}", "Backup written to stdout.".green());
+    Ok(())
+}
+
+/// Reads the child's piped stderr on a thread until it exits. Needed whenever its stdout is
+/// consumed first, or a child that fills the stderr pipe blocks and never finishes that output.
+fn drain_stderr(child: &mut Child) -> std::thread::JoinHandle<Vec<u8>> {
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = stderr.read_to_end(&mut buffer);
+        buffer
+    })
+}
+
+/// Takes the safety backup for a destructive command and says where it went. Retention isn't
+/// applied, so the backup can't push out regular ones or the archive being restored.
+fn backup_before(name: &str, info: &ServerInfo, action: &str) -> Result<PathBuf> {
+    let options = BackupOptions {
+        out: Some(config_dir().join(BACKUP_DIR)),
+        label: Some(format!("pre-{}", action)),
+        ..BackupOptions::default()
+    };
+    let backup = create_backup(name, info, &options, None)?;
+    print_status(format!("Pre-{} backup saved to {}", action, backup.display()).green());
+    Ok(backup)
+}
+
+fn record_backups(names: &[&str]) -> Result<()> {
+    let mut config = load_server_config()?;
+    let now = chrono::Utc::now();
+    for name in names {
+        if let Some(info) = config.servers.get_mut(*name) {
+            info.last_backup = Some(now);
+        }
+    }
+    save_server_config(&config)?;
+    Ok(())
+}
+
+/// Latest modification time of any file or directory under `path`
+fn newest_mtime(path: &Path) -> Result<Option<SystemTime>> {
+    let mut newest = fs::metadata(path)?.modified().ok();
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let child = newest_mtime(&entry?.path())?;
+            newest = newest.max(child);
+        }
+    }
+    Ok(newest)
+}
+
+/// Whether anything in the server's data dir changed after its last recorded backup
+fn changed_since_last_backup(info: &ServerInfo) -> Result<bool> {
+    let Some(last_backup) = info.last_backup else {
+        return Ok(true);
+    };
+    Ok(match newest_mtime(Path::new(&info.data_path))? {
+        Some(modified) => chrono::DateTime::<chrono::Utc>::from(modified) > last_backup,
+        None => true,
+    })
+}
+
+fn backup_all_servers(options: &BackupOptions, parallel: Option<usize>, since_last_backup: bool) -> Result<()> {
+    let config = load_server_config()?;
+    // Every server would be written to the same file
+    if let Some(out) = options.out.as_deref().filter(|out| !out.is_dir()) {
+        return Err(ServerError::InvalidValue(
+            "to".to_string(),
+            format!("{} isn't a directory, backing up every server needs one", out.display()),
+        ));
+    }
+    if config.servers.is_empty() {
+        print_status("No servers configured!".yellow());
+        return Ok(());
+    }
+
+    // The archive already holds everything an archived server has
+    let mut servers: Vec<_> = sorted_servers(&config).into_iter().filter(|(_, info)| !info.archived).collect();
+    if since_last_backup {
+        let mut unchanged = Vec::new();
+        for (name, info) in &servers {
+            if !changed_since_last_backup(info)? {
+                unchanged.push(name.clone());
+            }
+        }
+        if !unchanged.is_empty() {
+            print_status(format!("Skipping unchanged since their last backup: {}", unchanged.join(", ")).yellow());
+        }
+        servers.retain(|(name, _)| !unchanged.contains(name));
+    }
+
+    // Asked once up front rather than by every worker
+    let passphrase = options.encrypt.then(|| backup_passphrase(true)).transpose()?;
+    // create_backup shows its own spinner
+    let results: Vec<Result<()>> = run_parallel(&servers, parallel, |(name, info), _| {
+        create_backup(name, info, options, passphrase.as_ref()).map(|_| ())
+    });
+    let backed_up: Vec<&str> = servers
+        .iter()
+        .zip(&results)
+        .filter(|(_, result)| result.is_ok())
+        .map(|((name, _), _)| name.as_str())
+        .collect();
+    if options.worlds.is_empty() {
+        record_backups(&backed_up)?;
+    }
+    first_failure(&servers, results)?;
+    print_status(format!("Backed up {} server(s).", servers.len()).green());
+    Ok(())
+}
+
+/// Whether `file_name` is one of `name`'s archives, `<name>_<YYYYmmdd_HHMMSS>.tar.gz`
+fn is_backup_of(file_name: &str, name: &str) -> bool {
+    backup_time(name, Path::new(file_name)).is_some()
+}
+
+/// A server's archives in the backups directory, oldest first
+fn list_backups(name: &str) -> Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(config_dir().join(BACKUP_DIR))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|f| f.to_str())
+                .is_some_and(|f| is_backup_of(f, name))
+        })
+        .collect();
+
+    // Timestamps sort chronologically, so the newest come last
+    backups.sort();
+    Ok(backups)
+}
+
+/// When a backup was taken, from the local timestamp in its file name
+fn backup_time(name: &str, path: &Path) -> Option<chrono::NaiveDateTime> {
+    parse_backup_name(name, path).map(|(time, _)| time)
+}
+
+/// The `--label` a backup was taken with, if any
+fn backup_label(name: &str, path: &Path) -> Option<String> {
+    parse_backup_name(name, path).and_then(|(_, label)| label)
+}
+
+/// Splits `<name>_<timestamp>[_<label>].tar.gz[.age]` into its timestamp and label
+fn parse_backup_name(name: &str, path: &Path) -> Option<(chrono::NaiveDateTime, Option<String>)> {
+    let file_name = path.file_name()?.to_str()?;
+    let rest = file_name.strip_prefix(name)?.strip_prefix('_')?;
+    let stem = rest
+        .strip_suffix(&format!(".tar.gz.{}", ENCRYPTED_EXTENSION))
+        .or_else(|| rest.strip_suffix(".tar.gz"))?;
+    let (timestamp, label) = match stem.get(BACKUP_TIMESTAMP_LEN..) {
+        Some("") => (stem, None),
+        Some(label) => (&stem[..BACKUP_TIMESTAMP_LEN], Some(label.strip_prefix('_')?.to_string())),
+        None => return None,
+    };
+    let time = chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%d_%H%M%S").ok()?;
+    Some((time, label))
+}
+
+/// Keeps labels to lowercase letters, digits and dashes so they're safe in file names
+fn parse_backup_label(label: &str) -> std::result::Result<String, String> {
+    let mut sanitized = String::new();
+    for c in label.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            sanitized.push(c.to_ascii_lowercase());
+        } else if !sanitized.ends_with('-') {
+            sanitized.push('-');
+        }
+    }
+    let sanitized = sanitized.trim_matches('-');
+    if sanitized.is_empty() {
+        return Err("the label needs at least one letter or digit".to_string());
+    }
+    Ok(sanitized.chars().take(MAX_BACKUP_LABEL_LEN).collect::<String>().trim_end_matches('-').to_string())
+}
+
+/// One line per backup for `backups` and the restore picker
+fn describe_backup(name: &str, path: &Path) -> String {
+    let time = backup_time(name, path).map_or_else(String::new, |t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+    let size = fs::metadata(path).map(|m| format_size(m.len())).unwrap_or_default();
+    let mut line = format!("{:<19}  {:>10}", time, size);
+    if path.extension().is_some_and(|e| e == ENCRYPTED_EXTENSION) {
+        line.push_str("  [encrypted]");
+    }
+    if let Some(label) = backup_label(name, path) {
+        line.push_str(&format!("  {}", label));
+    }
+    line
+}
+
+fn show_backups(name: &str) -> Result<()> {
+    let config = load_server_config()?;
+    if !config.servers.contains_key(name) {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
+    let backups = list_backups(name)?;
+    if backups.is_empty() {
+        println!("No backups of {} yet.", name);
+        return Ok(());
+    }
+    for path in backups.iter().rev() {
+        println!("{}", describe_backup(name, path));
+        println!("  {}", path.display().to_string().dimmed());
+    }
+    Ok(())
+}
+
+/// Asks which of a server's backups to restore, newest first
+fn pick_backup(name: &str) -> Result<PathBuf> {
+    let mut backups = list_backups(name)?;
+    if backups.is_empty() {
+        return Err(ServerError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no backups of {} in the backups directory", name),
+        )));
+    }
+    require_interactive("give the path of the backup to restore")?;
+    backups.reverse();
+    let items: Vec<String> = backups.iter().map(|path| describe_backup(name, path)).collect();
+    let choice = Select::new()
+        .with_prompt("Backup to restore")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    Ok(backups.swap_remove(choice))
+}
+
+/// Deletes all but the `keep` newest backups of a server from the backups directory. Safety
+/// backups (labelled `pre-<action>`) are neither counted nor deleted.
+fn prune_backups(name: &str, keep: usize) -> Result<()> {
+    let mut backups = list_backups(name)?;
+    backups.retain(|path| !backup_label(name, path).is_some_and(|label| label.starts_with("pre-")));
+    let excess = backups.len().saturating_sub(keep);
+    for path in &backups[..excess] {
+        fs::remove_file(path)?;
+        print_status(format!("Removed old backup {}", path.display()));
+    }
+    Ok(())
+}
+
+/// World folders of a server: the configured list, or the level name from server.properties
+fn server_worlds(info: &ServerInfo) -> Vec<String> {
+    if !info.worlds.is_empty() {
+        return info.worlds.clone();
+    }
+    vec![read_server_property(&info.data_path, "level-name")
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| "world".to_string())]
+}
+
+/// Paths (relative to the data dir) that go into a backup. Without a world selection that's the
+/// whole data dir, otherwise the selected worlds, their Bukkit-style nether/end folders and
+/// server.properties.
+fn backup_contents(info: &ServerInfo, selected: &[String]) -> Result<Vec<String>> {
+    if selected.is_empty() {
+        return Ok(vec![".".to_string()]);
+    }
+
+    let known = server_worlds(info);
+    if let Some(unknown) = selected.iter().find(|w| !known.contains(w)) {
+        return Err(ServerError::InvalidValue(
+            "world".to_string(),
+            format!("'{}' is not one of this server's worlds ({})", unknown, known.join(", ")),
+        ));
+    }
+
+    let data = Path::new(&info.data_path);
+    let mut contents: Vec<String> = selected
+        .iter()
+        .flat_map(|w| [w.clone(), format!("{}_nether", w), format!("{}_the_end", w)])
+        .filter(|p| data.join(p).exists())
+        .collect();
+    if data.join("server.properties").exists() {
+        contents.push("server.properties".to_string());
+    }
+    if contents.is_empty() {
+        return Err(ServerError::InvalidValue("world".to_string(), "none of the selected worlds exist yet".to_string()));
+    }
+    Ok(contents)
+}
+
+/// Lists a backup's top-level entries, failing if it isn't a readable tar.gz of a server data dir
+fn validate_backup_archive(path: &Path, worlds: &[String]) -> Result<Vec<String>> {
+    let output = ProcessCommand::new("tar")
+        .args(["-tzf", &path.to_string_lossy()])
+        .output()?;
+    if !output.status.success() {
+        return Err(ServerError::InvalidBackup(format!(
+            "{} is not a readable tar.gz archive: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let mut entries: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|entry| {
+            let entry = entry.trim_start_matches("./");
+            entry.split('/').next().filter(|top| !top.is_empty()).map(String::from)
+        })
+        .collect();
+    entries.sort();
+    entries.dedup();
+
+    if !entries.iter().any(|e| worlds.contains(e) || e == "server.properties") {
+        return Err(ServerError::InvalidBackup(format!(
+            "{} doesn't look like a backup of this server (no {} or server.properties)",
+            path.display(),
+            worlds.join(", ")
+        )));
+    }
+    Ok(entries)
+}
+
+fn dir_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Like `dir_size`, but skips what can't be read. Also returns whether everything was counted.
+fn readable_dir_size(path: &Path) -> (u64, bool) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return (0, false);
+    };
+    let mut size = 0;
+    let mut complete = true;
+    for entry in entries {
+        match entry.and_then(|e| Ok((e.path(), e.metadata()?))) {
+            Ok((path, metadata)) if metadata.is_dir() => {
+                let (child, child_complete) = readable_dir_size(&path);
+                size += child;
+                complete &= child_complete;
+            }
+            Ok((_, metadata)) => size += metadata.len(),
+            Err(_) => complete = false,
+        }
+    }
+    (size, complete)
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn check_writable(dir: &Path) -> Result<()> {
+    let probe = dir.join(format!(".blockops-write-test-{}", std::process::id()));
+    fs::write(&probe, b"")?;
+    fs::remove_file(probe)?;
+    Ok(())
+}
+
+fn check_free_space(dir: &Path, needed: u64) -> Result<()> {
+    let available = fs2::available_space(dir)?;
+    if available < needed {
+        return Err(ServerError::InsufficientSpace(
+            dir.display().to_string(),
+            needed,
+            available,
+        ));
+    }
+    Ok(())
+}
+
+/// Estimates how much space extracting a `.tar.gz` takes. gzip stores the uncompressed size
+/// modulo 2^32 in its last four bytes, so the compressed size is used as a floor.
+fn extracted_size(archive: &Path) -> Result<u64> {
+    let mut file = File::open(archive)?;
+    let compressed = file.metadata()?.len();
+    if compressed < 4 {
+        return Ok(compressed);
+    }
+    file.seek(SeekFrom::End(-4))?;
+    let mut trailer = [0u8; 4];
+    file.read_exact(&mut trailer)?;
+    Ok(compressed.max(u32::from_le_bytes(trailer) as u64))
+}
+
+fn restore_server(name: &str, backup_path: &Path, backup: bool) -> Result<()> {
+    let config = load_server_config()?;
+    if let Some(info) = config.servers.get(name) {
+        // The next `unarchive` would replace whatever was restored
+        if info.archived {
+            return Err(ServerError::ServerArchived(name.to_string()));
+        }
+        if !backup_path.exists() {
+            return Err(ServerError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Backup file not found",
+            )));
+        }
+        if backup_path.extension().is_some_and(|e| e == ENCRYPTED_EXTENSION) {
+            return restore_encrypted(name, backup_path, backup);
+        }
+        // tar runs inside the data directory, so the archive path must be absolute
+        let backup_path = backup_path.canonicalize()?;
+
+        // Check the archive before anything is touched
+        let entries = validate_backup_archive(&backup_path, &server_worlds(info))?;
+        check_free_space(Path::new(&info.data_path), extracted_size(&backup_path)?)?;
+        println!("{}", "Archive contents:".bright_cyan());
+        for entry in &entries {
+            println!("- {}", entry);
+        }
+
+        // Stop server if running
+        stop_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
+
+        if backup {
+            backup_before(name, info, "restore")?;
+            record_backups(&[name])?;
+        }
+
+        let pb = create_spinner("Restoring backup");
+
+        // Extract backup
+        let output = ProcessCommand::new("tar")
+            .current_dir(&info.data_path)
+            .args(["-xzf", backup_path.to_str().unwrap()])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(ServerError::DockerCommandFailed(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+
+        pb.finish_with_message("Backup restored successfully!");
+
+        // Scripts default to not starting, like create
+        if is_interactive()
+            && Confirm::new()
+                .with_prompt("Would you like to start the server now?")
+                .interact()?
+        {
+            start_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
+        }
+    } else {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Decrypts an encrypted backup to a private temporary file and restores from that
+fn restore_encrypted(name: &str, backup_path: &Path, backup: bool) -> Result<()> {
+    let passphrase = backup_passphrase(false)?;
+    let decrypted = std::env::temp_dir().join(format!("blockops-restore-{}-{}.tar.gz", name, std::process::id()));
+
+    let mut open = fs::OpenOptions::new();
+    open.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        open.mode(0o600);
+    }
+    let pb = create_spinner("Decrypting backup");
+    let result = open
+        .open(&decrypted)
+        .and_then(|file| encrypt::decrypt(File::open(backup_path)?, file, &passphrase))
+        .map_err(ServerError::from)
+        .and_then(|_| {
+            pb.finish_and_clear();
+            restore_server(name, &decrypted, backup)
+        });
+    let _ = fs::remove_file(&decrypted);
+    result
+}
+
+/// Mirrors the data directory with `rsync --delete`. A running server keeps running on push, with
+/// saving paused so the copy is consistent; pulling stops it first since its files get replaced.
+/// Turns world saving back on after a `save-off`, best effort
+fn resume_saving(name: &str) {
+    if let Err(e) = run_rcon(name, "save-on") {
+        println!("{} Couldn't turn saving back on, run 'save-on' in the console: {}", "Warning:".yellow(), e);
+    }
+}
+
+fn sync_server(name: &str, target: Option<String>, pull: bool, dry_run: bool) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    let target = target.or_else(|| info.sync_target.clone()).ok_or_else(|| {
+        ServerError::InvalidValue(
+            "target".to_string(),
+            format!("none given and no earlier sync, use e.g. user@host:/srv/minecraft/{}", name),
+        )
+    })?;
+
+    // Trailing slashes make rsync copy the directory contents rather than the directory itself
+    let local = format!("{}/", info.data_path.trim_end_matches('/'));
+    let remote = format!("{}/", target.trim_end_matches('/'));
+    let (source, destination) = if pull { (&remote, &local) } else { (&local, &remote) };
+
+    let running = is_server_running(name)?;
+    let mut saving_paused = false;
+    if running && !dry_run {
+        if pull {
+            stop_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
+        } else {
+            run_rcon(name, "save-off")?;
+            saving_paused = true;
+            if let Err(e) = run_rcon(name, "save-all flush") {
+                resume_saving(name);
+                return Err(e);
+            }
+        }
+    }
+    // A pull overwrites and deletes local files, so keep what was there
+    if pull && !dry_run {
+        backup_before(name, info, "sync")?;
+    }
+
+    let pb = create_spinner(&format!("Syncing {} to {}", source, destination));
+    let mut command = ProcessCommand::new("rsync");
+    // The compose file is generated from this host's config, so it never travels
+    command.args(["-az", "--delete", "--stats", "--exclude=/docker-compose.yml"]);
+    if dry_run {
+        command.arg("--dry-run");
+    }
+    let output = command.arg(source).arg(destination).output();
+
+    // Saving is switched back on whether or not the copy worked
+    if saving_paused {
+        resume_saving(name);
+    }
+
+    let output = output.map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => ServerError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            "rsync isn't installed; it's needed on both hosts",
+        )),
+        _ => ServerError::Io(e),
+    })?;
+    if !output.status.success() {
+        return Err(ServerError::Io(io::Error::other(format!(
+            "rsync failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    pb.finish_and_clear();
+
+    let stats = String::from_utf8_lossy(&output.stdout);
+    let stat = |label: &str| {
+        stats
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(label))
+            .map(|value| value.trim().split(' ').next().unwrap_or_default().replace(',', ""))
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or_default()
+    };
+    let files = stat("Number of regular files transferred:");
+    let bytes = stat("Total transferred file size:");
+    let verb = if dry_run { "Would transfer" } else { "Transferred" };
+    println!("{} {} files ({}) from {} to {}", verb, files, format_size(bytes), source, destination);
+
+    if dry_run {
+        return Ok(());
+    }
+    let mut config = load_server_config()?;
+    if let Some(info) = config.servers.get_mut(name) {
+        info.sync_target = Some(target);
+        info.last_sync = Some(chrono::Utc::now());
+    }
+    save_server_config(&config)?;
+
+    if pull && running {
+        start_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
+    }
+    Ok(())
+}
+
+fn reinstall_server(name: &str, backup: bool) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+
+    if backup {
+        backup_before(name, info, "reinstall")?;
+        record_backups(&[name])?;
+    }
+
+    let pb = create_spinner("Reinstalling server");
+
+    // The container may already be gone, so a failure here is not an error
+    pb.set_message("Removing container...");
+    ProcessCommand::new("docker")
+        .args(["rm", "-f", &format!("mc-{}", name)])
+        .output()?;
+
+    pb.set_message("Regenerating docker-compose.yml...");
+    fs::create_dir_all(&info.data_path)?;
+    write_compose_file(name, info)?;
+
+    pb.set_message("Pulling image...");
+    let output = captured_compose_command(info)?
+        .arg("pull")
+        .args(ComposeVariant::detect().flag(ComposeFlag::QuietPull))
+        .output()?;
+    if !output.status.success() {
+        return Err(docker_error(&output.stderr));
+    }
+
+    start_single_server(name, info, &ComposeArgs::default(), &pb)?;
+    update_last_started(&[name])?;
+    pb.finish_with_message(format!("Server '{}' reinstalled; world data was kept.", name));
+    Ok(())
+}
+
+fn remove_server(name: &str, force: bool, backup: bool, keep_data: bool) -> Result<()> {
+    let mut config = load_server_config()?;
+    if let Some(info) = config.servers.get(name) {
+        let prompt = if keep_data {
+            format!("Are you sure you want to remove server '{}'? Its files are kept.", name)
+        } else {
+            format!("Are you sure you want to remove server '{}'? This will delete all data!", name)
+        };
+        if !force {
+            require_interactive("pass --force to remove without confirming")?;
+            if !Confirm::new().with_prompt(prompt).interact()? {
+                return Ok(());
+            }
+        }
+
+        let kept = keep_data.then(|| if info.archived { archive_path(name) } else { PathBuf::from(&info.data_path) });
+        if keep_data {
+            // Nothing to back up when nothing is deleted; the compose file stays for reconcile or a re-import
+            if !info.archived {
+                stop_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
+            }
+        } else if info.archived {
+            // The archive is a complete backup already, it only changes place
+            let archive = archive_path(name);
+            if backup {
+                let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+                let backup = config_dir()
+                    .join(BACKUP_DIR)
+                    .join(format!("{}_{}_pre-removal.tar.gz", name, timestamp));
+                fs::rename(&archive, &backup)?;
+                println!("{}", format!("Pre-removal backup saved to {}", backup.display()).green());
+            } else {
+                fs::remove_file(&archive)?;
+            }
+        } else {
+            // Stop the server first
+            stop_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
+
+            if backup {
+                backup_before(name, info, "removal")?;
+            }
+
+            // Remove the server directory
+            fs::remove_dir_all(&info.data_path)?;
+        }
+        if info.compose_path.is_some() && !keep_data {
+            let compose_file = info.compose_file();
+            if compose_file.exists() {
+                fs::remove_file(&compose_file)?;
+            }
+            // Only removed when empty, anything else in there isn't ours
+            if let Some(parent) = compose_file.parent() {
+                let _ = fs::remove_dir(parent);
+            }
+        }
+
+        // Remove from config. The kept compose file still refers to the keyring secrets, so they
+        // stay for a re-import.
+        let mut kept_secrets = 0;
+        if let Some(removed) = config.servers.remove(name) {
+            for value in removed.extra_env.values().chain(removed.managed_backups.iter().map(|m| &m.rcon_password)) {
+                if !keep_data {
+                    forget_secret(value);
+                } else if secrets::account(value).is_some() {
+                    kept_secrets += 1;
+                }
+            }
+        }
+        for (other, other_info) in config.servers.iter_mut() {
+            if other_info.depends_on.iter().any(|d| d == name) {
+                other_info.depends_on.retain(|d| d != name);
+                println!("Removed '{}' from the dependencies of '{}'.", name, other);
+            }
+        }
+        save_server_config(&config)?;
+
+        println!("{}", format!("Server '{}' removed successfully!", name).green());
+        if let Some(kept) = kept {
+            println!("Its data is still in {}", std::path::absolute(kept)?.display());
+        }
+        if kept_secrets > 0 {
+            println!("Its {} secret(s) stay in the OS keyring for its compose file.", kept_secrets);
+        }
+    } else {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
+    Ok(())
+}
+
+fn archive_path(name: &str) -> PathBuf {
+    config_dir().join(ARCHIVE_DIR).join(format!("{}.tar.gz", name))
+}
+
+/// Stops the server, removing its container, and replaces the data directory by a tar.gz of it.
+/// The config and compose file stay, so `unarchive` brings it back as it was.
+fn archive_server(name: &str) -> Result<()> {
+    let mut config = load_server_config()?;
+    let mut info = config
+        .servers
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    if info.archived {
+        println!("{}", format!("Server '{}' is already archived.", name).yellow());
+        return Ok(());
+    }
+
+    stop_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
+
+    let archive = archive_path(name);
+    fs::create_dir_all(config_dir().join(ARCHIVE_DIR))?;
+    let data_size = dir_size(Path::new(&info.data_path))?;
+    let options = BackupOptions { out: Some(archive.clone()), ..BackupOptions::default() };
+    create_backup(name, &info, &options, None)?;
+    fs::remove_dir_all(&info.data_path)?;
+
+    info.archived = true;
+    config.servers.insert(name.to_string(), info);
+    save_server_config(&config)?;
+
+    println!(
+        "{}",
+        format!(
+            "Server '{}' archived to {} ({} packed into {}). Use 'unarchive {}' to bring it back.",
+            name,
+            archive.display(),
+            format_size(data_size),
+            format_size(fs::metadata(&archive)?.len()),
+            name
+        )
+        .green()
+    );
+    Ok(())
+}
+
+fn unarchive_server(name: &str) -> Result<()> {
+    let mut config = load_server_config()?;
+    let mut info = config
+        .servers
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    if !info.archived {
+        println!("{}", format!("Server '{}' isn't archived.", name).yellow());
+        return Ok(());
+    }
+    let archive = archive_path(name);
+    if !archive.exists() {
+        return Err(ServerError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("the archive {} is missing", archive.display()),
+        )));
+    }
+    // tar runs inside the data directory, so the archive path must be absolute
+    let archive = archive.canonicalize()?;
+
+    fs::create_dir_all(&info.data_path)?;
+    check_free_space(Path::new(&info.data_path), extracted_size(&archive)?)?;
+    let pb = create_spinner("Unpacking archive");
+    let output = ProcessCommand::new("tar")
+        .current_dir(&info.data_path)
+        .args(["-xzf", archive.to_str().unwrap()])
+        .output()?;
+    if !output.status.success() {
+        return Err(ServerError::DockerCommandFailed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    info.archived = false;
+    write_compose_file(name, &info)?;
+    config.servers.insert(name.to_string(), info);
+    save_server_config(&config)?;
+    fs::remove_file(&archive)?;
+
+    pb.finish_with_message(format!("Server '{}' unarchived, 'start {}' runs it again.", name, name));
+    Ok(())
+}
+
+fn get_setting(name: &str, key: &str) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+
+    let value = if let Some(env_key) = key.strip_prefix("env.") {
+        info.extra_env
+            .get(env_key)
+            .cloned()
+            .ok_or_else(|| ServerError::UnknownKey(key.to_string()))?
+    } else {
+        match key {
+            "version" => info.version.clone(),
+            "port" => info.port.clone(),
+            "memory" => info.memory.clone(),
+            "memory_percent" => info.memory_percent.map_or(String::new(), |p| format!("{}%", p)),
+            "server_type" => info.server_type.clone(),
+            "mod_loader_version" => info.mod_loader_version.clone().unwrap_or_default(),
+            "java_args" => info.java_args.clone().unwrap_or_default(),
+            "java_version" => info.java_version.map_or(String::new(), |v| v.to_string()),
+            "uid" => info.uid.map_or(String::new(), |v| v.to_string()),
+            "gid" => info.gid.map_or(String::new(), |v| v.to_string()),
+            "read_only" => info.read_only.to_string(),
+            "auto_backup" => info.auto_backup.to_string(),
+            "restart_policy" => info.restart_policy.clone().unwrap_or_else(|| DEFAULT_RESTART_POLICY.to_string()),
+            "log_max_size" => info.log_max_size.clone().unwrap_or_else(|| DEFAULT_LOG_MAX_SIZE.to_string()),
+            "healthcheck_cmd" => info.healthcheck_cmd.clone().unwrap_or_default(),
+            "resource_pack" => info.resource_pack.clone().unwrap_or_default(),
+            "resource_pack_sha1" => info.resource_pack_sha1.clone().unwrap_or_default(),
+            "resource_pack_enforce" => info.resource_pack_enforce.to_string(),
+            "log_max_files" => info.log_max_files.unwrap_or(DEFAULT_LOG_MAX_FILES).to_string(),
+            "max_tick_time" => info.max_tick_time.map_or(String::new(), |ms| ms.to_string()),
+            "description" => info.description.clone().unwrap_or_default(),
+            "companions" => info.companions.iter().map(|c| c.profile()).collect::<Vec<_>>().join(","),
+            "tuning" => info.tuning.map_or(String::new(), |t| t.name().to_string()),
+            "level_type" => info.level_type.map_or(String::new(), |l| l.name().to_string()),
+            "generator_settings" => info.generator_settings.clone().unwrap_or_default(),
+            "image_digest" => info.image_digest.clone().unwrap_or_default(),
+            "bind_address" => info.bind_address.map_or(String::new(), |a| a.to_string()),
+            "tags" => info.tags.join(","),
+            "depends_on" => info.depends_on.join(","),
+            "worlds" => server_worlds(info).join(","),
+            "data_path" => info.data_path.clone(),
+            "compose_path" => info.compose_file().to_string_lossy().to_string(),
+            "created_at" => info.created_at.to_rfc3339(),
+            "last_started" => info.last_started.map_or(String::new(), |d| d.to_rfc3339()),
+            "last_backup" => info.last_backup.map_or(String::new(), |d| d.to_rfc3339()),
+            _ => return Err(ServerError::UnknownKey(key.to_string())),
+        }
+    };
+
+    println!("{}", value);
+    Ok(())
+}
+
+fn set_setting(name: &str, key: &str, value: &str, force: bool, dry_run: bool) -> Result<()> {
+    let mut config = load_server_config()?;
+    let mut info = config
+        .servers
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    let original = info.clone();
+
+    let optional = |value: &str| (!value.is_empty()).then(|| value.to_string());
+    // Stored in the keyring only once the change is confirmed
+    let affects_compose = if let Some(env_key) = key.strip_prefix("env.") {
+        validate_env_key(env_key)?;
+        if value.is_empty() {
+            info.extra_env.remove(env_key);
+        } else {
+            info.extra_env.insert(env_key.to_string(), value.to_string());
+        }
+        true
+    } else {
+        match key {
+            "version" => {
+                if value.is_empty() {
+                    return Err(ServerError::InvalidValue(key.to_string(), "version can't be empty".to_string()));
+                }
+                ensure_loader_compatible(info.mod_loader.as_deref(), info.mod_loader_version.as_deref(), value, force)?;
+                if dry_run {
+                    for warning in version_jump_warnings(&info, value) {
+                        println!("{}", format!("Warning: {}", warning).yellow());
+                    }
+                } else if confirm_version_change(name, &info, value, force)? {
+                    info.last_backup = Some(chrono::Utc::now());
+                }
+                info.version = value.to_string();
+                validate_level_type(&info)?;
+                true
+            }
+            "port" => {
+                if value != info.port {
+                    check_port_available(value, &config)?;
+                }
+                info.port = value.to_string();
+                true
+            }
+            "memory" => {
+                let (memory, memory_percent) = resolve_memory(value)?;
+                info.memory = memory;
+                info.memory_percent = memory_percent;
+                true
+            }
+            "server_type" => {
+                let server_type = value.to_uppercase();
+                if !SERVER_TYPES.contains(&server_type.as_str()) {
+                    return Err(ServerError::InvalidServerType(value.to_string()));
+                }
+                info.mod_loader = match server_type.as_str() {
+                    "FORGE" | "FABRIC" => Some(server_type.clone()),
+                    _ => None,
+                };
+                if info.mod_loader.is_none() {
+                    info.mod_loader_version = None;
+                }
+                let loader_version = info.mod_loader_version.as_deref();
+                ensure_loader_compatible(info.mod_loader.as_deref(), loader_version, &info.version, force)?;
+                info.server_type = server_type;
+                true
+            }
+            "mod_loader_version" => {
+                if info.mod_loader.is_none() {
+                    return Err(ServerError::InvalidValue(
+                        key.to_string(),
+                        format!("{} servers don't use a mod loader", info.server_type),
+                    ));
+                }
+                info.mod_loader_version = optional(value);
+                let loader_version = info.mod_loader_version.as_deref();
+                ensure_loader_compatible(info.mod_loader.as_deref(), loader_version, &info.version, force)?;
+                true
+            }
+            "java_args" => {
+                info.java_args = optional(value);
+                true
+            }
+            "java_version" => {
+                info.java_version = match value {
+                    "" => None,
+                    _ => {
+                        let java = value.parse().map_err(|_| {
+                            ServerError::InvalidValue(key.to_string(), format!("'{}' is not a number", value))
+                        })?;
+                        validate_java_version(java)?;
+                        Some(java)
+                    }
+                };
+                true
+            }
+            "uid" | "gid" => {
+                let id = match value {
+                    "" => None,
+                    _ => Some(value.parse().map_err(|_| {
+                        ServerError::InvalidValue(key.to_string(), format!("'{}' is not a numeric id", value))
+                    })?),
+                };
+                if key == "uid" {
+                    info.uid = id;
+                } else {
+                    info.gid = id;
+                }
+                true
+            }
+            "tuning" => {
+                info.tuning = match value {
+                    "" => None,
+                    _ => Some(Tuning::from_str(value, true).map_err(|e| ServerError::InvalidValue(key.to_string(), e))?),
+                };
+                true
+            }
+            "level_type" => {
+                info.level_type = match value {
+                    "" => None,
+                    _ => {
+                        let level_type = LevelType::from_str(value, true)
+                            .map_err(|e| ServerError::InvalidValue(key.to_string(), e))?;
+                        (level_type != LevelType::Normal).then_some(level_type)
+                    }
+                };
+                validate_level_type(&info)?;
+                println!("{}", "Only worlds generated from now on use it, e.g. after reset-dimension.".yellow());
+                true
+            }
+            "generator_settings" => {
+                if !value.is_empty() {
+                    validate_generator_settings(value)?;
+                }
+                info.generator_settings = optional(value);
+                println!("{}", "Only worlds generated from now on use it, e.g. after reset-dimension.".yellow());
+                true
+            }
+            "image_digest" => {
+                info.image_digest = match value {
+                    "" => None,
+                    _ => Some(parse_image_digest(value)?),
+                };
+                true
+            }
+            "bind_address" => {
+                info.bind_address = match value {
+                    "" => None,
+                    _ => {
+                        let address =
+                            parse_bind_address(value).map_err(|e| ServerError::InvalidValue(key.to_string(), e))?;
+                        validate_bind_address(address)?;
+                        Some(address)
+                    }
+                };
+                true
+            }
+            "read_only" => {
+                info.read_only = value.parse().map_err(|_| {
+                    ServerError::InvalidValue(key.to_string(), format!("'{}' is not true or false", value))
+                })?;
+                true
+            }
+            "auto_backup" => {
+                info.auto_backup = value.parse().map_err(|_| {
+                    ServerError::InvalidValue(key.to_string(), format!("'{}' is not true or false", value))
+                })?;
+                false
+            }
+            "restart_policy" => {
+                if !value.is_empty() {
+                    validate_restart_policy(value)?;
+                }
+                info.restart_policy = optional(value);
+                true
+            }
+            "healthcheck_cmd" => {
+                info.healthcheck_cmd = optional(value);
+                true
+            }
+            "resource_pack" => {
+                if !value.is_empty() && !is_url(value) {
+                    return Err(ServerError::InvalidValue(
+                        key.to_string(),
+                        "use an http(s) URL; local files can be set with 'create --resource-pack'".to_string(),
+                    ));
+                }
+                info.resource_pack = optional(value);
+                info.resource_pack_sha1 = None;
+                info.resource_pack_port = None;
+                true
+            }
+            "resource_pack_sha1" => {
+                if !value.is_empty() {
+                    validate_sha1(value)?;
+                }
+                info.resource_pack_sha1 = optional(&value.to_lowercase());
+                true
+            }
+            "resource_pack_enforce" => {
+                info.resource_pack_enforce = value.parse().map_err(|_| {
+                    ServerError::InvalidValue(key.to_string(), format!("'{}' is not true or false", value))
+                })?;
+                true
+            }
+            "log_max_size" => {
+                if !value.is_empty() {
+                    validate_log_size(value)?;
+                }
+                info.log_max_size = optional(value);
+                true
+            }
+            "log_max_files" => {
+                info.log_max_files = match value {
+                    "" => None,
+                    _ => {
+                        let files = value.parse().map_err(|_| {
+                            ServerError::InvalidValue(key.to_string(), format!("'{}' is not a number", value))
+                        })?;
+                        validate_log_files(files)?;
+                        Some(files)
+                    }
+                };
+                true
+            }
+            "max_tick_time" => {
+                info.max_tick_time = match value {
+                    "" => None,
+                    _ => {
+                        let ms = value.parse().map_err(|_| {
+                            ServerError::InvalidValue(key.to_string(), format!("'{}' is not a number", value))
+                        })?;
+                        validate_max_tick_time(ms)?;
+                        Some(ms)
+                    }
+                };
+                true
+            }
+            "description" => {
+                info.description = optional(value);
+                false
+            }
+            "companions" => {
+                let mut companions = Vec::new();
+                for item in value.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+                    let companion = Companion::from_str(item, true)
+                        .map_err(|e| ServerError::InvalidValue(key.to_string(), e))?;
+                    if !companions.contains(&companion) {
+                        companions.push(companion);
+                    }
+                }
+                info.companions = companions;
+                true
+            }
+            "tags" => {
+                let tags: Vec<String> = value
+                    .split(',')
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                if let Some(bad) = tags.iter().find(|t| validate_server_name(t).is_err()) {
+                    return Err(ServerError::InvalidValue(
+                        key.to_string(),
+                        format!("tag '{}' must be alphanumeric", bad),
+                    ));
+                }
+                info.tags = tags;
+                false
+            }
+            "depends_on" => {
+                let depends_on: Vec<String> = value
+                    .split(',')
+                    .map(|d| d.trim().to_string())
+                    .filter(|d| !d.is_empty())
+                    .collect();
+                validate_dependencies(name, &depends_on, &config)?;
+                info.depends_on = depends_on;
+                false
+            }
+            "worlds" => {
+                let worlds: Vec<String> = value
+                    .split(',')
+                    .map(|w| w.trim().to_string())
+                    .filter(|w| !w.is_empty())
+                    .collect();
+                if let Some(bad) = worlds.iter().find(|w| validate_server_name(w).is_err()) {
+                    return Err(ServerError::InvalidValue(
+                        key.to_string(),
+                        format!("world '{}' must be a plain folder name", bad),
+                    ));
+                }
+                info.worlds = worlds;
+                false
+            }
+            "data_path" | "compose_path" | "created_at" | "last_started" | "last_backup" | "memory_percent" => {
+                return Err(ServerError::InvalidValue(key.to_string(), "this setting is read-only".to_string()));
+            }
+            _ => return Err(ServerError::UnknownKey(format!("{} (valid keys: {}, env.<KEY>)", key, SETTING_KEYS.join(", ")))),
+        }
+    };
+
+    if dry_run || !force {
+        println!("{}", format!("\n{} for '{}':", CONFIG_FILE, name).bright_cyan());
+        print_secret_diff(&serde_json::to_string_pretty(&original)?, &serde_json::to_string_pretty(&info)?);
+        if affects_compose {
+            let old = fs::read_to_string(info.compose_file())
+                .or_else(|_| serde_yaml::to_string(&build_compose_config(name, &original)))?;
+            println!("{}", format!("\n{}:", info.compose_file().display()).bright_cyan());
+            print_secret_diff(&old, &serde_yaml::to_string(&build_compose_config(name, &info))?);
+        }
+        if dry_run {
+            println!("{}", "\nDry run, nothing was written.".yellow());
+            return Ok(());
+        }
+        require_interactive("pass --force to apply the change without confirming")?;
+        if !Confirm::new().with_prompt("Apply this change?").default(true).interact()? {
+            return Ok(());
+        }
+    }
+
+    if let Some(env_key) = key.strip_prefix("env.").filter(|_| !value.is_empty()) {
+        info.extra_env.insert(env_key.to_string(), store_secret(&format!("{}.{}", name, key), value));
+    }
+    if let Some(env_key) = key.strip_prefix("env.") {
+        if let Some(old) = original.extra_env.get(env_key).filter(|old| info.extra_env.get(env_key) != Some(*old)) {
+            forget_secret(old);
+        }
+    }
+
+    if affects_compose {
+        write_compose_file(name, &info)?;
+    }
+    config.servers.insert(name.to_string(), info);
+    save_server_config(&config)?;
+
+    println!("{}", format!("Set '{}' for server '{}'.", key, name).green());
+    if affects_compose && is_server_running(name)? {
+        println!("{}", "Restart the server for the change to take effect.".yellow());
+    }
+    Ok(())
+}
+
+fn pregen_world(name: &str, radius: u32, timeout: Option<u64>) -> Result<()> {
+    let mut config = load_server_config()?;
+    let info = config
+        .servers
+        .get_mut(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+
+    if !matches!(info.server_type.as_str(), "PAPER" | "PURPUR") {
+        println!(
+            "{}",
+            format!(
+                "Pregeneration uses the Chunky plugin, which needs a PAPER or PURPUR server ('{}' is {}).",
+                name, info.server_type
+            )
+            .yellow()
+        );
+        println!("For modded servers, install the Chunky mod manually and run 'chunky start' from the console.");
+        return Ok(());
+    }
+
+    // Chunky is installed by the itzg image from Modrinth on the next container start
+    let projects = info.extra_env.entry("MODRINTH_PROJECTS".to_string()).or_default();
+    if !projects.split(',').any(|p| p.trim() == "chunky") {
+        if !projects.is_empty() {
+            projects.push(',');
+        }
+        projects.push_str("chunky");
+        let info = info.clone();
+        write_compose_file(name, &info)?;
+        save_server_config(&config)?;
+        println!("{}", "Added the Chunky plugin to the server configuration.".green());
+        println!("Restart the server so it gets installed, then run pregen again.");
+        return Ok(());
+    }
+
+    if !is_server_running(name)? {
+        return Err(ServerError::ServerNotRunning(name.to_string()));
+    }
+
+    if !has_jar(info, "chunky") {
+        return Err(ServerError::RconFailed(format!(
+            "Chunky isn't installed on '{}' yet, restart the server so it gets installed",
+            name
+        )));
+    }
+    run_rcon(name, &format!("chunky radius {}", radius))?;
+    run_rcon(name, "chunky start")?;
+
+    let pb = ProgressBar::new(100);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}% {msg}")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+    pb.set_message(format!("Pregenerating radius {}", radius));
+
+    let deadline = timeout.map(|secs| Instant::now() + Duration::from_secs(secs));
+    loop {
+        std::thread::sleep(Duration::from_secs(5));
+        let output = run_rcon(name, "chunky progress")?;
+        match parse_chunky_progress(&output) {
+            Some(ChunkyProgress::Running(percent)) => pb.set_position(percent as u64),
+            Some(ChunkyProgress::Done) => break,
+            None => {
+                pb.abandon();
+                return Err(ServerError::RconFailed(format!(
+                    "unexpected 'chunky progress' output: {}",
+                    output.trim()
+                )));
+            }
+        }
+        // Chunky keeps generating on the server, only the wait for it stops
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            pb.abandon();
+            return Err(ServerError::Timeout(
+                timeout.unwrap_or_default(),
+                format!("pregeneration of '{}' to finish, it continues in the background", name),
+                format!("{}% done", pb.position()),
+            ));
+        }
+    }
+
+    pb.set_position(100);
+    pb.finish_with_message("Pregeneration completed!");
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum ChunkyProgress {
+    /// Completion percentage of the running task
+    Running(f64),
+    /// No task is running any more
+    Done,
+}
+
+/// Reads Chunky's progress output, e.g. "Task running for world. Processed: 1200 chunks
+/// (12.34%), ETA: 0:10:00" or "No tasks running.". Returns `None` for anything else.
+fn parse_chunky_progress(output: &str) -> Option<ChunkyProgress> {
+    if output.to_lowercase().contains("no tasks running") {
+        return Some(ChunkyProgress::Done);
+    }
+    let end = output.find("%)")?;
+    let start = output[..end].rfind('(')? + 1;
+    output[start..end].trim().parse().ok().map(ChunkyProgress::Running)
+}
+
+fn rcon_history_path(name: &str) -> PathBuf {
+    config_dir().join(HISTORY_DIR).join(format!("{}.txt", name))
+}
+
+/// Returns the server's RCON history, most recent command first
+fn load_rcon_history(name: &str) -> Result<Vec<String>> {
+    let path = rcon_history_path(name);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .rev()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn append_rcon_history(name: &str, command: &str) -> Result<()> {
+    let mut history = load_rcon_history(name)?;
+    history.retain(|c| c != command);
+    history.insert(0, command.to_string());
+    history.truncate(RCON_HISTORY_LIMIT);
+    history.reverse();
+
+    let path = rcon_history_path(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, history.join("\n") + "\n")?;
+    Ok(())
+}
+
+fn rcon_command(name: &str, command: &[String], history: bool, repeat: Option<usize>) -> Result<()> {
+    let config = load_server_config()?;
+    if !config.servers.contains_key(name) {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
+
+    if history {
+        let history = load_rcon_history(name)?;
+        if history.is_empty() {
+            println!("{}", format!("No RCON history for server '{}' yet.", name).yellow());
+        }
+        for (i, command) in history.iter().enumerate() {
+            println!("{:>3}  {}", (i + 1).to_string().bright_blue(), command);
+        }
+        return Ok(());
+    }
+
+    let command = match repeat {
+        Some(n) => {
+            let history = load_rcon_history(name)?;
+            n.checked_sub(1)
+                .and_then(|i| history.get(i))
+                .cloned()
+                .ok_or_else(|| ServerError::InvalidValue("repeat".to_string(), format!("no history entry {}", n)))?
+        }
+        None => command.join(" "),
+    };
+
+    if !is_server_running(name)? {
+        return Err(ServerError::ServerNotRunning(name.to_string()));
+    }
+
+    println!("{} {}", ">".bright_blue(), command);
+    let output = run_rcon(name, &command)?;
+    append_rcon_history(name, &command)?;
+    if !output.trim().is_empty() {
+        println!("{}", output.trim_end());
+    }
+    Ok(())
+}
+
+fn collect_files_with_extension(dir: &Path, extension: &str, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files_with_extension(&path, extension, files)?;
+        } else if path.extension().is_some_and(|e| e == extension) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn verify_world(name: &str) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+
+    // Region files are rewritten constantly while the server runs
+    if is_server_running(name)? {
+        return Err(ServerError::ServerRunning(name.to_string()));
+    }
+
+    let pb = create_spinner("Scanning region files");
+    let mut region_files = Vec::new();
+    collect_files_with_extension(Path::new(&info.data_path), "mca", &mut region_files)?;
+    region_files.sort();
+
+    let mut chunks = 0;
+    let mut suspect = Vec::new();
+    for path in &region_files {
+        pb.set_message(format!("Checking {}", path.display()));
+        let report = region::check_region_file(path)?;
+        chunks += report.chunks;
+        if !report.issues.is_empty() {
+            suspect.push((path, report.issues));
+        }
+    }
+    pb.finish_and_clear();
+
+    for (path, issues) in &suspect {
+        println!("{}", path.display().to_string().red());
+        for issue in issues {
+            println!("  - {}", issue);
+        }
+    }
+
+    println!(
+        "\nChecked {} region files ({} chunks).",
+        region_files.len(),
+        chunks
+    );
+    if suspect.is_empty() {
+        println!("{}", "No corruption detected.".green());
+    } else {
+        println!(
+            "{}",
+            format!("{} region files look corrupted, consider restoring a backup.", suspect.len()).red()
+        );
+    }
+    Ok(())
+}
+
+/// Splits `host`, `host:port`, `[v6]` or `[v6]:port`, falling back to `default_port`
+fn parse_address(address: &str, default_port: u16) -> Result<(String, u16)> {
+    let invalid = || ServerError::InvalidValue("address".to_string(), address.to_string());
+    if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']').ok_or_else(invalid)?;
+        let port = match rest.strip_prefix(':') {
+            Some(port) => port.parse().map_err(|_| invalid())?,
+            None if rest.is_empty() => default_port,
+            None => return Err(invalid()),
+        };
+        return Ok((host.to_string(), port));
+    }
+    match address.split_once(':') {
+        // More than one colon is a bare IPv6 address
+        Some((host, port)) if !port.contains(':') => Ok((host.to_string(), port.parse().map_err(|_| invalid())?)),
+        _ => Ok((address.to_string(), default_port)),
+    }
+}
+
+fn ping_server(name: &str, address: Option<&str>) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+
+    let port: u16 = info
+        .port
+        .parse()
+        .map_err(|_| ServerError::InvalidPort(info.port.clone()))?;
+    // A server published on one address only answers there
+    let bound = info.bind_address.filter(|a| !a.is_unspecified()).map(|a| a.to_string());
+    let (host, port) = parse_address(address.or(bound.as_deref()).unwrap_or("127.0.0.1"), port)?;
+
+    let pb = create_spinner(&format!("Pinging {}:{}", host, port));
+    let status = ping::ping(&host, port);
+    pb.finish_and_clear();
+    let status = status?;
+
+    println!("\n{}", format!("{} is reachable at {}:{}", name, host, port).bright_green());
+    println!("  MOTD: {}", strip_color_codes(&status.motd).trim());
+    println!("  Version: {} (protocol {})", status.version.bright_blue(), status.protocol);
+    println!("  Players: {}/{}", status.players_online, status.players_max);
+    println!("  Latency: {} ms", status.latency.as_millis());
+    Ok(())
+}
+
+/// Tick rate of a running server as reported by its own command
+struct TickStats {
+    /// The command the figures came from
+    source: &'static str,
+    /// TPS per time window, e.g. ("1m", 19.8)
+    tps: Vec<(String, f64)>,
+    /// Milliseconds per tick over the last minute, when the command reports it
+    mspt: Option<f64>,
+}
+
+/// Whether a plugin or mod jar whose name starts with `prefix` is in plugins/ or mods/
+fn has_jar(info: &ServerInfo, prefix: &str) -> bool {
+    ["plugins", "mods"].iter().any(|dir| {
+        fs::read_dir(Path::new(&info.data_path).join(dir)).is_ok_and(|entries| {
+            entries.filter_map(|e| e.ok()).any(|e| {
+                let file = e.file_name().to_string_lossy().to_lowercase();
+                file.starts_with(prefix) && file.ends_with(".jar")
+            })
+        })
+    })
+}
+
+/// Windows and values of a "TPS from last 1m, 5m, 15m: 20.0, 20.0, 20.0" report, where Spark
+/// puts the values on the next line. Values above 20 are starred.
+fn parse_tps_report(output: &str) -> Option<Vec<(String, f64)>> {
+    let lines: Vec<&str> = output.lines().collect();
+    let index = lines.iter().position(|line| line.contains("TPS from last"))?;
+    let (header, rest) = lines[index].split_once("TPS from last")?.1.split_once(':')?;
+    let values = if rest.trim().is_empty() { lines.get(index + 1)? } else { rest };
+    let number = Regex::new(r"\*?(\d+(?:\.\d+)?)").ok()?;
+    let values: Vec<f64> = number.captures_iter(values).filter_map(|c| c[1].parse().ok()).collect();
+    let windows: Vec<String> = header.split(',').map(|w| w.trim().to_string()).collect();
+    (windows.len() == values.len()).then(|| windows.into_iter().zip(values).collect())
+}
+
+/// Median tick time over the last minute from `spark tps`: "Tick durations (min/med/95%ile/max
+/// ms) from last 10s, 1m:" followed by "a/b/c/d; a/b/c/d"
+fn parse_spark_mspt(output: &str) -> Option<f64> {
+    output
+        .lines()
+        .skip_while(|line| !line.contains("Tick durations"))
+        .nth(1)?
+        .split(';')
+        .next_back()?
+        .trim()
+        .split('/')
+        .nth(1)?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Average tick time over the last minute from Paper's `mspt`, the last of its "avg/min/max"
+/// triples for 5s, 10s and 1m
+fn parse_paper_mspt(output: &str) -> Option<f64> {
+    let triple = Regex::new(r"(\d+(?:\.\d+)?)/\d+(?:\.\d+)?/\d+(?:\.\d+)?").ok()?;
+    triple.captures_iter(output).last()?[1].parse().ok()
 }
 
-fn list_versions() {
-    println!("\n{}", "Available Minecraft Server Types:".bright_cyan());
-    println!("{}", "============================".bright_cyan());
-    println!("- {}: Vanilla Minecraft server", "VANILLA".bright_green());
-    println!("- {}: High performance fork of Spigot", "PAPER".bright_green());
-    println!("- {}: Modded Minecraft server", "FORGE".bright_green());
-    println!("- {}: Lightweight mod loader", "FABRIC".bright_green());
-    println!("- {}: Fork of CraftBukkit", "SPIGOT".bright_green());
-    println!("- {}: Performance-focused server", "PURPUR".bright_green());
+/// Mean TPS and tick time of the "Overall" line of `forge tps`: "Mean tick time: 1.2 ms. Mean
+/// TPS: 20.0" in older Forge, "20.0 TPS (1.2 ms/tick)" in newer
+fn parse_forge_tps(output: &str) -> Option<(f64, f64)> {
+    let overall = output.lines().find(|line| line.contains("Overall"))?;
+    let (tps, mspt) = Regex::new(r"Mean tick time: ([\d.]+) ms\. Mean TPS: ([\d.]+)")
+        .ok()?
+        .captures(overall)
+        .map(|c| (c[2].to_string(), c[1].to_string()))
+        .or_else(|| {
+            Regex::new(r"([\d.]+) TPS \(([\d.]+) ms/tick\)")
+                .ok()?
+                .captures(overall)
+                .map(|c| (c[1].to_string(), c[2].to_string()))
+        })?;
+    Some((tps.parse().ok()?, mspt.parse().ok()?))
+}
 
-    println!("\n{}", "Version Format Examples:".bright_yellow());
-    println!("- LATEST (always uses the latest release)");
-    println!("- 1.20.2 (specific version)");
-    println!("- SNAPSHOT (latest snapshot version)");
-    
-    println!("\n{}", "Mod Loader Examples:".bright_yellow());
-    println!("- Forge: RECOMMENDED or specific version (e.g., 47.1.0)");
-    println!("- Fabric: LATEST or specific version (e.g., 0.14.21)");
+fn tick_stats(name: &str, info: &ServerInfo) -> Result<TickStats> {
+    // Spark works on every server type, so it's preferred when it's installed
+    if has_jar(info, "spark") {
+        let output = run_rcon(name, "spark tps")?;
+        let tps = parse_tps_report(&output)
+            .ok_or_else(|| ServerError::RconFailed(format!("unexpected spark tps output: {}", output.trim())))?;
+        let mspt = parse_spark_mspt(&output);
+        return Ok(TickStats { source: "spark tps", tps, mspt });
+    }
+    match info.server_type.as_str() {
+        "PAPER" | "PURPUR" | "SPIGOT" => {
+            let output = run_rcon(name, "tps")?;
+            let tps = parse_tps_report(&output)
+                .ok_or_else(|| ServerError::RconFailed(format!("unexpected tps output: {}", output.trim())))?;
+            // Paper only
+            let mspt = match info.server_type.as_str() {
+                "SPIGOT" => None,
+                _ => run_rcon(name, "mspt").ok().and_then(|output| parse_paper_mspt(&output)),
+            };
+            Ok(TickStats { source: "tps", tps, mspt })
+        }
+        "FORGE" => {
+            let output = run_rcon(name, "forge tps")?;
+            let (tps, mspt) = parse_forge_tps(&output)
+                .ok_or_else(|| ServerError::RconFailed(format!("unexpected forge tps output: {}", output.trim())))?;
+            Ok(TickStats { source: "forge tps", tps: vec![("mean".to_string(), tps)], mspt: Some(mspt) })
+        }
+        server_type => Err(ServerError::InvalidValue(
+            "server_type".to_string(),
+            format!("{} servers have no tps command, install Spark (spark.lucko.me) for tick statistics", server_type),
+        )),
+    }
 }
 
-fn create_server() -> Result<()> {
-    println!("\n{}", "Let's configure a new Minecraft server!".bright_cyan());
+fn show_tps(name: &str, json: bool) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    if !is_server_running(name)? {
+        return Err(ServerError::ServerNotRunning(name.to_string()));
+    }
+    let stats = tick_stats(name, info)?;
 
-    // Server Name
-    let server_name: String = Input::new()
-        .with_prompt("Enter server name (alphanumeric only)")
-        .validate_with(|input: &String| {
-            if input.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
-                Ok(())
-            } else {
-                Err("Server name must be alphanumeric")
-            }
+    if json {
+        let tps: Vec<_> = stats
+            .tps
+            .iter()
+            .map(|(window, tps)| serde_json::json!({ "window": window, "tps": tps }))
+            .collect();
+        let report = serde_json::json!({
+            "name": name,
+            "source": stats.source,
+            "tps": tps,
+            "mspt": stats.mspt,
+        });
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+        return Ok(());
+    }
+
+    // 20 TPS is full speed, which leaves each tick 50 ms
+    let tps_colored = |tps: f64| match tps {
+        t if t >= 19.0 => format!("{:.1}", t).green(),
+        t if t >= 15.0 => format!("{:.1}", t).yellow(),
+        t => format!("{:.1}", t).red(),
+    };
+    println!("\n{}", format!("Tick rate of '{}' ({}):", name, stats.source).bright_cyan());
+    let windows: Vec<String> = stats
+        .tps
+        .iter()
+        .map(|(window, tps)| format!("{}: {}", window, tps_colored(*tps)))
+        .collect();
+    println!("  TPS {}", windows.join(", "));
+    if let Some(mspt) = stats.mspt {
+        let value = format!("{:.1} ms", mspt);
+        let value = match mspt {
+            m if m <= 40.0 => value.green(),
+            m if m <= 50.0 => value.yellow(),
+            _ => value.red(),
+        };
+        println!("  MSPT {}", value);
+    }
+    Ok(())
+}
+
+fn list_reserved_ports(json: bool) -> Result<()> {
+    let reserved = load_settings()?.ports.reserved;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reserved)?);
+    } else if reserved.is_empty() {
+        println!("{}", "No ports are reserved.".yellow());
+    } else {
+        for port in reserved {
+            println!("{}", port);
+        }
+    }
+    Ok(())
+}
+
+fn reserve_port(port: u16) -> Result<()> {
+    if port == 0 {
+        return Err(ServerError::InvalidPort(port.to_string()));
+    }
+    let config = load_server_config()?;
+    if let Some(owner) = port_owner(port, &config) {
+        return Err(ServerError::PortConflict(port.to_string(), owner.clone()));
+    }
+    let mut settings = load_settings()?;
+    if !settings.ports.reserved.insert(port) {
+        println!("{}", format!("Port {} is already reserved.", port).yellow());
+        return Ok(());
+    }
+    save_settings(&settings)?;
+    println!("{}", format!("Reserved port {}.", port).green());
+    Ok(())
+}
+
+fn release_port(port: u16) -> Result<()> {
+    let mut settings = load_settings()?;
+    if !settings.ports.reserved.remove(&port) {
+        println!("{}", format!("Port {} isn't reserved.", port).yellow());
+        return Ok(());
+    }
+    save_settings(&settings)?;
+    println!("{}", format!("Released port {}.", port).green());
+    Ok(())
+}
+
+fn get_global_setting(key: Option<&str>) -> Result<()> {
+    let settings = load_settings()?;
+    let defaults = &settings.defaults;
+    let value_of = |key: &str| -> Result<String> {
+        Ok(match key {
+            "defaults.server_type" => defaults.server_type.clone(),
+            "defaults.version" => defaults.version.clone(),
+            "defaults.memory" => defaults.memory.clone(),
+            "defaults.java" => defaults.java.map_or(String::new(), |v| v.to_string()),
+            "defaults.restart_policy" => defaults.restart_policy.clone(),
+            "defaults.backup_retention" => defaults.backup_retention.map_or(String::new(), |v| v.to_string()),
+            // The URL is the credential, so only its host is shown
+            "notifications.webhook_url" => match settings.notifications.webhook_url.as_deref() {
+                None => String::new(),
+                Some(url) if secrets::account(url).is_some() => "(set, in the OS keyring)".to_string(),
+                Some(url) => format!("{} (set)", webhook_host(url)),
+            },
+            _ => return Err(ServerError::UnknownKey(key.to_string())),
         })
-        .interact_text()?;
+    };
 
-    let mut config = load_server_config()?;
-    if config.servers.contains_key(&server_name) {
-        return Err(ServerError::ServerExists(server_name));
+    match key {
+        Some(key) => println!("{}", value_of(key)?),
+        None => {
+            for key in SETTINGS_KEYS {
+                println!("{} = {}", key.bright_blue(), value_of(key)?);
+            }
+        }
     }
+    Ok(())
+}
 
-    // Server Type Selection
-    let server_types = vec!["VANILLA", "PAPER", "FORGE", "FABRIC", "SPIGOT", "PURPUR"];
-    let server_type_idx = Select::new()
-        .with_prompt("Select server type")
-        .items(&server_types)
-        .default(0)
-        .interact()?;
-    
-    let server_type = server_types[server_type_idx];
+fn set_global_setting(key: &str, value: &str) -> Result<()> {
+    let mut settings = load_settings()?;
+    let fallback = ServerDefaults::default();
+    let defaults = &mut settings.defaults;
+    let not_a_number = || ServerError::InvalidValue(key.to_string(), format!("'{}' is not a number", value));
 
-    // Version Input
-    let version: String = Input::new()
-        .with_prompt("Enter Minecraft version (e.g., LATEST, 1.20.2, SNAPSHOT)")
-        .default("LATEST".into())
-        .interact_text()?;
+    match key {
+        "defaults.server_type" => {
+            let server_type = value.to_uppercase();
+            if !server_type.is_empty() && !SERVER_TYPES.contains(&server_type.as_str()) {
+                return Err(ServerError::InvalidServerType(value.to_string()));
+            }
+            defaults.server_type = if server_type.is_empty() { fallback.server_type } else { server_type };
+        }
+        "defaults.version" => {
+            defaults.version = if value.is_empty() { fallback.version } else { value.to_string() };
+        }
+        "defaults.memory" => {
+            if !value.is_empty() {
+                resolve_memory(value)?;
+            }
+            defaults.memory = if value.is_empty() { fallback.memory } else { value.to_string() };
+        }
+        "defaults.java" => {
+            defaults.java = match value {
+                "" => None,
+                _ => {
+                    let java = value.parse().map_err(|_| not_a_number())?;
+                    validate_java_version(java)?;
+                    Some(java)
+                }
+            };
+        }
+        "defaults.restart_policy" => {
+            if !value.is_empty() {
+                validate_restart_policy(value)?;
+            }
+            defaults.restart_policy = if value.is_empty() { fallback.restart_policy } else { value.to_string() };
+        }
+        "defaults.backup_retention" => {
+            defaults.backup_retention = match value {
+                "" => None,
+                _ => match value.parse().map_err(|_| not_a_number())? {
+                    0 => {
+                        return Err(ServerError::InvalidValue(
+                            key.to_string(),
+                            "use an empty value to keep every backup".to_string(),
+                        ))
+                    }
+                    n => Some(n),
+                },
+            };
+        }
+        "notifications.webhook_url" => {
+            if !value.is_empty() && !value.starts_with("https://") {
+                return Err(ServerError::InvalidValue(key.to_string(), "webhook URLs start with https://".to_string()));
+            }
+            let stored = (!value.is_empty()).then(|| store_secret(key, value));
+            if let Some(old) = settings.notifications.webhook_url.take().filter(|old| Some(old) != stored.as_ref()) {
+                forget_secret(&old);
+            }
+            settings.notifications.webhook_url = stored;
+        }
+        _ => {
+            return Err(ServerError::UnknownKey(format!(
+                "{} (valid keys: {})",
+                key,
+                SETTINGS_KEYS.join(", ")
+            )))
+        }
+    }
 
-    // Mod Loader Configuration
-    let (mod_loader, mod_loader_version) = match server_type {
-        "FORGE" => {
-            let version = Input::new()
-                .with_prompt("Enter Forge version (e.g., 47.1.0, RECOMMENDED)")
-                .default("RECOMMENDED".into())
-                .interact_text()?;
-            (Some("FORGE".to_string()), Some(version))
-        },
-        "FABRIC" => {
-            let version = Input::new()
-                .with_prompt("Enter Fabric Loader version (e.g., 0.14.21, LATEST)")
-                .default("LATEST".into())
-                .interact_text()?;
-            (Some("FABRIC".to_string()), Some(version))
-        },
-        _ => (None, None)
+    save_settings(&settings)?;
+    println!("{}", format!("Set '{}'.", key).green());
+    Ok(())
+}
+
+/// Posts a message to the configured webhook. Notifications are best effort: a missing or
+/// failing webhook never fails the command that triggered it.
+fn notify(message: &str) {
+    let Some(url) = load_settings().ok().and_then(|s| s.notifications.webhook_url) else {
+        return;
     };
+    if let Err(e) = resolve_secret(&url).and_then(|url| post_webhook(&url, message)) {
+        eprintln!("{} Couldn't send the notification: {}", "Warning:".yellow(), e);
+    }
+}
 
-    // Memory Configuration
-    let memory: String = Input::new()
-        .with_prompt("Enter server memory (e.g., 2G, 4G)")
-        .default("2G".into())
-        .interact_text()?;
+fn post_webhook(url: &str, message: &str) -> Result<()> {
+    // Discord expects "content", Slack (and Slack-compatible services) "text"
+    let field = if webhook_host(url).contains("discord") { "content" } else { "text" };
+    let body = serde_json::json!({ field: format!("[BlockOps] {}", message) }).to_string();
+    // The URL is a credential, so it goes through a config on stdin rather than the visible argv
+    let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"));
+    let curl_config = format!("url = {}\ndata-binary = {}\n", quote(url), quote(&body));
+    let mut child = ProcessCommand::new("curl")
+        .args(["-fsS", "--max-time", "10", "-H", "Content-Type: application/json", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().expect("stdin is piped").write_all(curl_config.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(ServerError::Io(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        )));
+    }
+    Ok(())
+}
 
-    // Server Port
-    let port: String = Input::new()
-        .with_prompt("Enter server port")
-        .default("25565".into())
-        .interact_text()?;
+fn webhook_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split('/').next().unwrap_or(rest)
+}
 
-    // Java Arguments (Optional)
-    let java_args: Option<String> = if Confirm::new()
-        .with_prompt("Would you like to customize Java arguments?")
-        .interact()?
-    {
-        Some(
-            Input::new()
-                .with_prompt("Enter custom Java arguments")
-                .default("-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200".into())
-                .interact_text()?,
-        )
-    } else {
-        None
+fn check_notifications(test: bool) -> Result<()> {
+    let settings = load_settings()?;
+    let Some(url) = settings.notifications.webhook_url else {
+        println!("{}", "No webhook configured. Set one with:".yellow());
+        println!("  mc-server settings set notifications.webhook_url https://discord.com/api/webhooks/...");
+        return Ok(());
     };
+    let in_keyring = secrets::account(&url).is_some();
+    let url = resolve_secret(&url)?;
+    println!(
+        "Notifications go to a webhook on {}{}",
+        webhook_host(&url),
+        if in_keyring { " (URL kept in the OS keyring)" } else { "" }
+    );
+    if test {
+        post_webhook(&url, "Test notification, webhooks are working")?;
+        println!("{}", "Test message sent.".green());
+    }
+    Ok(())
+}
 
-    // EULA Agreement
-    if !Confirm::new()
-        .with_prompt("Do you agree to the Minecraft EULA? (https://account.mojang.com/documents/minecraft_eula)")
-        .interact()?
-    {
-        println!("{}", "EULA must be accepted to continue.".red());
+/// Reads a value from the server's `server.properties`, if the file and key exist
+fn read_server_property(data_path: &str, key: &str) -> Option<String> {
+    let content = fs::read_to_string(Path::new(data_path).join("server.properties")).ok()?;
+    parse_properties(&content)
+        .into_iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v)
+}
+
+/// Key/value pairs of a server.properties file, in file order
+fn parse_properties(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .filter(|(k, _)| !k.is_empty())
+        .collect()
+}
+
+/// Replaces existing values in place and appends new keys, keeping comments and order
+fn merge_properties(content: &str, values: &BTreeMap<String, String>) -> String {
+    let mut remaining = values.clone();
+    let mut merged = String::new();
+    for line in content.lines() {
+        let key = line.split_once('=').map(|(k, _)| k.trim()).filter(|_| !line.trim_start().starts_with('#'));
+        match key.and_then(|k| remaining.remove_entry(k)) {
+            Some((key, value)) => merged.push_str(&format!("{}={}\n", key, value)),
+            None => {
+                merged.push_str(line);
+                merged.push('\n');
+            }
+        }
+    }
+    for (key, value) in remaining {
+        merged.push_str(&format!("{}={}\n", key, value));
+    }
+    merged
+}
+
+fn show_properties(name: &str) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+
+    let path = Path::new(&info.data_path).join("server.properties");
+    if !path.exists() {
+        println!("{}", "No server.properties yet, it's written the first time the server starts.".yellow());
         return Ok(());
     }
+    for (key, value) in parse_properties(&fs::read_to_string(path)?) {
+        let marker = if info.property_overrides.contains_key(&key) { " (imported)" } else { "" };
+        println!("{}={}{}", key.bright_blue(), value, marker.bright_magenta());
+    }
+    Ok(())
+}
 
-    // Create server directory and compose file
-    let data_path = Path::new(CONFIG_DIR).join(&server_name);
-    fs::create_dir_all(&data_path)?;
+fn import_properties(name: &str, path: &Path) -> Result<()> {
+    if !load_server_config()?.servers.contains_key(name) {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
 
-    let mut environment = vec![
-        "EULA=TRUE".to_string(),
-        format!("MEMORY={}", memory),
-        format!("VERSION={}", version),
-        format!("TYPE={}", server_type),
-    ];
+    let mut values = BTreeMap::new();
+    for (key, value) in parse_properties(&fs::read_to_string(path)?) {
+        if MANAGED_PROPERTIES.contains(&key.as_str()) {
+            println!("{}", format!("Skipping {}, it's managed by the server image", key).yellow());
+        } else {
+            if !KNOWN_PROPERTIES.contains(&key.as_str()) {
+                println!("{}", format!("Warning: '{}' isn't a known server property, importing it anyway", key).yellow());
+            }
+            values.insert(key, value);
+        }
+    }
+    if values.is_empty() {
+        println!("{}", "Nothing to import.".yellow());
+        return Ok(());
+    }
 
-    // Add Java arguments if specified
-    if let Some(args) = &java_args {
-        environment.push(format!("JVM_OPTS={}", args));
+    // The server rewrites server.properties on shutdown, so it has to be down while we edit it
+    let was_running = is_server_running(name)?;
+    if was_running {
+        stop_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
     }
 
-    let compose_config = ComposeConfig {
-        version: "3.8".to_string(),
-        services: {
-            let mut services = HashMap::new();
-            services.insert(
-                server_name.clone(),
-                MinecraftService {
-                    image: "itzg/minecraft-server".to_string(),
-                    container_name: format!("mc-{}", server_name),
-                    ports: vec![format!("{}:25565", port)],
-                    environment,
-                    volumes: vec![
-                        format!("{}:/data", data_path.to_string_lossy()),
-                    ],
-                    restart: "unless-stopped".to_string(),
-                    stdin_open: true,
-                    tty: true,
-                },
-            );
-            services
-        },
+    // Loaded after stopping, which may record an auto backup
+    let mut config = load_server_config()?;
+    let info = config
+        .servers
+        .get_mut(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    let target = Path::new(&info.data_path).join("server.properties");
+    let current = if target.exists() { fs::read_to_string(&target)? } else { String::new() };
+    fs::write(&target, merge_properties(&current, &values))?;
+
+    let count = values.len();
+    info.property_overrides.extend(values);
+    write_compose_file(name, info)?;
+    save_server_config(&config)?;
+    println!("{}", format!("Imported {} properties into '{}'.", count, name).green());
+
+    if was_running {
+        start_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
+    }
+    Ok(())
+}
+
+/// Directories holding a dimension's terrain, covering both the vanilla layout and the
+/// Bukkit layout where the nether and end live in `<world>_nether` and `<world>_the_end`
+fn dimension_dirs(data_path: &str, world: &str, dimension: Dimension) -> Vec<PathBuf> {
+    let data = Path::new(data_path);
+    let world_dir = data.join(world);
+
+    let candidates = match dimension {
+        Dimension::Overworld => vec![world_dir.join("region"), world_dir.join("entities"), world_dir.join("poi")],
+        Dimension::Nether => vec![world_dir.join("DIM-1"), data.join(format!("{}_nether", world)).join("DIM-1")],
+        Dimension::End => vec![world_dir.join("DIM1"), data.join(format!("{}_the_end", world)).join("DIM1")],
     };
+    candidates.into_iter().filter(|p| p.is_dir()).collect()
+}
 
-    // Save configurations
-    let compose_path = data_path.join("docker-compose.yml");
-    let yaml = serde_yaml::to_string(&compose_config)?;
-    fs::write(compose_path, yaml)?;
+fn reset_dimension(name: &str, dimension: Dimension, world: Option<&str>, force: bool) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+
+    let dimension_name = dimension.to_possible_value().map_or(String::new(), |v| v.get_name().to_string());
+    let worlds = server_worlds(info);
+    let world = match world {
+        Some(world) if !worlds.iter().any(|w| w == world) => {
+            return Err(ServerError::InvalidValue(
+                "world".to_string(),
+                format!("'{}' is not one of this server's worlds ({})", world, worlds.join(", ")),
+            ))
+        }
+        Some(world) => world.to_string(),
+        None if worlds.len() > 1 && is_interactive() => {
+            let index = Select::new()
+                .with_prompt("Select the world to reset")
+                .items(&worlds)
+                .default(0)
+                .interact()?;
+            worlds[index].clone()
+        }
+        None => worlds[0].clone(),
+    };
+    let dirs = dimension_dirs(&info.data_path, &world, dimension);
+    if dirs.is_empty() {
+        println!("{}", format!("No {} data found for server '{}', nothing to reset.", dimension_name, name).yellow());
+        return Ok(());
+    }
+
+    println!("{}", format!("\nThe following will be deleted from '{}':", name).bright_cyan());
+    for dir in &dirs {
+        println!("- {}", dir.display());
+    }
+    if !force {
+        require_interactive("pass --force to reset without confirming")?;
+        if !Confirm::new()
+            .with_prompt(format!("Reset the {} of '{}'? Player data and other dimensions are kept.", dimension_name, name))
+            .interact()?
+        {
+            return Ok(());
+        }
+    }
+
+    if is_server_running(name)? {
+        stop_servers(Some(name.to_string()), None, &ComposeArgs::default())?;
+    }
+    backup_server(name, &BackupOptions::default())?;
+
+    for dir in &dirs {
+        fs::remove_dir_all(dir)?;
+    }
+    println!(
+        "{}",
+        format!("The {} of '{}' will regenerate on the next start.", dimension_name, name).green()
+    );
+    Ok(())
+}
+
+/// Name and UUID of a player, given either. Names are resolved through the server's usercache.json,
+/// so only players who have joined before can be found by name.
+fn resolve_player(info: &ServerInfo, player: &str) -> Result<(String, String)> {
+    let uuid = Regex::new(r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$")?;
+    match cached_player(info, player) {
+        Some(found) => Ok(found),
+        None if uuid.is_match(player) => Ok((player.to_lowercase(), player.to_lowercase())),
+        None => Err(ServerError::InvalidValue(
+            "player".to_string(),
+            format!("'{}' isn't in usercache.json, give their UUID instead", player),
+        )),
+    }
+}
+
+/// Name and UUID of a player (given by either) from the server's usercache.json
+fn cached_player(info: &ServerInfo, player: &str) -> Option<(String, String)> {
+    let cache: Vec<serde_json::Value> = fs::read_to_string(Path::new(&info.data_path).join("usercache.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let entry = cache.iter().find(|entry| {
+        entry["name"].as_str().is_some_and(|n| n.eq_ignore_ascii_case(player))
+            || entry["uuid"].as_str().is_some_and(|u| u.eq_ignore_ascii_case(player))
+    })?;
+    Some((
+        entry["name"].as_str().unwrap_or(player).to_string(),
+        entry["uuid"].as_str().unwrap_or_default().to_lowercase(),
+    ))
+}
+
+/// Name and UUID of a player by name, from usercache.json or else the Mojang API, which also
+/// fixes the capitalisation of the name
+fn lookup_player(info: &ServerInfo, player: &str) -> Result<(String, String)> {
+    if let Some(found) = cached_player(info, player) {
+        return Ok(found);
+    }
+    if !Regex::new(r"^[A-Za-z0-9_]{1,16}$")?.is_match(player) {
+        return Err(ServerError::InvalidValue(
+            "player".to_string(),
+            format!("'{}' isn't a valid player name", player),
+        ));
+    }
+    let output = ProcessCommand::new("curl")
+        .args(["-fsS", "--max-time", "10"])
+        .arg(format!("{}{}", MOJANG_PROFILE_URL, player))
+        .output()?;
+    // curl -f exits with 22 on an HTTP error, which is how the API reports unknown names
+    if output.status.code() == Some(22) || (output.status.success() && output.stdout.is_empty()) {
+        return Err(ServerError::InvalidValue(
+            "player".to_string(),
+            format!("there's no Minecraft account named '{}'", player),
+        ));
+    }
+    if !output.status.success() {
+        return Err(ServerError::Io(io::Error::other(format!(
+            "couldn't reach the Mojang API: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    let profile: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let (Some(id), Some(name)) = (profile["id"].as_str(), profile["name"].as_str()) else {
+        return Err(ServerError::Io(io::Error::other("unexpected response from the Mojang API")));
+    };
+    if id.len() != 32 {
+        return Err(ServerError::Io(io::Error::other(format!("unexpected UUID '{}' from the Mojang API", id))));
+    }
+    // The API returns UUIDs without dashes; the server's files use the 8-4-4-4-12 form
+    let uuid = format!("{}-{}-{}-{}-{}", &id[..8], &id[8..12], &id[12..16], &id[16..20], &id[20..]);
+    Ok((name.to_string(), uuid.to_lowercase()))
+}
+
+/// An entry of whitelist.json or ops.json; the level fields are only present in ops.json
+#[derive(Serialize, Deserialize)]
+struct AccessEntry {
+    uuid: String,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    level: Option<u8>,
+    #[serde(default, rename = "bypassesPlayerLimit", skip_serializing_if = "Option::is_none")]
+    bypasses_player_limit: Option<bool>,
+}
+
+fn read_access_list(path: &Path) -> Result<Vec<AccessEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&content).map_err(|e| {
+        ServerError::InvalidValue(
+            path.display().to_string(),
+            format!("expected a list of {{\"uuid\", \"name\"}} entries: {}", e),
+        )
+    })
+}
+
+/// Adds or removes players in whitelist.json or ops.json while the server is down. The server
+/// rewrites both files from memory, so edits made while it runs would be lost.
+fn edit_access_list(name: &str, file: &str, action: AccessAction) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    let path = Path::new(&info.data_path).join(file);
+    let mut entries = read_access_list(&path)?;
+    let is_ops = file == OPS_FILE;
+
+    let (players, add) = match action {
+        AccessAction::List => {
+            if entries.is_empty() {
+                println!("{} is empty.", file);
+            }
+            for entry in &entries {
+                match entry.level {
+                    Some(level) => println!("{} ({}), level {}", entry.name, entry.uuid, level),
+                    None => println!("{} ({})", entry.name, entry.uuid),
+                }
+            }
+            return Ok(());
+        }
+        AccessAction::Add { players } => (players, true),
+        AccessAction::Remove { players } => (players, false),
+    };
+    if is_server_running(name)? {
+        return Err(ServerError::ServerRunning(name.to_string()));
+    }
+
+    // Every name is looked up first, so one unknown player leaves the file untouched
+    let found = if add {
+        players.iter().map(|p| lookup_player(info, p)).collect::<Result<Vec<_>>>()?
+    } else {
+        Vec::new()
+    };
+    let mut changed = false;
+    for (index, player) in players.iter().enumerate() {
+        if add {
+            let (player_name, uuid) = found[index].clone();
+            if entries.iter().any(|e| e.uuid.eq_ignore_ascii_case(&uuid)) {
+                println!("{} is already in {}", player_name, file);
+                continue;
+            }
+            entries.push(AccessEntry {
+                uuid,
+                name: player_name.clone(),
+                level: is_ops.then_some(4),
+                bypasses_player_limit: is_ops.then_some(false),
+            });
+            println!("{}", format!("Added {} to {}", player_name, file).green());
+        } else {
+            let before = entries.len();
+            entries.retain(|e| !e.name.eq_ignore_ascii_case(player) && !e.uuid.eq_ignore_ascii_case(player));
+            if entries.len() == before {
+                println!("{} isn't in {}", player, file);
+                continue;
+            }
+            println!("{}", format!("Removed {} from {}", player, file).green());
+        }
+        changed = true;
+    }
+    if !changed {
+        return Ok(());
+    }
+
+    if path.exists() {
+        fs::copy(&path, path.with_extension("json.bak"))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&entries)?)?;
+    Ok(())
+}
+
+/// A player's files in the main world that exist, relative to the data directory
+fn player_files(info: &ServerInfo, uuid: &str) -> Vec<PathBuf> {
+    let world = PathBuf::from(&server_worlds(info)[0]);
+    [
+        world.join("playerdata").join(format!("{}.dat", uuid)),
+        world.join("playerdata").join(format!("{}.dat_old", uuid)),
+        world.join("stats").join(format!("{}.json", uuid)),
+        world.join("advancements").join(format!("{}.json", uuid)),
+    ]
+    .into_iter()
+    .filter(|path| Path::new(&info.data_path).join(path).is_file())
+    .collect()
+}
+
+fn inspect_player(name: &str, player: &str) -> Result<()> {
+    let config = load_server_config()?;
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    let (player_name, uuid) = resolve_player(info, player)?;
+    let path = Path::new(&info.data_path)
+        .join(&server_worlds(info)[0])
+        .join("playerdata")
+        .join(format!("{}.dat", uuid));
+    if !path.is_file() {
+        println!("{}", format!("No saved data for {} ({}) on '{}'.", player_name, uuid, name).yellow());
+        return Ok(());
+    }
+    let data = nbt::read_gzip_file(&path).map_err(|e| {
+        ServerError::Io(io::Error::new(e.kind(), format!("couldn't read {}: {}", path.display(), e)))
+    })?;
 
-    // Update server config
-    config.servers.insert(
-        server_name.clone(),
-        ServerInfo {
-            version,
-            port,
-            memory,
-            data_path: data_path.to_string_lossy().to_string(),
-            server_type: server_type.to_string(),
-            mod_loader,
-            mod_loader_version,
-            java_args,
-            created_at: chrono::Utc::now(),
-            last_started: None,
+    println!("{}", format!("{} ({})", player_name, uuid).bright_cyan());
+    if is_server_running(name)? {
+        println!("{}", "The server is running, so this is as of its last autosave.".yellow());
+    }
+    let dimension = match data.get("Dimension") {
+        Some(nbt::Tag::String(dimension)) => dimension.clone(),
+        Some(tag) => match tag.as_i64() {
+            Some(-1) => "minecraft:the_nether".to_string(),
+            Some(1) => "minecraft:the_end".to_string(),
+            _ => "minecraft:overworld".to_string(),
         },
-    );
-    save_server_config(&config)?;
+        None => "unknown".to_string(),
+    };
+    let position: Vec<String> = data
+        .get("Pos")
+        .and_then(|pos| pos.as_list())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| v.as_f64())
+        .map(|v| format!("{:.1}", v))
+        .collect();
+    println!("  Position: {} in {}", position.join(", "), dimension);
 
-    println!("{}", "\nServer configuration saved successfully!".green());
-    if Confirm::new()
-        .with_prompt("Would you like to start the server now?")
-        .interact()?
-    {
-        start_servers(Some(server_name))?;
+    let number = |key: &str| data.get(key).and_then(|v| v.as_f64());
+    if let Some(health) = number("Health") {
+        println!("  Health: {:.1} / 20", health);
+    }
+    if let Some(food) = number("foodLevel") {
+        println!("  Food: {} / 20", food);
+    }
+    if let Some(level) = number("XpLevel") {
+        println!("  XP level: {}", level);
     }
+    let mode = match data.get("playerGameType").and_then(|v| v.as_i64()) {
+        Some(0) => "survival",
+        Some(1) => "creative",
+        Some(2) => "adventure",
+        Some(3) => "spectator",
+        _ => "unknown",
+    };
+    println!("  Game mode: {}", mode);
 
+    for (key, label) in [("Inventory", "Inventory"), ("EnderItems", "Ender chest")] {
+        let items = data.get(key).and_then(|v| v.as_list()).unwrap_or_default();
+        println!("  {}: {} stack(s)", label, items.len());
+        for item in items {
+            let id = item.get("id").and_then(|v| v.as_str()).unwrap_or("?");
+            // 1.20.5 renamed Count (a byte) to count (an int, left out when 1)
+            let count = item.get("count").or_else(|| item.get("Count")).and_then(|v| v.as_i64()).unwrap_or(1);
+            let slot = item.get("Slot").and_then(|v| v.as_i64()).map_or(String::new(), |s| format!("slot {:>3}: ", s));
+            println!("    {}{} x{}", slot, id, count);
+        }
+    }
     Ok(())
 }
 
-fn list_servers() -> Result<()> {
+fn reset_player(name: &str, player: &str, force: bool) -> Result<()> {
     let config = load_server_config()?;
-    if config.servers.is_empty() {
-        println!("{}", "\nNo servers configured yet. Use 'create' to add a server.".yellow());
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
+    // A running server writes the player's data back from memory when they leave or it saves
+    if is_server_running(name)? {
+        return Err(ServerError::ServerRunning(name.to_string()));
+    }
+    let (player_name, uuid) = resolve_player(info, player)?;
+    let files = player_files(info, &uuid);
+    if files.is_empty() {
+        println!("{}", format!("No saved data for {} ({}) on '{}'.", player_name, uuid, name).yellow());
         return Ok(());
     }
 
-    println!("\n{}", "Configured Minecraft Servers:".bright_cyan());
-    println!("{}", "=========================".bright_cyan());
+    println!("{}", format!("\nThe following will be deleted from '{}':", name).bright_cyan());
+    for file in &files {
+        println!("- {}", file.display());
+    }
+    if !force {
+        require_interactive("pass --force to reset without confirming")?;
+        if !Confirm::new()
+            .with_prompt(format!("Reset {}? Their inventory, position, stats and advancements are lost.", player_name))
+            .interact()?
+        {
+            return Ok(());
+        }
+    }
 
-    for (name, info) in config.servers {
-        let status = get_server_status(&name)?;
-        let mod_info = info.mod_loader.map_or("".to_string(), |m| format!(" ({})", m));
-        
-        println!(
-            "{}: {} {}\n  Version: {}{}\n  Port: {}, Memory: {}\n  Created: {}\n  Last Started: {}\n",
-            name.bright_green(),
-            status,
-            info.server_type.bright_blue(),
-            info.version.bright_blue(),
-            mod_info.bright_blue(),
-            info.port,
-            info.memory,
-            info.created_at.format("%Y-%m-%d %H:%M:%S"),
-            info.last_started.map_or("Never".to_string(), |d| d.format("%Y-%m-%d %H:%M:%S").to_string())
-        );
+    // Archived relative to the data directory, so extracting it there puts the files back
+    let backup_dir = config_dir().join(BACKUP_DIR).join(PLAYER_BACKUP_DIR).join(name);
+    fs::create_dir_all(&backup_dir)?;
+    let backup_file = backup_dir.canonicalize()?.join(format!(
+        "{}_{}.tar.gz",
+        chrono::Local::now().format("%Y%m%d_%H%M%S"),
+        uuid
+    ));
+    let output = ProcessCommand::new("tar")
+        .current_dir(&info.data_path)
+        .arg("-czf")
+        .arg(&backup_file)
+        .args(&files)
+        .output()?;
+    if !output.status.success() {
+        let _ = fs::remove_file(&backup_file);
+        return Err(ServerError::Io(io::Error::other(format!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
     }
+    println!("Player data backed up to {}", backup_file.display());
+    println!("Undo with: tar -xzf {} -C {}", backup_file.display(), info.data_path);
 
+    for file in &files {
+        fs::remove_file(Path::new(&info.data_path).join(file))?;
+    }
+    println!(
+        "{}",
+        format!("{} starts fresh at spawn the next time they join '{}'.", player_name, name).green()
+    );
     Ok(())
 }
 
-fn get_server_status(name: &str) -> Result<ColoredString> {
-    let output = ProcessCommand::new("docker")
-        .args(["ps", "-q", "-f", &format!("name=mc-{}", name)])
-        .output()?;
+/// Value to write to a config file for a secret. With `--use-keyring` the secret goes into the OS
+/// keyring and the file gets a placeholder; without a usable keyring it stays in the file.
+fn store_secret(account: &str, secret: &str) -> String {
+    if !USE_KEYRING.load(Ordering::Relaxed) {
+        return secret.to_string();
+    }
+    match secrets::store(account, secret) {
+        Ok(()) => secrets::placeholder(account),
+        Err(e) => {
+            println!(
+                "{} the OS keyring isn't available ({}), so '{}' is stored in the config file",
+                "Warning:".yellow(),
+                e,
+                account
+            );
+            secret.to_string()
+        }
+    }
+}
+
+/// Secret behind a config value: the value itself, or for a keyring placeholder the keyring entry,
+/// falling back to the account's BLOCKOPS_SECRET_ variable when there's no keyring
+fn resolve_secret(value: &str) -> Result<String> {
+    let Some(account) = secrets::account(value) else {
+        return Ok(value.to_string());
+    };
+    let reason = match secrets::load(account) {
+        Ok(Some(secret)) => return Ok(secret),
+        Ok(None) => "has no entry for it".to_string(),
+        Err(e) => format!("isn't available ({})", e),
+    };
+    let var = secrets::env_var(account);
+    std::env::var(&var)
+        .map_err(|_| ServerError::SecretUnavailable(account.to_string(), format!("{}; set {} instead", reason, var)))
+}
+
+/// Deletes the keyring entry behind a placeholder that's being replaced or removed, best effort
+fn forget_secret(value: &str) {
+    if let Some(account) = secrets::account(value) {
+        let _ = secrets::delete(account);
+    }
+}
+
+/// How a possibly secret value is written into a compose file: keyring secrets as a variable
+/// that `compose_command` sets
+fn compose_secret(value: &str) -> String {
+    secrets::account(value).map_or_else(|| value.to_string(), |account| format!("${{{}}}", secrets::env_var(account)))
+}
+
+/// Whether an environment variable or config key likely holds a credential
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_uppercase();
+    ["PASSWORD", "PASSWD", "TOKEN", "SECRET", "API_KEY", "APIKEY", "WEBHOOK", "CREDENTIAL"]
+        .iter()
+        .any(|marker| key.contains(marker))
+}
+
+/// Redacts the value of `KEY=value` and `key: value` lines whose key looks like a secret
+fn redact_line(line: &str) -> String {
+    let body = line.trim_start().trim_start_matches("- ").trim_start_matches('"');
+    let separator = match (body.find('='), body.find(": ")) {
+        (Some(eq), Some(colon)) => eq.min(colon),
+        (Some(eq), None) => eq,
+        (None, Some(colon)) => colon,
+        (None, None) => return line.to_string(),
+    };
+    if !is_secret_key(body[..separator].trim_matches('"')) {
+        return line.to_string();
+    }
+    let prefix_len = line.len() - body.len() + separator + 1;
+    let space = if body[separator..].starts_with(':') { " " } else { "" };
+    format!("{}{}<redacted>", &line[..prefix_len], space)
+}
 
-    Ok(if !output.stdout.is_empty() {
-        "RUNNING".bright_green()
+/// [`print_line_diff`] with the values of secret-looking keys hidden
+fn print_secret_diff(old: &str, new: &str) {
+    let (masked_old, masked_new) = (redact_text(old), redact_text(new));
+    if masked_old == masked_new && old != new {
+        println!("(only hidden secret values change)");
     } else {
-        "STOPPED".red()
-    })
+        print_line_diff(&masked_old, &masked_new);
+    }
+}
+
+/// Redacts every value, at any depth, whose key looks like a secret
+fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                if is_secret_key(key) && !value.is_null() {
+                    *value = "<redacted>".into();
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+fn redact_text(text: &str) -> String {
+    text.lines().map(redact_line).collect::<Vec<_>>().join("\n")
 }
 
-fn start_servers(name: Option<String>) -> Result<()> {
+fn create_bundle(name: &str, lines: usize, out: Option<PathBuf>) -> Result<()> {
     let config = load_server_config()?;
-    let pb = create_spinner("Starting server(s)");
+    let info = config
+        .servers
+        .get(name)
+        .ok_or_else(|| ServerError::ServerNotFound(name.to_string()))?;
 
-    match name {
-        Some(server_name) => {
-            if let Some(info) = config.servers.get(&server_name) {
-                start_single_server(&server_name, &info.data_path, &pb)?;
-                update_last_started(&server_name)?;
-            } else {
-                return Err(ServerError::ServerNotFound(server_name));
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let out = out.unwrap_or_else(|| PathBuf::from(format!("{}-bundle-{}.zip", name, timestamp)));
+    let pb = create_spinner("Collecting diagnostics");
+
+    let mut zip = zip::ZipWriter::new(File::create(&out)?);
+    let options = zip::write::SimpleFileOptions::default();
+    let mut add = |path: &str, content: &str| -> Result<()> {
+        zip.start_file(path, options)?;
+        io::Write::write_all(&mut zip, content.as_bytes())?;
+        Ok(())
+    };
+
+    let mut info_json = serde_json::to_value(info)?;
+    redact_json(&mut info_json);
+    add("server-info.json", &serde_json::to_string_pretty(&info_json)?)?;
+
+    match fs::read_to_string(info.compose_file()) {
+        Ok(compose) => add("docker-compose.yml", &redact_text(&compose))?,
+        Err(e) => add("docker-compose.yml.missing", &e.to_string())?,
+    }
+
+    pb.set_message("Collecting logs");
+    let variant = ComposeVariant::detect();
+    let logs = captured_compose_command(info)?
+        .arg("logs")
+        .args(variant.flag(ComposeFlag::LogsNoColor))
+        .args(variant.flag(ComposeFlag::LogsTail(lines)))
+        .output();
+    match logs {
+        Ok(output) => add(
+            "logs.txt",
+            &redact_text(&(String::from_utf8_lossy(&output.stdout) + String::from_utf8_lossy(&output.stderr))),
+        )?,
+        Err(e) => add("logs.txt", &format!("Failed to collect logs: {}", e))?,
+    }
+
+    pb.set_message("Inspecting container");
+    let inspect = ProcessCommand::new("docker")
+        .args(["inspect", &format!("mc-{}", name)])
+        .output();
+    match inspect {
+        Ok(output) if output.status.success() => {
+            let mut inspect: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+            for container in inspect.as_array_mut().into_iter().flatten() {
+                for entry in container["Config"]["Env"].as_array_mut().into_iter().flatten() {
+                    if let Some(text) = entry.as_str() {
+                        *entry = redact_line(text).into();
+                    }
+                }
             }
+            add("docker-inspect.json", &serde_json::to_string_pretty(&inspect)?)?;
         }
-        None => {
-            if config.servers.is_empty() {
-                println!("{}", "No servers configured!".yellow());
-                return Ok(());
-            }
-            for (name, info) in config.servers {
-                start_single_server(&name, &info.data_path, &pb)?;
-                update_last_started(&name)?;
+        Ok(output) => add("docker-inspect.txt", &String::from_utf8_lossy(&output.stderr))?,
+        Err(e) => add("docker-inspect.txt", &format!("Failed to inspect container: {}", e))?,
+    }
+
+    let crash_dir = Path::new(&info.data_path).join("crash-reports");
+    if crash_dir.is_dir() {
+        for entry in fs::read_dir(crash_dir)? {
+            let path = entry?.path();
+            if let (true, Some(file_name)) = (path.is_file(), path.file_name().and_then(|f| f.to_str())) {
+                let report = String::from_utf8_lossy(&fs::read(&path)?).to_string();
+                add(&format!("crash-reports/{}", file_name), &redact_text(&report))?;
             }
         }
     }
-    pb.finish_with_message("Server start operation completed!");
+
+    zip.finish()?;
+    pb.finish_and_clear();
+    println!("{}", format!("Diagnostic bundle written to {}", out.display()).green());
+    println!("Secrets were redacted, but review the bundle before sharing it.");
     Ok(())
 }
 
-fn update_last_started(name: &str) -> Result<()> {
-    let mut config = load_server_config()?;
-    if let Some(info) = config.servers.get_mut(name) {
-        info.last_started = Some(chrono::Utc::now());
-        save_server_config(&config)?;
-    }
-    Ok(())
+/// Parses the reply to RCON `list`, e.g. "There are 2 of a max of 20 players online: Steve, Alex"
+fn parse_player_list(output: &str) -> Vec<String> {
+    output
+        .split_once(':')
+        .map(|(_, players)| {
+            players
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
-fn start_single_server(name: &str, path: &str, pb: &ProgressBar) -> Result<()> {
-    pb.set_message(format!("Starting server {}...", name));
-    let output = ProcessCommand::new("docker-compose")
-        .current_dir(path)
-        .arg("up")
-        .arg("-d")
-        .output()?;
+fn online_players(name: &str) -> Result<Vec<String>> {
+    Ok(parse_player_list(&run_rcon(name, "list")?))
+}
 
-    if !output.status.success() {
-        return Err(ServerError::DockerCommandFailed(
-            String::from_utf8_lossy(&output.stderr).to_string(),
-        ));
+fn activity_path(name: &str) -> PathBuf {
+    config_dir().join(ACTIVITY_DIR).join(format!("{}.json", name))
+}
+
+fn load_activity(name: &str) -> Result<ActivityLog> {
+    let path = activity_path(name);
+    if !path.exists() {
+        return Ok(ActivityLog::default());
     }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
 
-    println!("{}", format!("Server '{}' started successfully!", name).green());
+fn save_activity(name: &str, activity: &ActivityLog) -> Result<()> {
+    let path = activity_path(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(activity)?)?;
     Ok(())
 }
 
-fn stop_servers(name: Option<String>) -> Result<()> {
+fn record_activity(name: &str, players: &[String], interval: u64) -> Result<()> {
+    let mut activity = load_activity(name)?;
+    let now = chrono::Utc::now();
+    for player in players {
+        let entry = activity.players.entry(player.clone()).or_insert(PlayerActivity {
+            seconds_played: 0,
+            first_seen: now,
+            last_seen: now,
+        });
+        entry.seconds_played += interval;
+        entry.last_seen = now;
+    }
+    save_activity(name, &activity)
+}
+
+fn monitor_server(name: &str, interval: u64, track_activity: bool) -> Result<()> {
     let config = load_server_config()?;
-    let pb = create_spinner("Stopping server(s)");
+    if !config.servers.contains_key(name) {
+        return Err(ServerError::ServerNotFound(name.to_string()));
+    }
+    let interval = interval.max(1);
 
-    match name {
-        Some(server_name) => {
-            if let Some(info) = config.servers.get(&server_name) {
-                stop_single_server(&server_name, &info.data_path, &pb)?;
-            } else {
-                return Err(ServerError::ServerNotFound(server_name));
+    println!("{}", format!("\nMonitoring server '{}' every {}s:", name, interval).bright_cyan());
+    if track_activity {
+        println!("Recording player activity to {}", activity_path(name).display());
+    }
+    println!("{}", "Press Ctrl+C to exit".bright_yellow());
+
+    let mut was_running = None;
+    // Whether the server went down through BlockOps, which sends its own stop and start notifications
+    let mut stopped_by_tool = false;
+    loop {
+        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        let running = is_server_running(name)?;
+        match (was_running, running) {
+            (Some(true), false) => {
+                // `stop`, `restart` and `archive` take the server down with Compose, which removes
+                // the container; a crash or `docker stop` leaves it behind
+                stopped_by_tool = !managed_containers()?.contains(&format!("mc-{}", name));
+                if !stopped_by_tool {
+                    notify(&format!("Server '{}' went down (crashed or stopped outside BlockOps)", name));
+                }
             }
+            (Some(false), true) if !stopped_by_tool => notify(&format!("Server '{}' is running again", name)),
+            _ => {}
         }
-        None => {
-            if config.servers.is_empty() {
-                println!("{}", "No servers configured!".yellow());
-                return Ok(());
-            }
-            for (name, info) in config.servers {
-                stop_single_server(&name, &info.data_path, &pb)?;
+        was_running = Some(running);
+        if running {
+            match online_players(name) {
+                Ok(players) => {
+                    println!(
+                        "[{}] {} {} player(s) online{}",
+                        timestamp,
+                        "RUNNING".bright_green(),
+                        players.len(),
+                        if players.is_empty() { String::new() } else { format!(": {}", players.join(", ")) }
+                    );
+                    if track_activity {
+                        record_activity(name, &players, interval)?;
+                    }
+                }
+                // RCON is unavailable while the server is still starting
+                Err(e) => println!("[{}] {} {}", timestamp, "RUNNING".bright_green(), e.to_string().yellow()),
             }
+        } else {
+            println!("[{}] {}", timestamp, "STOPPED".red());
         }
+        std::thread::sleep(Duration::from_secs(interval));
     }
-    pb.finish_with_message("Server stop operation completed!");
-    Ok(())
 }
 
-fn stop_single_server(name: &str, path: &str, pb: &ProgressBar) -> Result<()> {
-    pb.set_message(format!("Stopping server {}...", name));
-    let output = ProcessCommand::new("docker-compose")
-        .current_dir(path)
-        .arg("down")
-        .output()?;
+fn format_duration(seconds: u64) -> String {
+    let (hours, minutes) = (seconds / 3600, seconds % 3600 / 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m", minutes),
+        _ => format!("{}h {}m", hours, minutes),
+    }
+}
 
-    if !output.status.success() {
-        return Err(ServerError::DockerCommandFailed(
-            String::from_utf8_lossy(&output.stderr).to_string(),
-        ));
+fn show_activity(name: &str, top: usize) -> Result<()> {
+    let config = load_server_config()?;
+    if !config.servers.contains_key(name) {
+        return Err(ServerError::ServerNotFound(name.to_string()));
     }
 
-    println!("{}", format!("Server '{}' stopped successfully!", name).green());
+    let activity = load_activity(name)?;
+    if activity.players.is_empty() {
+        println!(
+            "{}",
+            format!("No activity recorded for '{}'. Run 'monitor {} --track-activity' to start.", name, name).yellow()
+        );
+        return Ok(());
+    }
+
+    let mut players: Vec<(&String, &PlayerActivity)> = activity.players.iter().collect();
+    players.sort_by(|a, b| b.1.seconds_played.cmp(&a.1.seconds_played).then(a.0.cmp(b.0)));
+
+    println!("\n{}", format!("Top players on '{}':", name).bright_cyan());
+    println!("{}", "=========================".bright_cyan());
+    for (rank, (player, stats)) in players.iter().take(top).enumerate() {
+        println!(
+            "{:>3}. {:<16} {:>10}  last seen {}",
+            rank + 1,
+            player.bright_green(),
+            format_duration(stats.seconds_played),
+            stats.last_seen.format("%Y-%m-%d %H:%M")
+        );
+    }
     Ok(())
 }
 
-fn show_logs(name: &str, follow: bool) -> Result<()> {
-    let config = load_server_config()?;
-    if let Some(info) = config.servers.get(name) {
-        println!("{}", format!("\nShowing logs for server '{}':", name).bright_cyan());
-        
-        let mut cmd = ProcessCommand::new("docker-compose");
-        cmd.current_dir(&info.data_path)
-            .arg("logs");
-        
-        if follow {
-            println!("{}", "Press Ctrl+C to exit".bright_yellow());
-            cmd.arg("-f");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: ComposeVariant = ComposeVariant::V1;
+    const V2: ComposeVariant = ComposeVariant::V2 { plugin: false };
+    const PLUGIN: ComposeVariant = ComposeVariant::V2 { plugin: true };
+
+    #[test]
+    fn compose_flags_per_variant() {
+        assert_eq!(V1.flag(ComposeFlag::NoAnsi), ["--no-ansi"]);
+        assert_eq!(V2.flag(ComposeFlag::NoAnsi), ["--ansi", "never"]);
+        assert_eq!(PLUGIN.flag(ComposeFlag::NoAnsi), ["--ansi", "never"]);
+
+        for variant in [V1, V2, PLUGIN] {
+            assert_eq!(variant.flag(ComposeFlag::QuietPull), ["--quiet"]);
+            assert_eq!(variant.flag(ComposeFlag::StopTimeout(60)), ["--timeout", "60"]);
+            assert_eq!(variant.flag(ComposeFlag::LogsTail(200)), ["--tail", "200"]);
+            assert_eq!(variant.flag(ComposeFlag::LogsNoColor), ["--no-color"]);
         }
+    }
 
-        let status = cmd.status()?;
-        if !status.success() {
-            return Err(ServerError::DockerCommandFailed("Failed to show logs".to_string()));
+    #[test]
+    fn restarted_container_logs_skip_earlier_runs() {
+        // A reused container still holds the last run's Done and crash lines
+        let started = chrono::DateTime::parse_from_rfc3339("2026-10-15T10:00:00.75Z").unwrap().with_timezone(&chrono::Utc);
+        for variant in [V2, PLUGIN] {
+            assert_eq!(variant.flag(ComposeFlag::LogsSince(started)), ["--since", "2026-10-15T10:00:00Z"]);
         }
-    } else {
-        return Err(ServerError::ServerNotFound(name.to_string()));
+        assert_eq!(V1.flag(ComposeFlag::LogsSince(started)), ["--tail", "0"]);
     }
-    Ok(())
-}
 
-fn attach_console(name: &str) -> Result<()> {
-    let config = load_server_config()?;
-    if let Some(_) = config.servers.get(name) {
-        println!("{}", format!("\nAttaching to server '{}' console:", name).bright_cyan());
-        println!("{}", "Type 'exit' or press Ctrl+P, Ctrl+Q to detach".bright_yellow());
-        
-        let _ = ProcessCommand::new("docker")
-            .args(["exec", "-it", &format!("mc-{}", name),"rcon-cli"])
-            .status()?;
-    } else {
-        return Err(ServerError::ServerNotFound(name.to_string()));
+    #[test]
+    fn compose_command_per_variant() {
+        let args = |cmd: &ProcessCommand| {
+            cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(V1.command().get_program(), "docker-compose");
+        assert!(args(&V1.command()).is_empty());
+        assert_eq!(V2.command().get_program(), "docker-compose");
+        assert!(args(&V2.command()).is_empty());
+        assert_eq!(PLUGIN.command().get_program(), "docker");
+        assert_eq!(args(&PLUGIN.command()), ["compose"]);
     }
-    Ok(())
-}
 
-fn backup_server(name: &str) -> Result<()> {
-    let config = load_server_config()?;
-    if let Some(info) = config.servers.get(name) {
-        let pb = create_spinner("Creating backup");
-        
-        let backup_dir = Path::new(CONFIG_DIR).join(BACKUP_DIR);
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let backup_file = backup_dir.join(format!("{}_{}.tar.gz", name, timestamp));
+    #[test]
+    fn startup_lines_with_the_default_pattern() {
+        let ready = Regex::new(DEFAULT_READY_PATTERN).unwrap();
+        let crash = Regex::new(STARTUP_CRASH_PATTERN).unwrap();
+        let classify = |line| classify_startup_line(line, &ready, &crash);
 
-        // Create tar.gz archive
-        let output = ProcessCommand::new("tar")
-            .current_dir(&info.data_path)
-            .args(["-czf", backup_file.to_str().unwrap(), "."])
-            .output()?;
+        // Vanilla and Paper
+        assert_eq!(
+            classify(r#"[12:00:03] [Server thread/INFO]: Done (3.214s)! For help, type "help""#),
+            StartupLine::Ready
+        );
+        // Forge and NeoForge add the logger name
+        assert_eq!(
+            classify(r#"[12:01:40] [Server thread/INFO] [minecraft/DedicatedServer]: Done (95.6s)! For help"#),
+            StartupLine::Ready
+        );
+        // Velocity and BungeeCord
+        assert_eq!(classify("[12:00:02 INFO]: Listening on /0.0.0.0:25577"), StartupLine::Ready);
 
-        if !output.status.success() {
-            return Err(ServerError::DockerCommandFailed(
-                String::from_utf8_lossy(&output.stderr).to_string(),
-            ));
-        }
+        assert_eq!(classify("[12:00:01] [Worker-Main-1/INFO]: Preparing spawn area: 42%"), StartupLine::Waiting);
+        assert_eq!(classify("[12:00:01] [Server thread/INFO]: Starting minecraft server"), StartupLine::Waiting);
+        assert_eq!(
+            classify("[12:00:05] [Server thread/ERROR]: Encountered an unexpected exception"),
+            StartupLine::Crashed
+        );
+        assert_eq!(
+            classify("[12:00:05] [main/FATAL]: Failed to start the minecraft server"),
+            StartupLine::Crashed
+        );
+    }
 
-        pb.finish_with_message(format!("Backup created: {}", backup_file.display()));
-    } else {
-        return Err(ServerError::ServerNotFound(name.to_string()));
+    #[test]
+    fn startup_lines_with_a_custom_pattern() {
+        let ready = Regex::new(r"Timings Reset").unwrap();
+        let crash = Regex::new(STARTUP_CRASH_PATTERN).unwrap();
+
+        assert_eq!(classify_startup_line("[12:00:04 INFO]: Timings Reset", &ready, &crash), StartupLine::Ready);
+        // The default line no longer means ready
+        assert_eq!(
+            classify_startup_line(r#"[12:00:03 INFO]: Done (3.2s)! For help, type "help""#, &ready, &crash),
+            StartupLine::Waiting
+        );
+        assert_eq!(
+            classify_startup_line("[12:00:06 ERROR]: This crash report has been saved to: crash.txt", &ready, &crash),
+            StartupLine::Crashed
+        );
     }
-    Ok(())
-}
 
-fn restore_server(name: &str, backup_path: &Path) -> Result<()> {
-    let config = load_server_config()?;
-    if let Some(info) = config.servers.get(name) {
-        if !backup_path.exists() {
-            return Err(ServerError::Io(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Backup file not found",
-            )));
-        }
+    #[test]
+    fn last_log_line_descriptions() {
+        assert_eq!(last_log_line(""), "no log output");
+        assert_eq!(last_log_line("  \t"), "no log output");
+        assert_eq!(last_log_line("  Preparing spawn area: 42%\r"), "log line 'Preparing spawn area: 42%'");
+    }
 
-        // Stop server if running
-        stop_servers(Some(name.to_string()))?;
+    #[test]
+    fn timeout_error() {
+        let error = ServerError::Timeout(30, "'lobby' to be ready".to_string(), last_log_line(""));
+        assert_eq!(error.to_string(), "Timed out after 30s waiting for 'lobby' to be ready (last seen: no log output)");
+        assert_eq!(error.kind(), "Timeout");
+        assert_eq!(error.exit_code(), 5);
+    }
 
-        let pb = create_spinner("Restoring backup");
+    #[test]
+    fn log_lines_until_the_deadline() {
+        let (tx, rx) = mpsc::channel();
+        tx.send("Starting minecraft server".to_string()).unwrap();
+        tx.send("Preparing level \"world\"".to_string()).unwrap();
 
-        // Extract backup
-        let output = ProcessCommand::new("tar")
-            .current_dir(&info.data_path)
-            .args(["-xzf", backup_path.to_str().unwrap()])
-            .output()?;
+        // Once the deadline has passed queued lines no longer count, a chatty server still times out
+        let passed = Some(Instant::now());
+        assert_eq!(next_log_line(&rx, passed), Err(RecvTimeoutError::Timeout));
+        let ahead = Some(Instant::now() + Duration::from_secs(60));
+        assert_eq!(next_log_line(&rx, ahead).unwrap(), "Starting minecraft server");
+        assert_eq!(next_log_line(&rx, None).unwrap(), "Preparing level \"world\"");
 
-        if !output.status.success() {
-            return Err(ServerError::DockerCommandFailed(
-                String::from_utf8_lossy(&output.stderr).to_string(),
-            ));
-        }
+        let started = Instant::now();
+        let deadline = Some(started + Duration::from_millis(50));
+        assert_eq!(next_log_line(&rx, deadline), Err(RecvTimeoutError::Timeout));
+        assert!(started.elapsed() >= Duration::from_millis(50));
 
-        pb.finish_with_message("Backup restored successfully!");
+        drop(tx);
+        assert_eq!(next_log_line(&rx, ahead), Err(RecvTimeoutError::Disconnected));
+        assert_eq!(next_log_line(&rx, None), Err(RecvTimeoutError::Disconnected));
+    }
 
-        if Confirm::new()
-            .with_prompt("Would you like to start the server now?")
-            .interact()?
-        {
-            start_servers(Some(name.to_string()))?;
-        }
-    } else {
-        return Err(ServerError::ServerNotFound(name.to_string()));
+    #[test]
+    fn bundle_json_redacts_secret_keys_at_any_depth() {
+        let mut info = serde_json::json!({
+            "port": "25565",
+            "extra_env": { "CF_API_KEY": "abc", "MOTD": "hi" },
+            "managed_backups": { "interval": "6h", "rcon_password": "hunter2" },
+        });
+        redact_json(&mut info);
+        assert_eq!(info["extra_env"]["CF_API_KEY"], "<redacted>");
+        assert_eq!(info["extra_env"]["MOTD"], "hi");
+        assert_eq!(info["managed_backups"]["rcon_password"], "<redacted>");
+        assert_eq!(info["managed_backups"]["interval"], "6h");
+        assert_eq!(info["port"], "25565");
     }
-    Ok(())
-}
 
-fn remove_server(name: &str, force: bool) -> Result<()> {
-    let mut config = load_server_config()?;
-    if let Some(info) = config.servers.get(name) {
-        if !force && !Confirm::new()
-            .with_prompt(format!("Are you sure you want to remove server '{}'? This will delete all data!", name))
-            .interact()?
-        {
-            return Ok(());
+    #[test]
+    fn start_waves_follow_dependencies() {
+        let server = |name: &str, depends_on: &[&str]| {
+            let depends_on = depends_on.iter().map(|d| d.to_string()).collect();
+            (name.to_string(), ServerInfo { depends_on, ..ServerInfo::default() })
+        };
+        let names = |waves: Vec<Vec<(String, ServerInfo)>>| -> Vec<Vec<String>> {
+            waves.into_iter().map(|wave| wave.into_iter().map(|(name, _)| name).collect()).collect()
+        };
+
+        // Dependencies outside the started set (e.g. a disabled server) aren't waited for
+        let waves = start_waves(vec![
+            server("lobby", &["auth", "survival"]),
+            server("auth", &[]),
+            server("survival", &["auth", "proxy"]),
+            server("creative", &[]),
+        ])
+        .unwrap();
+        assert_eq!(names(waves), [vec!["auth", "creative"], vec!["survival"], vec!["lobby"]]);
+        assert!(start_waves(Vec::new()).unwrap().is_empty());
+
+        let cycle = start_waves(vec![server("a", &["b"]), server("b", &["c"]), server("c", &["a"]), server("d", &[])]);
+        match cycle {
+            Err(ServerError::InvalidValue(key, message)) => {
+                assert_eq!(key, "depends_on");
+                assert_eq!(message, "no start order for a, b, c, their dependencies contain a cycle");
+            }
+            _ => panic!("a dependency cycle has no start order"),
         }
+    }
 
-        // Stop the server first
-        stop_servers(Some(name.to_string()))?;
+    #[test]
+    fn imported_properties_are_passed_to_the_image() {
+        let overrides = [("view-distance", "16"), ("motd", "Imported"), ("level-type", "flat"), ("query.port", "25566")];
+        let info = ServerInfo {
+            property_overrides: overrides.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            extra_env: [("MOTD".to_string(), "From env".to_string())].into(),
+            ..ServerInfo::default()
+        };
+        let compose = build_compose_config("lobby", &info);
+        let environment = &compose.services["lobby"].environment;
+        assert!(environment.contains(&"VIEW_DISTANCE=16".to_string()));
+        assert!(environment.contains(&"MOTD=From env".to_string()));
+        assert!(!environment.iter().any(|e| e == "MOTD=Imported" || e.starts_with("LEVEL_TYPE=") || e.starts_with("QUERY")));
+    }
 
-        // Remove the server directory
-        fs::remove_dir_all(&info.data_path)?;
+    #[test]
+    fn tps_reports() {
+        let paper = "§6TPS from last 1m, 5m, 15m: §a19.97, §a20.0, §a20.0";
+        assert_eq!(
+            parse_tps_report(paper).unwrap(),
+            [("1m".to_string(), 19.97), ("5m".to_string(), 20.0), ("15m".to_string(), 20.0)]
+        );
+        // Spigot stars values above 20
+        let spigot = "TPS from last 1m, 5m, 15m: *20.0, 18.5, 17";
+        assert_eq!(
+            parse_tps_report(spigot).unwrap(),
+            [("1m".to_string(), 20.0), ("5m".to_string(), 18.5), ("15m".to_string(), 17.0)]
+        );
+        let spark = "TPS from last 5s, 10s, 1m, 5m, 15m:\n *20.0, 19.9, 19.85, 19.9, 20.0\n\n\
+                     Tick durations (min/med/95%ile/max ms) from last 10s, 1m:\n \
+                     1.2/3.4/10.1/52.0; 0.9/3.6/12.5/80.3\n";
+        let tps = parse_tps_report(spark).unwrap();
+        assert_eq!(tps.len(), 5);
+        assert_eq!(tps[2], ("1m".to_string(), 19.85));
+        assert_eq!(parse_spark_mspt(spark), Some(3.6));
 
-        // Remove from config
-        config.servers.remove(name);
-        save_server_config(&config)?;
+        assert!(parse_tps_report("Unknown command").is_none());
+        assert!(parse_tps_report("TPS from last 1m, 5m, 15m: 20.0, 20.0").is_none());
+        assert!(parse_tps_report("TPS from last 1m, 5m, 15m:").is_none());
+        assert!(parse_spark_mspt("TPS from last 5s:\n20.0").is_none());
+    }
 
-        println!("{}", format!("Server '{}' removed successfully!", name).green());
-    } else {
-        return Err(ServerError::ServerNotFound(name.to_string()));
+    #[test]
+    fn mspt_and_forge_reports() {
+        let paper = "Server tick times (avg/min/max) from last 5s, 10s, 1m:\n◴ 2.1/1.0/5.3, 2.4/1.0/9.9, 3.2/0.8/40.1";
+        assert_eq!(parse_paper_mspt(paper), Some(3.2));
+        assert_eq!(parse_paper_mspt("Unknown command"), None);
+
+        let old_forge = "Dim 0 (overworld): Mean tick time: 0.8 ms. Mean TPS: 20.000\n\
+                         Overall: Mean tick time: 1.25 ms. Mean TPS: 19.5";
+        assert_eq!(parse_forge_tps(old_forge), Some((19.5, 1.25)));
+        let new_forge = "minecraft:overworld: 20.000 TPS (0.9 ms/tick)\nOverall: 20.000 TPS (1.2 ms/tick)";
+        assert_eq!(parse_forge_tps(new_forge), Some((20.0, 1.2)));
+        assert_eq!(parse_forge_tps("minecraft:overworld: 20.000 TPS (0.9 ms/tick)"), None);
+        assert_eq!(parse_forge_tps("Overall: no ticks yet"), None);
+    }
+
+    #[test]
+    fn chunky_progress() {
+        assert_eq!(
+            parse_chunky_progress("[Chunky] Task running for world. Processed: 1200 chunks (12.34%), ETA: 0:10:00"),
+            Some(ChunkyProgress::Running(12.34))
+        );
+        assert_eq!(parse_chunky_progress("[Chunky] No tasks running."), Some(ChunkyProgress::Done));
+        assert_eq!(parse_chunky_progress(""), None);
+        assert_eq!(parse_chunky_progress("Unknown or incomplete command, see below for error"), None);
+        assert_eq!(parse_chunky_progress("Processed: (lots%)"), None);
     }
-    Ok(())
 }
@@ -0,0 +1,247 @@
+//! Minimal reader for the gzip-compressed NBT files the game keeps player data in.
+//!
+//! A file holds one named root compound. Every tag is a type byte, a big-endian u16-prefixed
+//! name and a payload; lists carry their element type and length instead of per-element headers.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+/// Nesting deeper than this is treated as corruption rather than recursed into
+const MAX_DEPTH: usize = 512;
+
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    /// Byte, int and long arrays (chunk and UUID data); their contents are skipped
+    Array,
+}
+
+impl Tag {
+    /// Child of a compound tag
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(v) => Some(v.into()),
+            Tag::Short(v) => Some(v.into()),
+            Tag::Int(v) => Some(v.into()),
+            Tag::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Tag::Float(v) => Some(v.into()),
+            Tag::Double(v) => Some(v),
+            _ => self.as_i64().map(|v| v as f64),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a gzip-compressed NBT file and returns its root compound
+pub fn read_gzip_file(path: &Path) -> io::Result<Tag> {
+    let mut data = Vec::new();
+    GzDecoder::new(BufReader::new(File::open(path)?)).read_to_end(&mut data)?;
+    read_root(&data)
+}
+
+fn read_root(data: &[u8]) -> io::Result<Tag> {
+    let mut reader = data;
+    if read_u8(&mut reader)? != 10 {
+        return Err(invalid_data("the root tag is not a compound".to_string()));
+    }
+    read_string(&mut reader)?;
+    read_payload(&mut reader, 10, 0)
+}
+
+fn read_payload(reader: &mut &[u8], kind: u8, depth: usize) -> io::Result<Tag> {
+    if depth > MAX_DEPTH {
+        return Err(invalid_data("tags are nested too deeply".to_string()));
+    }
+    Ok(match kind {
+        1 => Tag::Byte(read_u8(reader)? as i8),
+        2 => Tag::Short(i16::from_be_bytes(read_array(reader)?)),
+        3 => Tag::Int(i32::from_be_bytes(read_array(reader)?)),
+        4 => Tag::Long(i64::from_be_bytes(read_array(reader)?)),
+        5 => Tag::Float(f32::from_be_bytes(read_array(reader)?)),
+        6 => Tag::Double(f64::from_be_bytes(read_array(reader)?)),
+        7 => {
+            let len = read_length(reader)?;
+            take(reader, len)?;
+            Tag::Array
+        }
+        8 => Tag::String(read_string(reader)?),
+        9 => {
+            let element = read_u8(reader)?;
+            let len = read_length(reader)?;
+            let mut items = Vec::new();
+            for _ in 0..len {
+                items.push(read_payload(reader, element, depth + 1)?);
+            }
+            Tag::List(items)
+        }
+        10 => {
+            let mut entries = Vec::new();
+            loop {
+                let kind = read_u8(reader)?;
+                if kind == 0 {
+                    break;
+                }
+                let key = read_string(reader)?;
+                entries.push((key, read_payload(reader, kind, depth + 1)?));
+            }
+            Tag::Compound(entries)
+        }
+        11 | 12 => {
+            let len = read_length(reader)?;
+            take(reader, len.saturating_mul(if kind == 11 { 4 } else { 8 }))?;
+            Tag::Array
+        }
+        kind => return Err(invalid_data(format!("unknown tag type {}", kind))),
+    })
+}
+
+fn take<'a>(reader: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if reader.len() < len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the file ends inside a tag"));
+    }
+    let (head, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(head)
+}
+
+fn read_array<const N: usize>(reader: &mut &[u8]) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    bytes.copy_from_slice(take(reader, N)?);
+    Ok(bytes)
+}
+
+fn read_u8(reader: &mut &[u8]) -> io::Result<u8> {
+    Ok(read_array::<1>(reader)?[0])
+}
+
+/// Array and list lengths are signed ints; a negative one only shows up in a damaged file
+fn read_length(reader: &mut &[u8]) -> io::Result<usize> {
+    let len = i32::from_be_bytes(read_array(reader)?);
+    usize::try_from(len).map_err(|_| invalid_data(format!("negative length {}", len)))
+}
+
+/// Strings are modified UTF-8, which only differs from UTF-8 for NUL and astral characters
+fn read_string(reader: &mut &[u8]) -> io::Result<String> {
+    let len = u16::from_be_bytes(read_array(reader)?) as usize;
+    Ok(String::from_utf8_lossy(take(reader, len)?).into_owned())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(kind: u8, name: &str, payload: &[u8]) -> Vec<u8> {
+        let mut tag = vec![kind];
+        tag.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        tag.extend_from_slice(name.as_bytes());
+        tag.extend_from_slice(payload);
+        tag
+    }
+
+    fn player() -> Vec<u8> {
+        let mut pos = vec![6];
+        pos.extend_from_slice(&3i32.to_be_bytes());
+        for v in [1.5f64, 64.0, -2.25] {
+            pos.extend_from_slice(&v.to_be_bytes());
+        }
+        let mut uuid = 4i32.to_be_bytes().to_vec();
+        uuid.extend_from_slice(&[0; 16]);
+
+        let mut root = Vec::new();
+        root.extend(named(8, "Dimension", b"\x00\x13minecraft:overworld"));
+        root.extend(named(3, "XpLevel", &30i32.to_be_bytes()));
+        root.extend(named(1, "OnGround", &[1]));
+        root.extend(named(11, "UUID", &uuid));
+        root.extend(named(9, "Pos", &pos));
+        let mut abilities = named(5, "walkSpeed", &0.1f32.to_be_bytes());
+        abilities.push(0);
+        root.extend(named(10, "abilities", &abilities));
+        root.push(0);
+        named(10, "", &root)
+    }
+
+    #[test]
+    fn reads_a_player_file() {
+        let root = read_root(&player()).unwrap();
+        assert_eq!(root.get("Dimension").and_then(Tag::as_str), Some("minecraft:overworld"));
+        assert_eq!(root.get("XpLevel").and_then(Tag::as_i64), Some(30));
+        assert_eq!(root.get("OnGround").and_then(Tag::as_i64), Some(1));
+        assert!(matches!(root.get("UUID"), Some(Tag::Array)));
+        let pos: Vec<f64> = root.get("Pos").and_then(Tag::as_list).unwrap().iter().filter_map(Tag::as_f64).collect();
+        assert_eq!(pos, [1.5, 64.0, -2.25]);
+        let walk_speed = root.get("abilities").and_then(|a| a.get("walkSpeed")).and_then(Tag::as_f64);
+        assert_eq!(walk_speed, Some(0.1f32 as f64));
+        assert!(root.get("Missing").is_none());
+    }
+
+    #[test]
+    fn truncated_files_are_errors() {
+        let data = player();
+        for cut in 0..data.len() {
+            assert!(read_root(&data[..cut]).is_err(), "cut at {}", cut);
+        }
+    }
+
+    #[test]
+    fn damaged_files_are_errors() {
+        assert!(read_root(&named(8, "", b"\x00\x00")).is_err());
+        assert!(read_root(&named(10, "", &named(42, "x", &[]))).is_err());
+
+        // Oversized and negative lengths fail without allocating or reading past the end
+        for kind in [7, 11, 12] {
+            let oversized = named(10, "", &named(kind, "a", &i32::MAX.to_be_bytes()));
+            assert_eq!(read_root(&oversized).err().unwrap().kind(), io::ErrorKind::UnexpectedEof);
+            let negative = named(10, "", &named(kind, "a", &(-1i32).to_be_bytes()));
+            assert_eq!(read_root(&negative).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        }
+        let mut list = vec![10];
+        list.extend_from_slice(&i32::MAX.to_be_bytes());
+        assert!(read_root(&named(10, "", &named(9, "l", &list))).is_err());
+
+        let mut nested = vec![10, 0, 0];
+        for _ in 0..MAX_DEPTH + 1 {
+            nested.extend_from_slice(&[10, 0, 0]);
+        }
+        assert_eq!(read_root(&nested).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
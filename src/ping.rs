@@ -0,0 +1,210 @@
+//! Minimal client for the Minecraft Server List Ping (status) protocol.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// The protocol caps strings at 32767 characters of up to 4 bytes each
+const MAX_RESPONSE_LENGTH: i32 = 32767 * 4;
+
+pub struct ServerStatus {
+    pub version: String,
+    pub protocol: i64,
+    pub motd: String,
+    pub players_online: i64,
+    pub players_max: i64,
+    pub latency: Duration,
+}
+
+pub fn ping(host: &str, port: u16) -> io::Result<ServerStatus> {
+    let addr = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("couldn't resolve {}", host)))?;
+
+    let started = Instant::now();
+    let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    // Handshake with protocol version -1 ("any") and next state 1 (status)
+    let mut handshake = Vec::new();
+    write_varint(&mut handshake, 0x00);
+    write_varint(&mut handshake, -1);
+    write_varint(&mut handshake, host.len() as i32);
+    handshake.extend_from_slice(host.as_bytes());
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    send_packet(&mut stream, &handshake)?;
+
+    // Status request
+    send_packet(&mut stream, &[0x00])?;
+
+    let json = read_status_response(&mut stream)?;
+    let latency = started.elapsed();
+    parse_status(&json, latency)
+}
+
+/// The JSON of a status response packet
+fn read_status_response(stream: &mut impl Read) -> io::Result<Vec<u8>> {
+    let _length = read_varint(stream)?;
+    let packet_id = read_varint(stream)?;
+    if packet_id != 0x00 {
+        return Err(invalid_data(format!("unexpected packet id {}", packet_id)));
+    }
+    let json_length = read_varint(stream)?;
+    if !(0..=MAX_RESPONSE_LENGTH).contains(&json_length) {
+        return Err(invalid_data(format!("invalid response length {}", json_length)));
+    }
+    let mut json = vec![0; json_length as usize];
+    stream.read_exact(&mut json)?;
+    Ok(json)
+}
+
+fn parse_status(json: &[u8], latency: Duration) -> io::Result<ServerStatus> {
+    let status: serde_json::Value =
+        serde_json::from_slice(json).map_err(|e| invalid_data(format!("invalid status JSON: {}", e)))?;
+
+    Ok(ServerStatus {
+        version: status["version"]["name"].as_str().unwrap_or_default().to_string(),
+        protocol: status["version"]["protocol"].as_i64().unwrap_or_default(),
+        motd: chat_to_plain(&status["description"]),
+        players_online: status["players"]["online"].as_i64().unwrap_or_default(),
+        players_max: status["players"]["max"].as_i64().unwrap_or_default(),
+        latency,
+    })
+}
+
+/// Flattens a chat component (plain string or `{"text", "extra"}` object) into plain text
+fn chat_to_plain(component: &serde_json::Value) -> String {
+    match component {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Object(object) => {
+            let mut text = object.get("text").and_then(|t| t.as_str()).unwrap_or_default().to_string();
+            if let Some(serde_json::Value::Array(extra)) = object.get("extra") {
+                for part in extra {
+                    text.push_str(&chat_to_plain(part));
+                }
+            }
+            text
+        }
+        serde_json::Value::Array(parts) => parts.iter().map(chat_to_plain).collect(),
+        _ => String::new(),
+    }
+}
+
+fn send_packet(stream: &mut TcpStream, payload: &[u8]) -> io::Result<()> {
+    let mut packet = Vec::with_capacity(payload.len() + 5);
+    write_varint(&mut packet, payload.len() as i32);
+    packet.extend_from_slice(payload);
+    stream.write_all(&packet)
+}
+
+fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7f == 0 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn read_varint(stream: &mut impl Read) -> io::Result<i32> {
+    let mut result = 0u32;
+    for i in 0..5 {
+        let mut byte = [0u8];
+        stream.read_exact(&mut byte)?;
+        result |= u32::from(byte[0] & 0x7f) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(result as i32);
+        }
+    }
+    Err(invalid_data("varint is too long".to_string()))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(json: &str) -> Vec<u8> {
+        let mut payload = Vec::new();
+        write_varint(&mut payload, 0x00);
+        write_varint(&mut payload, json.len() as i32);
+        payload.extend_from_slice(json.as_bytes());
+        let mut packet = Vec::new();
+        write_varint(&mut packet, payload.len() as i32);
+        packet.extend_from_slice(&payload);
+        packet
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        for value in [0, 1, 127, 128, 25565, 2097151, i32::MAX, -1, i32::MIN] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value);
+            assert!(buf.len() <= 5);
+            assert_eq!(read_varint(&mut &buf[..]).unwrap(), value);
+        }
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 300);
+        assert_eq!(buf, [0xac, 0x02]);
+    }
+
+    #[test]
+    fn bad_varints_are_errors() {
+        assert_eq!(read_varint(&mut &[][..]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(read_varint(&mut &[0x80, 0x80][..]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        let too_long = [0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        assert_eq!(read_varint(&mut &too_long[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn status_response() {
+        let json = r#"{"version":{"name":"1.21.1","protocol":767},"players":{"max":20,"online":3},
+            "description":{"text":"A ","extra":[{"text":"Minecraft"},"Server"]}}"#;
+        let bytes = read_status_response(&mut &response(json)[..]).unwrap();
+        let status = parse_status(&bytes, Duration::from_millis(12)).unwrap();
+        assert_eq!(status.version, "1.21.1");
+        assert_eq!(status.protocol, 767);
+        assert_eq!(status.motd, "A MinecraftServer");
+        assert_eq!((status.players_online, status.players_max), (3, 20));
+
+        // Older servers send the description as a plain string, and fields can be missing
+        let status = parse_status(br#"{"description":"Hello"}"#, Duration::ZERO).unwrap();
+        assert_eq!(status.motd, "Hello");
+        assert_eq!(status.version, "");
+    }
+
+    #[test]
+    fn broken_status_responses_are_errors() {
+        let full = response(r#"{"description":"Hello"}"#);
+        for cut in 0..full.len() {
+            assert!(read_status_response(&mut &full[..cut]).is_err(), "cut at {}", cut);
+        }
+
+        let mut wrong_id = Vec::new();
+        for value in [10, 0x01, 4] {
+            write_varint(&mut wrong_id, value);
+        }
+        assert_eq!(read_status_response(&mut &wrong_id[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // A huge or negative length is rejected before anything is allocated
+        for length in [i32::MAX, MAX_RESPONSE_LENGTH + 1, -1] {
+            let mut packet = Vec::new();
+            for value in [5, 0x00, length] {
+                write_varint(&mut packet, value);
+            }
+            assert_eq!(read_status_response(&mut &packet[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+
+        assert_eq!(parse_status(b"{not json", Duration::ZERO).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,191 @@
+//! Minimal Anvil region file (`.mca`) parser used to spot obvious corruption.
+//!
+//! A region file starts with an 8 KiB header: 1024 big-endian location entries (3 bytes sector
+//! offset, 1 byte sector count) followed by 1024 timestamps. Each chunk starts at its sector with
+//! a 4 byte length and a 1 byte compression type.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const SECTOR_SIZE: usize = 4096;
+const HEADER_SIZE: usize = 2 * SECTOR_SIZE;
+const CHUNKS_PER_REGION: usize = 1024;
+
+pub struct RegionReport {
+    /// Number of chunks the header says are present
+    pub chunks: usize,
+    /// Human readable description of each problem found
+    pub issues: Vec<String>,
+}
+
+pub fn check_region_file(path: &Path) -> io::Result<RegionReport> {
+    let data = fs::read(path)?;
+    Ok(check_region_data(&data))
+}
+
+fn check_region_data(data: &[u8]) -> RegionReport {
+    let mut report = RegionReport {
+        chunks: 0,
+        issues: Vec::new(),
+    };
+
+    // Freshly created regions can be empty, which the game accepts
+    if data.is_empty() {
+        return report;
+    }
+    if data.len() < HEADER_SIZE {
+        report
+            .issues
+            .push(format!("truncated header ({} of {} bytes)", data.len(), HEADER_SIZE));
+        return report;
+    }
+
+    let total_sectors = data.len().div_ceil(SECTOR_SIZE);
+    let mut owner: Vec<Option<usize>> = vec![None; total_sectors];
+
+    for index in 0..CHUNKS_PER_REGION {
+        let entry = &data[index * 4..index * 4 + 4];
+        let offset = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]) as usize;
+        let count = entry[3] as usize;
+        if offset == 0 && count == 0 {
+            continue;
+        }
+        report.chunks += 1;
+        let (x, z) = (index % 32, index / 32);
+
+        if offset < 2 || count == 0 {
+            report
+                .issues
+                .push(format!("chunk ({}, {}) has an invalid location (sector {}, {} sectors)", x, z, offset, count));
+            continue;
+        }
+        if offset + count > total_sectors {
+            report
+                .issues
+                .push(format!("chunk ({}, {}) extends past the end of the file", x, z));
+            continue;
+        }
+
+        if let Some(other) = owner[offset..offset + count].iter().find_map(|o| *o) {
+            report.issues.push(format!(
+                "chunk ({}, {}) overlaps chunk ({}, {})",
+                x,
+                z,
+                other % 32,
+                other / 32
+            ));
+        }
+        owner[offset..offset + count].fill(Some(index));
+
+        let start = offset * SECTOR_SIZE;
+        if start + 5 > data.len() {
+            report.issues.push(format!("chunk ({}, {}) has a truncated header", x, z));
+            continue;
+        }
+        let length = u32::from_be_bytes([data[start], data[start + 1], data[start + 2], data[start + 3]]) as usize;
+        let compression = data[start + 4];
+
+        // Chunks flagged with 128 are stored in an external .mcc file
+        let external = compression & 0x80 != 0;
+        if !matches!(compression & 0x7f, 1..=4 | 127) {
+            report
+                .issues
+                .push(format!("chunk ({}, {}) uses unknown compression type {}", x, z, compression));
+        }
+        if length == 0 {
+            report.issues.push(format!("chunk ({}, {}) has zero length", x, z));
+        } else if !external && (length + 4 > count * SECTOR_SIZE || start + 4 + length > data.len()) {
+            report
+                .issues
+                .push(format!("chunk ({}, {}) data is truncated ({} bytes declared)", x, z, length));
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_location(data: &mut [u8], index: usize, offset: u32, count: u8) {
+        let offset = offset.to_be_bytes();
+        data[index * 4..index * 4 + 4].copy_from_slice(&[offset[1], offset[2], offset[3], count]);
+    }
+
+    fn set_chunk(data: &mut [u8], offset: usize, length: u32, compression: u8) {
+        let start = offset * SECTOR_SIZE;
+        data[start..start + 4].copy_from_slice(&length.to_be_bytes());
+        data[start + 4] = compression;
+    }
+
+    /// A region with healthy chunks (0, 0) and (1, 0) in sectors 2 and 3..5
+    fn region() -> Vec<u8> {
+        let mut data = vec![0; HEADER_SIZE + 3 * SECTOR_SIZE];
+        set_location(&mut data, 0, 2, 1);
+        set_chunk(&mut data, 2, 100, 2);
+        set_location(&mut data, 1, 3, 2);
+        set_chunk(&mut data, 3, 5000, 2);
+        data
+    }
+
+    #[test]
+    fn healthy_regions() {
+        let report = check_region_data(&region());
+        assert_eq!(report.chunks, 2);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+
+        let empty = check_region_data(&[]);
+        assert_eq!(empty.chunks, 0);
+        assert!(empty.issues.is_empty());
+    }
+
+    #[test]
+    fn truncated_regions_are_reported() {
+        let data = region();
+        for cut in [1, 100, HEADER_SIZE - 1] {
+            let report = check_region_data(&data[..cut]);
+            assert_eq!(report.issues.len(), 1);
+            assert!(report.issues[0].starts_with("truncated header"));
+        }
+        // Past that, every cut short of the last chunk's data is reported rather than panicking
+        for cut in HEADER_SIZE..3 * SECTOR_SIZE + 4 + 5000 {
+            let report = check_region_data(&data[..cut]);
+            assert!(!report.issues.is_empty(), "cut at {}", cut);
+        }
+    }
+
+    #[test]
+    fn damaged_chunks_are_reported() {
+        let issues = |data: &[u8]| check_region_data(data).issues;
+
+        let mut data = region();
+        set_location(&mut data, 2, 1, 1);
+        set_location(&mut data, 3, 0xff_ffff, 255);
+        assert_eq!(issues(&data), [
+            "chunk (2, 0) has an invalid location (sector 1, 1 sectors)",
+            "chunk (3, 0) extends past the end of the file"
+        ]);
+
+        let mut data = region();
+        set_location(&mut data, 32, 4, 1);
+        assert!(issues(&data).contains(&"chunk (0, 1) overlaps chunk (1, 0)".to_string()));
+
+        let mut data = region();
+        set_chunk(&mut data, 2, 0, 9);
+        assert_eq!(issues(&data), [
+            "chunk (0, 0) uses unknown compression type 9",
+            "chunk (0, 0) has zero length"
+        ]);
+
+        let mut data = region();
+        set_chunk(&mut data, 2, u32::MAX, 2);
+        assert_eq!(issues(&data), ["chunk (0, 0) data is truncated (4294967295 bytes declared)"]);
+
+        // Chunks stored in an external .mcc file only keep their header in the region
+        let mut data = region();
+        set_chunk(&mut data, 2, u32::MAX, 0x82);
+        assert!(issues(&data).is_empty());
+    }
+}
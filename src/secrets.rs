@@ -0,0 +1,57 @@
+//! Secrets kept in the OS keyring (macOS Keychain, Windows Credential Manager, or a Secret Service
+//! such as GNOME Keyring on Linux) instead of BlockOps' own files.
+//!
+//! A config value holding a secret is replaced by a placeholder naming its keyring account, so
+//! whatever reads the value can find the secret without knowing which server or setting it is.
+
+use std::io;
+
+const SERVICE: &str = "blockops";
+
+pub fn placeholder(account: &str) -> String {
+    format!("<keyring:{}>", account)
+}
+
+/// Keyring account named by a placeholder, `None` for values that are the secret itself
+pub fn account(value: &str) -> Option<&str> {
+    value.strip_prefix("<keyring:")?.strip_suffix('>')
+}
+
+/// Environment variable read instead of the keyring when there is none, e.g. on a headless host.
+/// Compose files also refer to secrets by this name.
+pub fn env_var(account: &str) -> String {
+    let name: String = account
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("BLOCKOPS_SECRET_{}", name)
+}
+
+pub fn store(account: &str, secret: &str) -> io::Result<()> {
+    entry(account)?.set_password(secret).map_err(keyring_error)
+}
+
+/// The secret, or `None` when the keyring has no entry for the account
+pub fn load(account: &str) -> io::Result<Option<String>> {
+    match entry(account)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+/// Removing an entry that doesn't exist is not an error
+pub fn delete(account: &str) -> io::Result<()> {
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+fn entry(account: &str) -> io::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, account).map_err(keyring_error)
+}
+
+fn keyring_error(error: keyring::Error) -> io::Error {
+    io::Error::other(error.to_string())
+}